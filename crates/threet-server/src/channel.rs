@@ -1,20 +1,64 @@
-use std::io::Write;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 use russh::ChannelId;
+use russh::Pty;
 use russh::server::Handle;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::channel;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::task::JoinHandle;
 
+use threet_storage::Database;
+use threet_storage::models::User;
+use threet_tui::AppBuilder;
+use threet_tui::Capabilities;
 use threet_tui::Event;
+use threet_tui::OutputStats;
+use threet_tui::SessionId;
+
+use crate::exec;
+use crate::line::LineSession;
+use crate::output::TextOutput;
+use crate::recording::Recorder;
+use crate::server::SessionOptions;
+use crate::session::SessionRegistry;
+use crate::stdout::ChannelStdout;
+
+/// the environment variable clients set to start the app in low bandwidth mode
+const LOW_BANDWIDTH_ENV: &str = "THREET_LOW_BANDWIDTH";
+
+/// the environment variable clients set to ask for their session to be recorded
+const RECORD_ENV: &str = "THREET_RECORD";
+
+/// how many server events can wait for a line session
+const LINE_EVENTS_CAPACITY: usize = 8;
 
 enum ChannelState {
     NotReady,
-    Ready { app_tx: Sender<Event> },
+    Ready {
+        app_tx: Sender<Event>,
+        app_task: JoinHandle<()>,
+    },
+    /// running a single non interactive command, the stdin sender
+    /// is dropped once the client sends EOF
+    Exec {
+        stdin: Option<UnboundedSender<Vec<u8>>>,
+        task: JoinHandle<()>,
+    },
+    /// running a plain line based session for clients
+    /// that can't run the interactive app
+    Line {
+        stdin: UnboundedSender<Vec<u8>>,
+        task: JoinHandle<()>,
+    },
 }
 
 impl ChannelState {
     #[inline]
-    fn is_ready(&self) -> bool {
-        matches!(self, ChannelState::Ready { .. })
+    fn is_running(&self) -> bool {
+        !matches!(self, ChannelState::NotReady)
     }
 }
 
@@ -22,83 +66,312 @@ pub struct ClientChannel {
     id: ChannelId,
     session_handle: Handle,
     state: ChannelState,
+    session: SessionId,
+    /// the user the client authenticated as on the ssh layer
+    user: Option<User>,
+    database: Database,
+    sessions: Arc<SessionRegistry>,
+    /// the pty the client requested, the app is started only on the
+    /// shell request because clients send their environment after the pty
+    pty: Option<PtyRequest>,
+    env: HashMap<String, String>,
+    options: Arc<SessionOptions>,
+    /// the session recording, only if recording is enabled
+    recorder: Option<Arc<Recorder>>,
+}
+
+/// the terminal the client requested for the channel
+struct PtyRequest {
+    term: String,
+    size: (u16, u16),
+    /// the `IUTF8` terminal mode, if the client sent it
+    utf8: Option<bool>,
 }
 
 impl ClientChannel {
-    pub fn new(id: ChannelId, session_handle: Handle) -> ClientChannel {
+    pub fn new(
+        id: ChannelId,
+        session_handle: Handle,
+        session: SessionId,
+        user: Option<User>,
+        database: Database,
+        sessions: Arc<SessionRegistry>,
+        options: Arc<SessionOptions>,
+    ) -> ClientChannel {
         ClientChannel {
             id,
             session_handle,
             state: ChannelState::NotReady,
+            session,
+            user,
+            database,
+            sessions,
+            pty: None,
+            env: HashMap::new(),
+            options,
+            recorder: None,
         }
     }
 
-    pub async fn pty_request(&mut self, size: (u16, u16)) -> anyhow::Result<()> {
-        if self.state.is_ready() {
-            anyhow::bail!("channel has already an app instance running");
+    /// stores an environment variable the client sent
+    pub fn env(&mut self, name: &str, value: &str) {
+        self.env.insert(name.to_string(), value.to_string());
+    }
+
+    /// returns true if the client sent the given environment variable
+    /// with a value that is not empty, `0` or `false`
+    fn env_flag(&self, name: &str) -> bool {
+        self.env
+            .get(name)
+            .is_some_and(|value| !matches!(value.as_str(), "" | "0" | "false"))
+    }
+
+    /// starts the interactive app on the requested pty, clients without a pty
+    /// or with a dumb terminal get the line based session instead
+    pub async fn shell_request(&mut self) -> anyhow::Result<()> {
+        if self.state.is_running() {
+            anyhow::bail!("channel is already running");
+        }
+
+        match self.pty.take() {
+            Some(pty) if pty.term != "dumb" => self.start_app(pty),
+            pty => self.start_line(pty.is_some()),
         }
-        let stdout = ChannelStdout {
-            buffer: Vec::with_capacity(size.0 as usize * size.1 as usize),
-            session_handle: self.session_handle.clone(),
-            channel_id: self.id.clone(),
+        Ok(())
+    }
+
+    fn start_line(&mut self, pty: bool) {
+        // like the commands stdin, the piped lines must never be dropped
+        let (stdin_tx, stdin_rx) = unbounded_channel();
+        // the server wide events, like the shutdown notice
+        let (events_tx, events_rx) = channel(LINE_EVENTS_CAPACITY);
+        self.sessions.attach(self.session, self.id, events_tx);
+        let output = TextOutput::new(self.session_handle.clone(), self.id);
+        let line = LineSession::new(
+            self.database.clone(),
+            output,
+            pty,
+            self.session,
+            self.sessions.clone(),
+            self.user.clone(),
+        );
+        let task = tokio::spawn(line.run(stdin_rx, events_rx));
+
+        self.state = ChannelState::Line {
+            stdin: stdin_tx,
+            task,
         };
-        let (app, app_tx) = threet_tui::App::new(stdout, size);
+    }
+
+    fn start_app(&mut self, pty: PtyRequest) {
+        let capabilities = Capabilities::detect(&pty.term, &self.env, pty.utf8);
+        log::info!(
+            "session {} terminal `{}` detected as {:?}",
+            self.session,
+            pty.term,
+            capabilities
+        );
+
+        // clients on slow links can ask for the low bandwidth
+        // mode with `SendEnv THREET_LOW_BANDWIDTH`
+        let low_bandwidth = self.env_flag(LOW_BANDWIDTH_ENV);
+
+        if let Some(dir) = self.options.record_dir.as_ref()
+            && (self.options.record_all || self.env_flag(RECORD_ENV))
+        {
+            let recorder = Recorder::create(dir, self.session, &pty.term, pty.size);
+            self.recorder = Some(Arc::new(recorder));
+        }
 
-        tokio::spawn(async move {
-            app.run().await.unwrap();
+        let stats = Arc::new(OutputStats::default());
+        let (stdout, writer) = ChannelStdout::new(
+            self.session_handle.clone(),
+            self.id,
+            pty.size.0 as usize * pty.size.1 as usize,
+            stats.clone(),
+            self.recorder.clone(),
+        );
+        let mut builder = AppBuilder::default();
+        if let Some(user) = self.user.clone() {
+            builder = builder.user(user);
+        }
+        let (app, app_tx) = builder
+            .size(pty.size)
+            .database(self.database.clone())
+            .session(self.session, self.sessions.clone())
+            .capabilities(capabilities)
+            .output_stats(stats)
+            .max_fps(self.options.max_fps)
+            .low_bandwidth(low_bandwidth)
+            .build(stdout);
+        self.sessions.attach(self.session, self.id, app_tx.clone());
+
+        let app_task = tokio::spawn({
+            let handle = self.session_handle.clone();
+            let channel_id = self.id;
+
+            async move {
+                let exit_status = app.run().await.unwrap_or_else(|err| {
+                    log::error!("app running on channel {:?} failed, {:?}", channel_id, err);
+                    1
+                });
+
+                // the app is dropped by now, wait for its last frames
+                // to be sent before the channel is closed
+                let _ = writer.await;
+
+                // the app quit on its own, report the exit status to the
+                // client and close the channel so the client can exit too
+                let _ = handle.exit_status_request(channel_id, exit_status).await;
+                let _ = handle.eof(channel_id).await;
+                let _ = handle.close(channel_id).await;
+            }
         });
 
-        self.state = ChannelState::Ready { app_tx };
-        Ok(())
+        self.state = ChannelState::Ready { app_tx, app_task };
     }
 
-    pub async fn resize(&mut self, dem: (u16, u16)) -> anyhow::Result<()> {
-        let ChannelState::Ready { ref app_tx } = self.state else {
-            anyhow::bail!("no application was created for this channel, request a pty")
+    /// runs a single command for the authenticated user instead of the
+    /// interactive app, the channel is closed once the command is done
+    pub async fn exec(&mut self, command: &[u8]) -> anyhow::Result<()> {
+        if self.state.is_running() {
+            anyhow::bail!("channel is already running");
+        }
+        let Some(user) = self.user.clone() else {
+            anyhow::bail!("commands require an authenticated user");
+        };
+
+        let line = String::from_utf8(command.to_vec())?;
+        // the stdin is unbounded so the data handler never waits on the
+        // command, and no input is lost while the command is busy
+        let (stdin_tx, stdin_rx) = unbounded_channel();
+        let output = TextOutput::new(self.session_handle.clone(), self.id);
+        let task = tokio::spawn(exec::run(
+            self.database.clone(),
+            user,
+            line,
+            stdin_rx,
+            output,
+        ));
+
+        self.state = ChannelState::Exec {
+            stdin: Some(stdin_tx),
+            task,
         };
-        app_tx.send(Event::Resize(dem)).await.unwrap();
         Ok(())
     }
 
-    pub async fn data(&mut self, data: &[u8]) -> anyhow::Result<()> {
-        let ChannelState::Ready { ref app_tx } = self.state else {
-            anyhow::bail!("no application was created for this channel, request a pty")
-        };
-        let event = Event::Stdin(data.to_vec());
-        app_tx.send(event).await.unwrap();
+    /// called when the client won't send any more data, returns a boolean value
+    /// indicating if the channel should be closed right away
+    pub fn eof(&mut self) -> bool {
+        match self.state {
+            // the command may still be writing its output, it
+            // will close the channel once it is done
+            ChannelState::Exec { ref mut stdin, .. } => {
+                stdin.take();
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// stores the requested terminal, the terminal is used once
+    /// the client requests a shell
+    pub async fn pty_request(
+        &mut self,
+        term: &str,
+        size: (u16, u16),
+        modes: &[(Pty, u32)],
+    ) -> anyhow::Result<()> {
+        if self.state.is_running() {
+            anyhow::bail!("channel has already an app instance running");
+        }
+
+        let utf8 = modes
+            .iter()
+            .find(|(mode, _)| *mode == Pty::IUTF8)
+            .map(|(_, value)| *value != 0);
+        self.sessions.set_size(self.session, size);
+        self.pty = Some(PtyRequest {
+            term: term.to_string(),
+            size,
+            utf8,
+        });
         Ok(())
     }
-}
 
-pub struct ChannelStdout {
-    buffer: Vec<u8>,
-    channel_id: ChannelId,
-    session_handle: Handle,
-}
+    pub async fn resize(&mut self, dem: (u16, u16)) -> anyhow::Result<()> {
+        self.sessions.set_size(self.session, dem);
+
+        // the client may resize before it requested a shell
+        if let Some(pty) = self.pty.as_mut() {
+            pty.size = dem;
+            return Ok(());
+        }
 
-impl Write for ChannelStdout {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.buffer.extend_from_slice(buf);
-        Ok(buf.len())
+        if let Some(recorder) = self.recorder.as_ref() {
+            recorder.resize(dem);
+        }
+
+        match self.state {
+            ChannelState::Ready { ref app_tx, .. } => app_tx
+                .send(Event::Resize(dem))
+                .await
+                .map_err(|_| anyhow::anyhow!("the app running on the channel has stopped"))?,
+            // the line session doesn't care about the terminal size
+            ChannelState::Line { .. } => {}
+            _ => anyhow::bail!("no application was created for this channel, request a pty"),
+        }
+        Ok(())
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        tokio::spawn({
-            let buffer = self.buffer.clone();
-            let handler = self.session_handle.clone();
-            let channel_id = self.channel_id.clone();
+    pub async fn data(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.sessions.touch(self.session);
 
-            async move {
-                let _ = handler
-                    .data(channel_id, buffer.into())
+        match self.state {
+            ChannelState::Ready { ref app_tx, .. } => {
+                // the app starts on the channels list for authenticated
+                // users, nothing typed into it is a password
+                if self.options.record_input
+                    && self.user.is_some()
+                    && let Some(recorder) = self.recorder.as_ref()
+                {
+                    recorder.input(data);
+                }
+
+                let event = Event::Stdin(data.to_vec());
+                app_tx
+                    .send(event)
                     .await
-                    .inspect_err(|err| {
-                        log::warn!("problem sending stdout data to remote client, {:?}", err)
-                    });
+                    .map_err(|_| anyhow::anyhow!("the app running on the channel has stopped"))?;
             }
-        });
-
-        self.buffer.clear();
+            // the data handler must not wait for the command, waiting
+            // would stop the whole connection, not just this channel
+            ChannelState::Exec { ref stdin, .. } => {
+                // the command may be done without reading its stdin
+                if let Some(sender) = stdin.as_ref() {
+                    let _ = sender.send(data.to_vec());
+                }
+            }
+            ChannelState::Line { ref stdin, .. } => {
+                let _ = stdin.send(data.to_vec());
+            }
+            _ => anyhow::bail!("no application was created for this channel, request a pty"),
+        }
         Ok(())
     }
 }
+
+impl Drop for ClientChannel {
+    fn drop(&mut self) {
+        // the app holds nothing that outlives the channel, stopping it drops
+        // the app and with it all of its background tasks
+        match self.state {
+            ChannelState::Ready { ref app_task, .. } => app_task.abort(),
+            ChannelState::Exec { ref task, .. } | ChannelState::Line { ref task, .. } => {
+                task.abort()
+            }
+            ChannelState::NotReady => {}
+        }
+    }
+}
@@ -1,13 +1,25 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use russh::Channel;
 use russh::ChannelId;
+use russh::MethodKind;
+use russh::MethodSet;
+use russh::keys::ssh_key::PublicKey;
 use russh::server::Auth;
 use russh::server::Handler;
 use russh::server::Msg;
 use russh::server::Session;
 
+use threet_storage::Database;
+use threet_storage::StorageError;
+use threet_storage::models::User;
+use threet_tui::SessionDirectory;
+use threet_tui::SessionId;
+
 use crate::channel::ClientChannel;
+use crate::server::SessionOptions;
+use crate::session::SessionRegistry;
 
 macro_rules! channel_mut {
     ($maybe_channel: expr) => {
@@ -22,21 +34,50 @@ macro_rules! channel_op_state {
     ($action: expr, $session: expr, $channel_id: expr) => {
         match $action {
             Ok(_) => $session.channel_success($channel_id)?,
-            Err(_) => $session.channel_failure($channel_id)?,
+            Err(err) => {
+                log::warn!("channel {:?} request failed, {:?}", $channel_id, err);
+                $session.channel_failure($channel_id)?
+            }
         }
     };
 }
 
 pub struct Client {
     peer: SocketAddr,
+    /// the user the client authenticated as, its password or
+    /// key was checked against the storage by the ssh layer
+    user: Option<User>,
     channel: Option<ClientChannel>,
+    database: Database,
+    sessions: Arc<SessionRegistry>,
+    session: Option<SessionId>,
+    options: Arc<SessionOptions>,
 }
 
 impl Client {
-    pub fn new(peer: SocketAddr) -> Self {
+    pub fn new(
+        peer: SocketAddr,
+        database: Database,
+        sessions: Arc<SessionRegistry>,
+        options: Arc<SessionOptions>,
+    ) -> Self {
         Client {
             peer,
+            user: None,
             channel: None,
+            database,
+            sessions,
+            session: None,
+            options,
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        log::info!("client {} disconnected", self.peer);
+        if let Some(id) = self.session {
+            self.sessions.unregister(id);
         }
     }
 }
@@ -53,25 +94,102 @@ impl Handler for Client {
             anyhow::bail!("only 1 channel per user is allowed");
         }
 
-        let channel = ClientChannel::new(channel.id(), session.handle());
+        let id = self.sessions.register(self.peer, session.handle());
+        if let Some(user) = self.user.as_ref() {
+            self.sessions.identify(id, user.username());
+        }
+        let channel = ClientChannel::new(
+            channel.id(),
+            session.handle(),
+            id,
+            self.user.clone(),
+            self.database.clone(),
+            self.sessions.clone(),
+            self.options.clone(),
+        );
+        self.session = Some(id);
         self.channel = Some(channel);
         Ok(true)
     }
 
+    async fn channel_eof(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> anyhow::Result<()> {
+        // the client won't send any more input, if there is nothing
+        // left to do on this channel it is closed
+        if channel_mut!(self.channel).eof() {
+            session.close(channel)?;
+        }
+        Ok(())
+    }
+
+    async fn exec_request(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> anyhow::Result<()> {
+        channel_op_state!(
+            channel_mut!(self.channel).exec(data).await,
+            session,
+            channel
+        );
+        Ok(())
+    }
+
+    async fn channel_close(&mut self, channel: ChannelId, _: &mut Session) -> anyhow::Result<()> {
+        // dropping the channel stops the app running on it
+        if self.channel.take().is_some() {
+            log::info!("channel {:?} closed by {}", channel, self.peer);
+        }
+        // the next channel of the connection registers a new session
+        if let Some(id) = self.session.take() {
+            self.sessions.unregister(id);
+        }
+        Ok(())
+    }
+
+    async fn env_request(
+        &mut self,
+        channel: ChannelId,
+        variable_name: &str,
+        variable_value: &str,
+        session: &mut Session,
+    ) -> anyhow::Result<()> {
+        channel_mut!(self.channel).env(variable_name, variable_value);
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> anyhow::Result<()> {
+        channel_op_state!(
+            channel_mut!(self.channel).shell_request().await,
+            session,
+            channel
+        );
+        Ok(())
+    }
+
     async fn pty_request(
         &mut self,
         channel: ChannelId,
-        _: &str,
+        term: &str,
         col_width: u32,
         row_height: u32,
         _: u32,
         _: u32,
-        _: &[(russh::Pty, u32)],
+        modes: &[(russh::Pty, u32)],
         session: &mut Session,
     ) -> anyhow::Result<()> {
         channel_op_state!(
             channel_mut!(self.channel)
-                .pty_request((col_width as u16, row_height as u16))
+                .pty_request(term, (col_width as u16, row_height as u16), modes)
                 .await,
             session,
             channel
@@ -112,19 +230,73 @@ impl Handler for Client {
         Ok(())
     }
 
-    async fn auth_publickey(
+    /// only the keys the user added are offered to be signed, the
+    /// others are rejected before the client signs anything
+    async fn auth_publickey_offered(
         &mut self,
-        _: &str,
-        _: &russh::keys::ssh_key::PublicKey,
+        user: &str,
+        key: &PublicKey,
     ) -> Result<Auth, Self::Error> {
-        Ok(Auth::Accept)
+        let key = authorized_key(key)?;
+        match User::by_username_key(self.database.clone(), user, &key).await {
+            Ok(_) => Ok(Auth::Accept),
+            Err(StorageError::NotFound) => Ok(credentials_required()),
+            Err(err) => Err(err.into()),
+        }
     }
 
-    async fn auth_password(&mut self, _: &str, _: &str) -> anyhow::Result<Auth> {
-        Ok(Auth::Accept)
+    /// the keys are the ones the users added with the `keys add`
+    /// command, so scripts can run commands without a password
+    async fn auth_publickey(&mut self, user: &str, key: &PublicKey) -> Result<Auth, Self::Error> {
+        let key = authorized_key(key)?;
+        match User::by_username_key(self.database.clone(), user, &key).await {
+            Ok(user) => {
+                log::info!(
+                    "client {} authenticated as {} with a key",
+                    self.peer,
+                    user.username()
+                );
+                self.user = Some(user);
+                Ok(Auth::Accept)
+            }
+            Err(StorageError::NotFound) => Ok(credentials_required()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// the credentials are the ones the users log in to the app with, the
+    /// sessions start as the authenticated user
+    async fn auth_password(&mut self, user: &str, password: &str) -> anyhow::Result<Auth> {
+        match User::by_username_password(self.database.clone(), user, password).await {
+            Ok(user) => {
+                log::info!("client {} authenticated as {}", self.peer, user.username());
+                self.user = Some(user);
+                Ok(Auth::Accept)
+            }
+            Err(StorageError::NotFound) => Ok(credentials_required()),
+            Err(err) => Err(err.into()),
+        }
     }
 
     async fn auth_none(&mut self, _: &str) -> anyhow::Result<Auth> {
-        Ok(Auth::Accept)
+        Ok(credentials_required())
     }
 }
+
+/// rejects the authentication attempt, asking the client for a key or a password
+fn credentials_required() -> Auth {
+    Auth::Reject {
+        proceed_with_methods: Some(MethodSet::from(
+            &[MethodKind::PublicKey, MethodKind::Password][..],
+        )),
+        partial_success: false,
+    }
+}
+
+/// returns the key as it is stored, in the openssh format without
+/// the comment so a key matches whatever comment the client sent
+pub(crate) fn authorized_key(key: &PublicKey) -> anyhow::Result<String> {
+    let mut key = key.clone();
+    key.set_comment("");
+    Ok(key.to_openssh()?)
+}
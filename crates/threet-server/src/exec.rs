@@ -0,0 +1,378 @@
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use russh::keys::ssh_key::PublicKey;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use threet_storage::Database;
+use threet_storage::StorageError;
+use threet_storage::StorageEvent;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+use threet_storage::models::User;
+
+use crate::client::authorized_key;
+use crate::output::TextOutput;
+use crate::output::format_message;
+
+/// how many messages `tail` prints before following the channel
+const TAIL_BACKLOG: usize = 10;
+
+/// default amount of messages returned by `history`
+const HISTORY_LIMIT: usize = 100;
+
+const USAGE: &str = "\
+usage: ssh <host> <command> [args...]
+
+commands:
+    post <channel> [message...]    post a message, reads the message from stdin if not given
+    tail <channel>                 print the last messages and follow new ones
+    history <channel> [options]    print the channel history
+        --since <duration>         only messages newer than the duration, e.g 30m, 1h, 2d
+        --limit <n>                max amount of messages (default 100)
+        --json                     print each message as a json object
+    channels                       list all the channels
+    keys                           list your ssh public keys
+    keys add [key]                 add a ssh public key to log in with, reads the key from stdin if not given
+    keys remove <key>              remove a ssh public key
+    help                           print this message
+";
+
+/// the commands available for non interactive sessions
+#[derive(Debug)]
+enum Command {
+    Post {
+        channel: String,
+        body: Option<String>,
+    },
+    Tail {
+        channel: String,
+    },
+    History {
+        channel: String,
+        since: Option<Duration>,
+        limit: usize,
+        json: bool,
+    },
+    Channels,
+    Keys,
+    KeyAdd {
+        key: Option<String>,
+    },
+    KeyRemove {
+        key: String,
+    },
+    Help,
+}
+
+impl Command {
+    fn parse(line: &str) -> anyhow::Result<Command> {
+        let args = split_args(line);
+        let mut args = args.into_iter();
+
+        let command = match args.next().as_deref() {
+            Some("post") => {
+                let channel = args.next().ok_or(anyhow::anyhow!("missing channel name"))?;
+                let body = args.collect::<Vec<_>>().join(" ");
+                Command::Post {
+                    channel,
+                    body: (!body.is_empty()).then_some(body),
+                }
+            }
+            Some("tail") => Command::Tail {
+                channel: args.next().ok_or(anyhow::anyhow!("missing channel name"))?,
+            },
+            Some("history") => {
+                let channel = args.next().ok_or(anyhow::anyhow!("missing channel name"))?;
+                let mut since = None;
+                let mut limit = HISTORY_LIMIT;
+                let mut json = false;
+
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--since" => {
+                            let value = args
+                                .next()
+                                .ok_or(anyhow::anyhow!("--since requires a value"))?;
+                            since = Some(parse_duration(&value)?);
+                        }
+                        "--limit" => {
+                            let value = args
+                                .next()
+                                .ok_or(anyhow::anyhow!("--limit requires a value"))?;
+                            limit = value.parse()?;
+                        }
+                        "--json" => json = true,
+                        other => anyhow::bail!("unknown option `{}`", other),
+                    }
+                }
+                Command::History {
+                    channel,
+                    since,
+                    limit,
+                    json,
+                }
+            }
+            Some("channels") => Command::Channels,
+            Some("keys") => match args.next().as_deref() {
+                None => Command::Keys,
+                Some("add") => {
+                    let key = args.collect::<Vec<_>>().join(" ");
+                    Command::KeyAdd {
+                        key: (!key.is_empty()).then_some(key),
+                    }
+                }
+                Some("remove") => {
+                    let key = args.collect::<Vec<_>>().join(" ");
+                    if key.is_empty() {
+                        anyhow::bail!("missing key");
+                    }
+                    Command::KeyRemove { key }
+                }
+                Some(other) => anyhow::bail!("unknown keys command `{}`", other),
+            },
+            Some("help") | None => Command::Help,
+            Some(other) => anyhow::bail!("unknown command `{}`", other),
+        };
+        Ok(command)
+    }
+}
+
+/// splits the given command line into arguments, the command line is
+/// what the ssh client sent which is the arguments joined by spaces, quotes
+/// are respected in case the user quoted them for the remote side
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_arg = false;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// parses a public key in the openssh format, like a line
+/// of `~/.ssh/id_ed25519.pub`, to the stored key format
+fn parse_key(line: &str) -> anyhow::Result<String> {
+    let key = PublicKey::from_openssh(line.trim())
+        .map_err(|err| anyhow::anyhow!("invalid public key, {}", err))?;
+    authorized_key(&key)
+}
+
+/// reads the client stdin until it sends EOF
+async fn read_stdin(stdin: &mut UnboundedReceiver<Vec<u8>>) -> anyhow::Result<String> {
+    let mut buffer = Vec::new();
+    while let Some(data) = stdin.recv().await {
+        buffer.extend(data);
+    }
+    Ok(String::from_utf8(buffer)?)
+}
+
+/// parses a short duration like `45s`, `30m`, `1h` or `2d`
+fn parse_duration(value: &str) -> anyhow::Result<Duration> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid duration `{}`", value))?;
+
+    let secs = match unit {
+        "s" | "" => amount,
+        "m" => amount * 60,
+        "h" => amount * 60 * 60,
+        "d" => amount * 60 * 60 * 24,
+        _ => anyhow::bail!("invalid duration unit `{}`, expected s, m, h or d", unit),
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+fn format_message_json(message: &Message, channel: &Channel) -> String {
+    let object = serde_json::json!({
+        "id": message.id(),
+        "channel": channel.name(),
+        "author": message.author(),
+        "body": message.body(),
+        "created_at": message.created_at(),
+        "edited_at": message.edited_at(),
+        "deleted": message.is_deleted(),
+        "reactions": message
+            .reactions()
+            .iter()
+            .map(|reaction| {
+                serde_json::json!({
+                    "emoji": reaction.emoji(),
+                    "count": reaction.count(),
+                })
+            })
+            .collect::<Vec<_>>(),
+    });
+    format!("{}\n", object)
+}
+
+/// runs the given command line for the given ssh user, the client stdin is
+/// received via the `stdin` channel which is closed once the client sent EOF
+pub async fn run(
+    db: Database,
+    user: User,
+    line: String,
+    stdin: UnboundedReceiver<Vec<u8>>,
+    output: TextOutput,
+) {
+    log::info!("{} executing `{}`", user.username(), line);
+
+    let exit_status = match execute(db, &user, &line, stdin, &output).await {
+        Ok(_) => 0,
+        Err(err) => {
+            let _ = output.stderr(&format!("error: {}\n", err)).await;
+            1
+        }
+    };
+    output.exit(exit_status).await;
+}
+
+async fn execute(
+    db: Database,
+    user: &User,
+    line: &str,
+    mut stdin: UnboundedReceiver<Vec<u8>>,
+    output: &TextOutput,
+) -> anyhow::Result<()> {
+    let command = Command::parse(line)?;
+
+    let channel_by_name = |name: String| {
+        let db = db.clone();
+        async move {
+            match Channel::by_name(db, &name).await {
+                Err(StorageError::NotFound) => Err(anyhow::anyhow!("unknown channel `{}`", name)),
+                channel => Ok(channel?),
+            }
+        }
+    };
+
+    match command {
+        Command::Post { channel, body } => {
+            let channel = channel_by_name(channel).await?;
+            let body = match body {
+                Some(body) => body,
+                None => read_stdin(&mut stdin).await?.trim_end().to_string(),
+            };
+
+            if body.is_empty() {
+                anyhow::bail!("cannot post an empty message");
+            }
+            Message::create(db, &channel, user, &body).await?;
+        }
+        Command::Tail { channel } => {
+            let channel = channel_by_name(channel).await?;
+
+            // subscribe before reading the backlog so no message
+            // is missed between the two
+            let mut events = db.subscribe();
+            for message in Message::history(db.clone(), channel.id(), 0, TAIL_BACKLOG).await? {
+                output
+                    .stdout(&format!("{}\n", format_message(&message)))
+                    .await?;
+            }
+
+            loop {
+                match events.recv().await {
+                    // edits and deletions are printed again, the
+                    // output is a log of the channel changes
+                    Ok(
+                        StorageEvent::MessageCreated(message)
+                        | StorageEvent::MessageEdited(message)
+                        | StorageEvent::MessageDeleted(message),
+                    ) if message.channel_id() == channel.id() => {
+                        output
+                            .stdout(&format!("{}\n", format_message(&message)))
+                            .await?;
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        output
+                            .stderr(&format!("warning: missed {} messages\n", missed))
+                            .await?;
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+        Command::History {
+            channel,
+            since,
+            limit,
+            json,
+        } => {
+            let channel = channel_by_name(channel).await?;
+            let since = since
+                .and_then(|since| SystemTime::now().checked_sub(since))
+                .and_then(|since| since.duration_since(UNIX_EPOCH).ok())
+                .map(|since| since.as_secs() as i64)
+                .unwrap_or_default();
+
+            for message in Message::history(db, channel.id(), since, limit).await? {
+                let line = if json {
+                    format_message_json(&message, &channel)
+                } else {
+                    format!("{}\n", format_message(&message))
+                };
+                output.stdout(&line).await?;
+            }
+        }
+        Command::Channels => {
+            for channel in Channel::all(db).await? {
+                output.stdout(&format!("{}\n", channel.name())).await?;
+            }
+        }
+        Command::Keys => {
+            for key in user.keys(db).await? {
+                output.stdout(&format!("{}\n", key)).await?;
+            }
+        }
+        Command::KeyAdd { key } => {
+            let key = match key {
+                Some(key) => key,
+                None => read_stdin(&mut stdin).await?,
+            };
+            match user.add_key(db, &parse_key(&key)?).await {
+                Err(StorageError::ConstraintViolation(_)) => {
+                    anyhow::bail!("the key was already added")
+                }
+                result => result?,
+            }
+        }
+        Command::KeyRemove { key } => match user.remove_key(db, &parse_key(&key)?).await {
+            Err(StorageError::NotFound) => anyhow::bail!("the key was never added"),
+            result => result?,
+        },
+        Command::Help => output.stdout(USAGE).await?,
+    }
+    Ok(())
+}
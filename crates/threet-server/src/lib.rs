@@ -1,18 +1,31 @@
 use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use rand::rngs::OsRng;
 use russh::keys::PrivateKey;
 use russh::server::Config;
 use russh::server::Server as _;
+use tokio::signal::unix::SignalKind;
+use tokio::signal::unix::signal;
 
 use threet_storage::DatabaseBuilder;
-use threet_storage::set_database;
+use threet_tui::Event;
+use threet_tui::Notification;
 
 mod channel;
 mod client;
+mod exec;
+mod line;
+mod output;
+mod recording;
 mod server;
+mod session;
+mod stdout;
+
+pub use recording::replay;
+pub use server::SessionOptions;
 
 /// loads the ssh server private keys from the given path, if coudln't
 /// find a private file at the given path, will create one and save
@@ -32,18 +45,29 @@ where
     Ok(key)
 }
 
+/// resolves when the process is asked to terminate, either
+/// by `SIGTERM` or by ctrl-c
+async fn shutdown_signal() -> anyhow::Result<()> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result?,
+        _ = terminate.recv() => {}
+    };
+    Ok(())
+}
+
 pub async fn main(
     addr: SocketAddr,
     database_path: impl AsRef<Path>,
     threads: usize,
+    shutdown_grace: Duration,
+    options: SessionOptions,
 ) -> anyhow::Result<()> {
     let database = DatabaseBuilder::default()
         .num_connections(threads)
         .path(database_path)
         .build()
-        .await;
-
-    set_database(database);
+        .await?;
 
     let private_key = load_server_private_key("./key.pem")?;
     let config = Arc::new(Config {
@@ -51,6 +75,42 @@ pub async fn main(
         ..Config::default()
     });
 
-    server::Server::new().run_on_address(config, addr).await?;
+    let mut server = server::Server::new(database.clone(), options);
+    let sessions = server.sessions();
+
+    // dropping the server future stops accepting new connections, the
+    // already connected sessions run on their own tasks and are not effected
+    tokio::select! {
+        result = server.run_on_address(config, addr) => result?,
+        result = shutdown_signal() => result?,
+    };
+
+    log::info!(
+        "shutting down, waiting {}s for connected sessions",
+        shutdown_grace.as_secs()
+    );
+
+    let notified = sessions.broadcast(|| {
+        let notification = Notification::warning(
+            "server shutdown".to_string(),
+            format!(
+                "the server is shutting down in {} seconds",
+                shutdown_grace.as_secs()
+            ),
+        );
+        Event::Notification((notification, shutdown_grace))
+    });
+    log::info!("notified {} sessions about the shutdown", notified);
+
+    // the sessions that quit on their own are unregistered, once
+    // they are all gone there is nothing left to wait for
+    if tokio::time::timeout(shutdown_grace, sessions.wait_empty())
+        .await
+        .is_ok()
+    {
+        log::info!("all the sessions disconnected before the shutdown");
+    }
+    sessions.close_all(0, "server shutdown").await;
+    database.close().await;
     Ok(())
 }
@@ -0,0 +1,309 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use threet_storage::Database;
+use threet_storage::StorageError;
+use threet_storage::StorageEvent;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+use threet_storage::models::User;
+use threet_tui::Event;
+use threet_tui::SessionDirectory;
+use threet_tui::SessionId;
+
+use crate::output::TextOutput;
+use crate::output::format_message;
+use crate::session::SessionRegistry;
+
+/// max length of a single input line, longer lines are cut
+const MAX_LINE_LENGTH: usize = 4096;
+
+const HELP: &str = "\
+commands:
+    /login <username> <password>    authenticate
+    /channels                       list all the channels
+    /join <channel>                 switch to the given channel
+    /who                            list who is online
+    /quit                           close the session
+    /help                           print this message
+any other line is sent as a message to the current channel";
+
+/// a plain line based session, used when the client has no pty or when its
+/// terminal can't display the interactive app, every line the user types is
+/// either a command or a message to the current channel
+pub struct LineSession {
+    db: Database,
+    output: TextOutput,
+    /// the client terminal is in raw mode when it requested a pty,
+    /// in that case the typed characters must be echoed back
+    echo: bool,
+    newline: &'static str,
+    buffer: String,
+    /// the first bytes of a character the client didn't finish sending
+    partial: Vec<u8>,
+    user: Option<User>,
+    channel: Option<Channel>,
+    session: SessionId,
+    sessions: Arc<SessionRegistry>,
+}
+
+impl LineSession {
+    pub fn new(
+        db: Database,
+        output: TextOutput,
+        pty: bool,
+        session: SessionId,
+        sessions: Arc<SessionRegistry>,
+        user: Option<User>,
+    ) -> Self {
+        LineSession {
+            db,
+            output,
+            echo: pty,
+            newline: if pty { "\r\n" } else { "\n" },
+            buffer: String::new(),
+            partial: Vec::new(),
+            user,
+            channel: None,
+            session,
+            sessions,
+        }
+    }
+
+    /// runs the session until the client closes its input or quits, the
+    /// server events are the ones broadcast to all the sessions
+    pub async fn run(
+        mut self,
+        mut stdin: UnboundedReceiver<Vec<u8>>,
+        mut server_events: Receiver<Event>,
+    ) {
+        let mut events = self.db.subscribe();
+        let _ = self
+            .println("welcome to 3T, type /help to list the available commands")
+            .await;
+
+        loop {
+            let result = tokio::select! {
+                data = stdin.recv() => match data {
+                    Some(data) => self.input(&data).await,
+                    None => break,
+                },
+                event = events.recv() => match event {
+                    Ok(event) => self.storage_event(event).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        self.println(&format!("* missed {} messages", missed)).await.map(|_| true)
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                Some(event) = server_events.recv() => self.server_event(event).await,
+            };
+
+            match result {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => {
+                    log::warn!("line session {} stopped, {:?}", self.session, err);
+                    break;
+                }
+            }
+        }
+        self.output.exit(0).await;
+    }
+
+    async fn println(&self, text: &str) -> anyhow::Result<()> {
+        for line in text.lines() {
+            self.output.stdout(line).await?;
+            self.output.stdout(self.newline).await?;
+        }
+        Ok(())
+    }
+
+    /// handles raw input from the client, returns false
+    /// if the session should end
+    async fn input(&mut self, data: &[u8]) -> anyhow::Result<bool> {
+        for byte in data {
+            match byte {
+                b'\r' | b'\n' => {
+                    // a pty sends `\r` while a pipe sends `\n`, a `\r\n`
+                    // pair would end up as an empty line which is ignored
+                    if self.echo {
+                        self.output.stdout("\r\n").await?;
+                    }
+                    let line = self.buffer.trim().to_string();
+                    self.buffer.clear();
+                    self.partial.clear();
+
+                    if !line.is_empty() && !self.line(&line).await? {
+                        return Ok(false);
+                    }
+                }
+                0x7f | 0x08 => {
+                    self.partial.clear();
+                    if self.buffer.pop().is_some() && self.echo {
+                        self.output.stdout("\x08 \x08").await?;
+                    }
+                }
+                // ctrl-c and ctrl-d end the session
+                0x03 | 0x04 => return Ok(false),
+                byte => {
+                    // a character can be split between two reads, its
+                    // bytes are kept until the character is complete
+                    self.partial.push(*byte);
+                    let c = match std::str::from_utf8(&self.partial) {
+                        Ok(c) => c.to_string(),
+                        Err(err) if err.error_len().is_none() => continue,
+                        Err(_) => String::from(char::REPLACEMENT_CHARACTER),
+                    };
+                    self.partial.clear();
+
+                    if self.buffer.len() + c.len() <= MAX_LINE_LENGTH {
+                        self.buffer.push_str(&c);
+                        if self.echo {
+                            self.output.stdout(&c).await?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    async fn line(&mut self, line: &str) -> anyhow::Result<bool> {
+        let db = self.db.clone();
+        let mut args = line.split_whitespace();
+
+        match args.next() {
+            Some("/quit") => return Ok(false),
+            Some("/help") => self.println(HELP).await?,
+            Some("/login") => {
+                let (Some(username), Some(password)) = (args.next(), args.next()) else {
+                    self.println("usage: /login <username> <password>").await?;
+                    return Ok(true);
+                };
+
+                match User::by_username_password(db, username, password).await {
+                    Ok(user) => {
+                        self.sessions.identify(self.session, user.username());
+                        self.println(&format!("* logged in as {}", user.username()))
+                            .await?;
+                        self.user = Some(user);
+                    }
+                    Err(StorageError::NotFound) => {
+                        self.println("* couldn't authenticate with given credentials")
+                            .await?
+                    }
+                    Err(err) => self.storage_error(err).await?,
+                }
+            }
+            Some("/channels") => match Channel::all(db).await {
+                Ok(channels) => {
+                    for channel in channels {
+                        self.println(channel.name()).await?;
+                    }
+                }
+                Err(err) => self.storage_error(err).await?,
+            },
+            Some("/join") => {
+                let Some(name) = args.next() else {
+                    self.println("usage: /join <channel>").await?;
+                    return Ok(true);
+                };
+
+                match Channel::by_name(db.clone(), name).await {
+                    Ok(channel) => {
+                        self.println(&format!("* joined {}", channel.name()))
+                            .await?;
+                        match Message::history(db, channel.id(), 0, 10).await {
+                            Ok(messages) => {
+                                for message in messages {
+                                    self.println(&format_message(&message)).await?;
+                                }
+                            }
+                            Err(err) => self.storage_error(err).await?,
+                        }
+                        self.channel = Some(channel);
+                    }
+                    Err(StorageError::NotFound) => {
+                        self.println(&format!("* unknown channel {}", name)).await?
+                    }
+                    Err(err) => self.storage_error(err).await?,
+                }
+            }
+            Some("/who") => {
+                let mut sessions = self.sessions.sessions();
+                sessions.sort_by_key(|session| session.id);
+                for session in sessions {
+                    if let Some(username) = &session.username {
+                        self.println(&format!("{} {}", session.presence(), username))
+                            .await?;
+                    }
+                }
+            }
+            Some(command) if command.starts_with('/') => {
+                self.println(&format!("* unknown command {}, type /help", command))
+                    .await?
+            }
+            _ => match (&self.user, &self.channel) {
+                (Some(user), Some(channel)) => {
+                    if let Err(err) = Message::create(db, channel, user, line).await {
+                        log::warn!("couldn't send the message, {}", err);
+                        self.println("* couldn't send the message").await?;
+                    }
+                }
+                (None, _) => self.println("* login first with /login").await?,
+                (_, None) => self.println("* join a channel first with /join").await?,
+            },
+        }
+        Ok(true)
+    }
+
+    /// tells the user the command failed because of the database,
+    /// the details are only logged
+    async fn storage_error(&self, err: StorageError) -> anyhow::Result<()> {
+        log::warn!("line session {} storage error, {}", self.session, err);
+        if err.is_unavailable() {
+            self.println("* the database is unavailable, try again later")
+                .await
+        } else {
+            self.println("* something went wrong, try again later")
+                .await
+        }
+    }
+
+    /// prints the notifications, the other app events
+    /// mean nothing to a line session
+    async fn server_event(&self, event: Event) -> anyhow::Result<bool> {
+        if let Event::Notification((notification, _)) = event {
+            self.println(&format!(
+                "* {}: {}",
+                notification.title(),
+                notification.content()
+            ))
+            .await?;
+        }
+        Ok(true)
+    }
+
+    async fn storage_event(&mut self, event: StorageEvent) -> anyhow::Result<bool> {
+        match event {
+            StorageEvent::MessageCreated(message)
+            | StorageEvent::MessageEdited(message)
+            | StorageEvent::MessageDeleted(message) => {
+                let joined = self
+                    .channel
+                    .as_ref()
+                    .is_some_and(|channel| channel.id() == message.channel_id());
+                if joined {
+                    self.println(&format_message(&message)).await?;
+                }
+            }
+            // the plain text chat doesn't display the reactions
+            // nor the read markers
+            StorageEvent::MessageReacted(_) | StorageEvent::ChannelRead(_) => {}
+        }
+        Ok(true)
+    }
+}
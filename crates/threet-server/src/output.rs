@@ -0,0 +1,88 @@
+use russh::ChannelId;
+use russh::server::Handle;
+
+use threet_storage::models::Message;
+use threet_tui::strip_escapes;
+
+/// writes plain text output to a client channel, used by the
+/// sessions that don't run the interactive app
+pub struct TextOutput {
+    handle: Handle,
+    channel: ChannelId,
+}
+
+impl TextOutput {
+    pub fn new(handle: Handle, channel: ChannelId) -> Self {
+        TextOutput { handle, channel }
+    }
+
+    pub async fn stdout(&self, text: &str) -> anyhow::Result<()> {
+        self.write(text.as_bytes()).await
+    }
+
+    pub async fn write(&self, data: &[u8]) -> anyhow::Result<()> {
+        self.handle
+            .data(self.channel, data.to_vec().into())
+            .await
+            .map_err(|_| anyhow::anyhow!("client closed the channel"))
+    }
+
+    pub async fn stderr(&self, text: &str) -> anyhow::Result<()> {
+        self.handle
+            .extended_data(self.channel, 1, text.as_bytes().to_vec().into())
+            .await
+            .map_err(|_| anyhow::anyhow!("client closed the channel"))
+    }
+
+    /// reports the exit status to the client and closes the channel
+    pub async fn exit(&self, exit_status: u32) {
+        let _ = self
+            .handle
+            .exit_status_request(self.channel, exit_status)
+            .await;
+        let _ = self.handle.eof(self.channel).await;
+        let _ = self.handle.close(self.channel).await;
+    }
+}
+
+/// formats a unix timestamp as `YYYY-MM-DD HH:MM:SS` in UTC
+pub fn format_timestamp(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86400);
+    let secs = timestamp.rem_euclid(86400);
+
+    // civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// formats a message as plain text, deleted messages are shown as a tombstone,
+/// the escape sequences of the body are removed so a message can't take over
+/// the terminals reading it
+pub fn format_message(message: &Message) -> String {
+    let timestamp = format_timestamp(message.created_at());
+    let body = strip_escapes(message.body());
+    if message.is_deleted() {
+        format!("[{}] {}: (deleted)", timestamp, message.author())
+    } else if message.edited_at().is_some() {
+        format!("[{}] {}: {} (edited)", timestamp, message.author(), body)
+    } else {
+        format!("[{}] {}: {}", timestamp, message.author(), body)
+    }
+}
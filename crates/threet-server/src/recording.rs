@@ -0,0 +1,225 @@
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde_json::Value;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufWriter;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::unbounded_channel;
+
+use threet_tui::SessionId;
+
+/// records a session in the asciicast v2 format, the recording has a
+/// header line followed by a line for each event in the form of
+/// `[seconds, kind, data]`, where kind is `o` for the bytes sent to the
+/// terminal, `i` for the input received from it and `r` for resizes
+///
+/// see https://docs.asciinema.org/manual/asciicast/v2/
+pub struct Recorder {
+    events: UnboundedSender<String>,
+    start: Instant,
+    /// the first bytes of a character split between two writes
+    partial_output: Mutex<Vec<u8>>,
+    partial_input: Mutex<Vec<u8>>,
+}
+
+impl Recorder {
+    /// creates a new recording for the given session in the given directory,
+    /// the file is named after the session id and the time it started
+    ///
+    /// the file is written by a background task, so recording never
+    /// waits on the disk, the task finishes once the recorder is dropped
+    pub fn create(dir: &Path, session: SessionId, term: &str, size: (u16, u16)) -> Recorder {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let path = dir.join(format!("{}-session-{}.cast", timestamp, session.0));
+        let header = json!({
+            "version": 2,
+            "width": size.0,
+            "height": size.1,
+            "timestamp": timestamp,
+            "env": { "TERM": term },
+        });
+
+        let (events, rx) = unbounded_channel();
+        tokio::spawn(async move {
+            if let Err(err) = writer(path, header, rx).await {
+                log::warn!("couldn't record session {}, {:?}", session, err);
+            }
+        });
+
+        Recorder {
+            events,
+            start: Instant::now(),
+            partial_output: Mutex::default(),
+            partial_input: Mutex::default(),
+        }
+    }
+
+    /// records the bytes sent to the terminal
+    #[inline]
+    pub fn output(&self, bytes: &[u8]) {
+        self.bytes_event("o", &self.partial_output, bytes);
+    }
+
+    /// records the input received from the terminal
+    #[inline]
+    pub fn input(&self, bytes: &[u8]) {
+        self.bytes_event("i", &self.partial_input, bytes);
+    }
+
+    /// records the terminal new size in a (width, height) format
+    #[inline]
+    pub fn resize(&self, size: (u16, u16)) {
+        self.event("r", &format!("{}x{}", size.0, size.1));
+    }
+
+    /// records the given bytes as text, a character split between
+    /// two writes is recorded with the second write
+    fn bytes_event(&self, kind: &str, partial: &Mutex<Vec<u8>>, bytes: &[u8]) {
+        let mut partial = partial.lock().unwrap();
+        partial.extend_from_slice(bytes);
+
+        let complete = partial.len() - incomplete_tail(&partial);
+        if complete == 0 {
+            return;
+        }
+        let data = String::from_utf8_lossy(&partial[..complete]).into_owned();
+        partial.drain(..complete);
+        self.event(kind, &data);
+    }
+
+    fn event(&self, kind: &str, data: &str) {
+        let event = json!([self.start.elapsed().as_secs_f64(), kind, data]);
+        // the writer only stops early when the file couldn't be written
+        let _ = self.events.send(event.to_string());
+    }
+}
+
+/// writes the recording header and then the events as they come
+/// until the recorder is dropped
+async fn writer(
+    path: PathBuf,
+    header: Value,
+    mut events: UnboundedReceiver<String>,
+) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut file = BufWriter::new(tokio::fs::File::create(&path).await?);
+    log::info!("recording session to {}", path.display());
+
+    file.write_all(format!("{}\n", header).as_bytes()).await?;
+    while let Some(event) = events.recv().await {
+        file.write_all(event.as_bytes()).await?;
+        file.write_all(b"\n").await?;
+    }
+    file.flush().await
+}
+
+/// returns how many bytes at the end of the given bytes are the
+/// start of a character that is not complete yet
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    for len in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - len];
+        // continuation bytes, the character starts further back
+        if byte & 0xc0 == 0x80 {
+            continue;
+        }
+
+        let expected = match byte {
+            0xf0.. => 4,
+            0xe0.. => 3,
+            0xc0.. => 2,
+            _ => 1,
+        };
+        return if expected > len { len } else { 0 };
+    }
+    0
+}
+
+/// plays the given asciicast recording back to the local terminal, `speed`
+/// multiplies the recording speed and `max_idle` caps the time waited between
+/// two events so long pauses in the recording are skipped
+pub async fn replay(
+    path: impl AsRef<Path>,
+    speed: f64,
+    max_idle: Option<Duration>,
+) -> anyhow::Result<()> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+
+    let header: Value = match lines.next() {
+        Some(line) => serde_json::from_str(&line?)?,
+        None => anyhow::bail!("the recording is empty"),
+    };
+    if header["version"] != 2 {
+        anyhow::bail!("only asciicast v2 recordings are supported");
+    }
+
+    let mut stdout = std::io::stdout();
+    if let (Some(width), Some(height)) = (header["width"].as_u64(), header["height"].as_u64()) {
+        resize(&mut stdout, width, height)?;
+    }
+
+    let mut last = 0.0;
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event: Value = serde_json::from_str(&line)?;
+        let (Some(time), Some(kind), Some(data)) =
+            (event[0].as_f64(), event[1].as_str(), event[2].as_str())
+        else {
+            anyhow::bail!("invalid recording event `{}`", line);
+        };
+
+        let mut wait = Duration::from_secs_f64((time - last).max(0.0) / speed);
+        if let Some(max_idle) = max_idle {
+            wait = wait.min(max_idle);
+        }
+        tokio::time::sleep(wait).await;
+        last = time;
+
+        match kind {
+            "o" => {
+                stdout.write_all(data.as_bytes())?;
+                stdout.flush()?;
+            }
+            "r" => {
+                if let Some((width, height)) = data.split_once('x')
+                    && let (Ok(width), Ok(height)) = (width.parse(), height.parse())
+                {
+                    resize(&mut stdout, width, height)?;
+                }
+            }
+            // the input is recorded to know what the user pressed,
+            // it is not something to write to the terminal
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// asks the terminal to resize to the recorded size, terminals
+/// that don't allow it will just ignore the sequence
+fn resize(stdout: &mut impl Write, width: u64, height: u64) -> std::io::Result<()> {
+    write!(stdout, "\x1b[8;{};{}t", height, width)?;
+    stdout.flush()
+}
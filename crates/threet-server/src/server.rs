@@ -1,20 +1,59 @@
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use russh::server::Server as SshServerTrait;
+use threet_storage::Database;
 
 use crate::client::Client;
+use crate::session::SessionRegistry;
 
-pub struct Server {}
+/// options applied to every session opened on the server
+#[derive(Debug, Clone)]
+pub struct SessionOptions {
+    /// the max frames per second the app sends to the client
+    pub max_fps: u32,
+    /// the directory session recordings are stored in, recording
+    /// is disabled if no directory is configured
+    pub record_dir: Option<PathBuf>,
+    /// record all the sessions, otherwise only the sessions
+    /// that asked for it are recorded
+    pub record_all: bool,
+    /// record the input of the sessions too, only once the session
+    /// is authenticated so the recordings never have a password
+    pub record_input: bool,
+}
+
+pub struct Server {
+    database: Database,
+    sessions: Arc<SessionRegistry>,
+    options: Arc<SessionOptions>,
+}
 
 impl Server {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(database: Database, options: SessionOptions) -> Self {
+        Self {
+            database,
+            sessions: Arc::new(SessionRegistry::new()),
+            options: Arc::new(options),
+        }
+    }
+
+    /// returns the registry of all the sessions connected to the server
+    #[inline]
+    pub fn sessions(&self) -> Arc<SessionRegistry> {
+        self.sessions.clone()
     }
 }
 
 impl SshServerTrait for Server {
     type Handler = Client;
     fn new_client(&mut self, peer: Option<SocketAddr>) -> Self::Handler {
-        Self::Handler::new(peer.unwrap())
+        Self::Handler::new(
+            peer.unwrap(),
+            self.database.clone(),
+            self.sessions.clone(),
+            self.options.clone(),
+        )
     }
 }
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use russh::ChannelId;
+use russh::Disconnect;
+use russh::server::Handle;
+use tokio::sync::Notify;
+use tokio::sync::mpsc::Sender;
+
+use threet_tui::Event;
+use threet_tui::SessionDirectory;
+use threet_tui::SessionId;
+use threet_tui::SessionInfo;
+
+struct SessionEntry {
+    info: SessionInfo,
+    handle: Handle,
+    /// the channel and events sender, set once an app or
+    /// a line session is running for the session
+    app: Option<(ChannelId, Sender<Event>)>,
+}
+
+/// the session registry is shared between all the clients on the server, each
+/// client registers its session when it is opened and removes it when it is dropped
+pub struct SessionRegistry {
+    next_id: AtomicU64,
+    sessions: RwLock<HashMap<SessionId, SessionEntry>>,
+    /// notified once the last session is unregistered
+    emptied: Notify,
+}
+
+impl SessionRegistry {
+    pub fn new() -> Self {
+        SessionRegistry {
+            next_id: AtomicU64::new(1),
+            sessions: RwLock::new(HashMap::new()),
+            emptied: Notify::new(),
+        }
+    }
+
+    /// registers a new session for the given peer, the returned
+    /// id should be used to update the session later
+    pub fn register(&self, peer: SocketAddr, handle: Handle) -> SessionId {
+        let id = SessionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let info = SessionInfo {
+            id,
+            username: None,
+            peer,
+            connected_at: SystemTime::now(),
+            last_activity: Instant::now(),
+            size: (0, 0),
+        };
+
+        self.sessions.write().unwrap().insert(
+            id,
+            SessionEntry {
+                info,
+                handle,
+                app: None,
+            },
+        );
+        log::info!("session {} registered for {}", id, peer);
+        id
+    }
+
+    pub fn unregister(&self, id: SessionId) {
+        let mut sessions = self.sessions.write().unwrap();
+        if sessions.remove(&id).is_some() {
+            log::info!("session {} unregistered", id);
+            if sessions.is_empty() {
+                self.emptied.notify_waiters();
+            }
+        }
+    }
+
+    /// waits until every session is unregistered, returns
+    /// right away if there are no sessions
+    pub async fn wait_empty(&self) {
+        loop {
+            // created before the check so an unregister in between is not missed
+            let emptied = self.emptied.notified();
+            if self.sessions.read().unwrap().is_empty() {
+                return;
+            }
+            emptied.await;
+        }
+    }
+
+    /// marks the session as active now
+    pub fn touch(&self, id: SessionId) {
+        if let Some(entry) = self.sessions.write().unwrap().get_mut(&id) {
+            entry.info.last_activity = Instant::now();
+        }
+    }
+
+    pub fn set_size(&self, id: SessionId, size: (u16, u16)) {
+        if let Some(entry) = self.sessions.write().unwrap().get_mut(&id) {
+            entry.info.size = size;
+        }
+    }
+
+    /// attach the app or line session running on the given channel to
+    /// the session so server wide events can be delivered to it
+    pub fn attach(&self, id: SessionId, channel: ChannelId, app_tx: Sender<Event>) {
+        if let Some(entry) = self.sessions.write().unwrap().get_mut(&id) {
+            entry.app = Some((channel, app_tx));
+        }
+    }
+
+    /// sends an event created by the given function to every running app and
+    /// line session, the busy ones are skipped, returns the amount of sessions
+    /// the event was delivered to
+    pub fn broadcast<F>(&self, event: F) -> usize
+    where
+        F: Fn() -> Event,
+    {
+        let mut delivered = 0;
+        for entry in self.sessions.read().unwrap().values() {
+            if let Some((_, app_tx)) = &entry.app {
+                match app_tx.try_send(event()) {
+                    Ok(_) => delivered += 1,
+                    Err(err) => {
+                        log::warn!(
+                            "couldn't deliver event to session {}, {}",
+                            entry.info.id,
+                            err
+                        )
+                    }
+                }
+            }
+        }
+        delivered
+    }
+
+    /// closes every session channel with the given exit status and
+    /// disconnects the sessions
+    pub async fn close_all(&self, exit_status: u32, reason: &str) {
+        let entries = self
+            .sessions
+            .read()
+            .unwrap()
+            .values()
+            .map(|entry| (entry.info.id, entry.handle.clone(), entry.app.clone()))
+            .collect::<Vec<_>>();
+
+        for (id, handle, app) in entries {
+            if let Some((channel, _)) = app {
+                let _ = handle.exit_status_request(channel, exit_status).await;
+                let _ = handle.eof(channel).await;
+                let _ = handle.close(channel).await;
+            }
+            let _ = handle
+                .disconnect(
+                    Disconnect::ByApplication,
+                    reason.to_string(),
+                    "en".to_string(),
+                )
+                .await
+                .inspect_err(|err| log::warn!("problem disconnecting session {}, {:?}", id, err));
+        }
+    }
+}
+
+impl SessionDirectory for SessionRegistry {
+    fn sessions(&self) -> Vec<SessionInfo> {
+        self.sessions
+            .read()
+            .unwrap()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    fn identify(&self, id: SessionId, username: &str) {
+        if let Some(entry) = self.sessions.write().unwrap().get_mut(&id) {
+            entry.info.username = Some(username.to_string());
+        }
+    }
+
+    fn terminate(&self, id: SessionId) -> bool {
+        let Some(handle) = self
+            .sessions
+            .read()
+            .unwrap()
+            .get(&id)
+            .map(|entry| entry.handle.clone())
+        else {
+            return false;
+        };
+
+        tokio::spawn(async move {
+            let _ = handle
+                .disconnect(
+                    Disconnect::ByApplication,
+                    "session terminated by an administrator".to_string(),
+                    "en".to_string(),
+                )
+                .await
+                .inspect_err(|err| log::warn!("problem terminating session {}, {:?}", id, err));
+        });
+        true
+    }
+}
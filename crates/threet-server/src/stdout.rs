@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use russh::ChannelId;
+use russh::server::Handle;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use threet_tui::OutputStats;
+use threet_tui::RawOutput;
+
+use crate::recording::Recorder;
+
+/// how many frames can wait for the client before it is considered
+/// lagging, when the queue is full the stale queued frames are dropped
+const MAX_QUEUED_FRAMES: usize = 4;
+
+/// the bytes waiting to be sent, in the order they were written
+enum Frame {
+    /// a frame the app drew, a diff on top of the previous frames
+    /// which can be dropped once the client is lagging
+    Diff(Vec<u8>),
+    /// a raw escape sequence, like setting the clipboard, which
+    /// is never dropped
+    Raw(Vec<u8>),
+}
+
+/// the frames waiting to be sent, shared between the
+/// `ChannelStdout` and its writer task
+#[derive(Default)]
+struct FrameQueue {
+    frames: Mutex<VecDeque<Frame>>,
+    notify: Notify,
+    closed: AtomicBool,
+}
+
+/// the app stdout for a channel, each flush is a single frame which is queued for
+/// a writer task that sends the frames one after the other in order
+///
+/// `russh` waits for the channel window before sending data, so a slow
+/// client slows down the writer task, in that case the queue fills up, the stale
+/// queued frames are dropped for the latest one and the app is asked to redraw
+/// the whole screen
+pub struct ChannelStdout {
+    buffer: Vec<u8>,
+    queue: Arc<FrameQueue>,
+    stats: Arc<OutputStats>,
+}
+
+impl ChannelStdout {
+    /// creates a new stdout for the given channel, the returned handle is the writer
+    /// task which finishes once the stdout is dropped and all the frames were sent
+    pub fn new(
+        session_handle: Handle,
+        channel_id: ChannelId,
+        capacity: usize,
+        stats: Arc<OutputStats>,
+        recorder: Option<Arc<Recorder>>,
+    ) -> (ChannelStdout, JoinHandle<()>) {
+        let queue = Arc::new(FrameQueue::default());
+        let writer = tokio::spawn(writer(
+            session_handle,
+            channel_id,
+            queue.clone(),
+            stats.clone(),
+            recorder,
+        ));
+
+        let stdout = ChannelStdout {
+            buffer: Vec::with_capacity(capacity),
+            queue,
+            stats,
+        };
+        (stdout, writer)
+    }
+}
+
+impl Write for ChannelStdout {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        // the frame is a diff on top of frames the client never got, it
+        // is useless until the app redraws the whole screen
+        if self.stats.resync_requested() {
+            self.stats.record_dropped(1);
+            self.buffer.clear();
+            return Ok(());
+        }
+
+        let mut frames = self.queue.frames.lock().unwrap();
+        if frames.len() >= MAX_QUEUED_FRAMES {
+            let len = frames.len();
+            frames.retain(|frame| matches!(frame, Frame::Raw(_)));
+            let dropped = len - frames.len();
+            self.stats.record_dropped(dropped);
+            log::debug!("client is lagging, dropped {} frames", dropped);
+        }
+
+        let capacity = self.buffer.capacity();
+        frames.push_back(Frame::Diff(std::mem::replace(
+            &mut self.buffer,
+            Vec::with_capacity(capacity),
+        )));
+        drop(frames);
+        self.queue.notify.notify_one();
+        Ok(())
+    }
+}
+
+impl RawOutput for ChannelStdout {
+    /// queues the sequence after the frames already written, the
+    /// sequence is sent even if the frames around it are dropped
+    fn write_raw(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.flush()?;
+        self.queue
+            .frames
+            .lock()
+            .unwrap()
+            .push_back(Frame::Raw(bytes.to_vec()));
+        self.queue.notify.notify_one();
+        Ok(())
+    }
+}
+
+impl Drop for ChannelStdout {
+    fn drop(&mut self) {
+        self.queue.closed.store(true, Ordering::Release);
+        self.queue.notify.notify_one();
+    }
+}
+
+/// sends the queued frames in order until the stdout
+/// is dropped and there are no more frames to send
+async fn writer(
+    handle: Handle,
+    channel_id: ChannelId,
+    queue: Arc<FrameQueue>,
+    stats: Arc<OutputStats>,
+    recorder: Option<Arc<Recorder>>,
+) {
+    loop {
+        let frame = queue.frames.lock().unwrap().pop_front();
+        let Some(Frame::Diff(frame) | Frame::Raw(frame)) = frame else {
+            if queue.closed.load(Ordering::Acquire) {
+                break;
+            }
+            queue.notify.notified().await;
+            continue;
+        };
+
+        // the dropped frames never reach the writer, the recording
+        // has exactly what the client received
+        if let Some(recorder) = recorder.as_ref() {
+            recorder.output(&frame);
+        }
+
+        let len = frame.len();
+        if let Err(err) = handle.data(channel_id, frame.into()).await {
+            log::warn!("problem sending stdout data to remote client, {:?}", err);
+            break;
+        }
+        stats.record_sent(len);
+    }
+
+    log::info!(
+        "channel {:?} output done, {} frames sent ({} bytes), {} frames dropped",
+        channel_id,
+        stats.frames_sent(),
+        stats.bytes_sent(),
+        stats.frames_dropped()
+    );
+}
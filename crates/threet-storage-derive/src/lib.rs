@@ -0,0 +1,201 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Fields;
+use syn::Ident;
+use syn::LitStr;
+use syn::Type;
+use syn::parse_macro_input;
+
+/// derives the `Model` and `FromRow` traits for a struct with named fields, each
+/// field is a column with the same name unless it is marked with `#[model(skip)]`
+///
+/// ```ignore
+/// #[derive(Model)]
+/// #[model(table = "Channel")]
+/// pub struct Channel {
+///     #[model(primary_key)]
+///     id: i32,
+///     name: String,
+/// }
+/// ```
+///
+/// the table name defaults to the struct name, and the primary key defaults
+/// to the field named `id`, skipped fields are set to their default value
+/// when the model is read from a row
+#[proc_macro_derive(Model, attributes(model))]
+pub fn derive_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct Column {
+    ident: Ident,
+    ty: Type,
+    skip: bool,
+    primary_key: bool,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let mut table = name.to_string();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("model"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("unsupported model attribute, expected `table`"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "`Model` can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "`Model` can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut columns = Vec::with_capacity(fields.len());
+    for field in fields {
+        let mut column = Column {
+            ident: field.ident.clone().expect("named fields have an ident"),
+            ty: field.ty.clone(),
+            skip: false,
+            primary_key: false,
+        };
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("model"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    column.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("primary_key") {
+                    column.primary_key = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported model attribute, expected `skip` or `primary_key`"))
+                }
+            })?;
+        }
+        columns.push(column);
+    }
+
+    // without an explicit primary key, the `id` field is the primary key
+    if !columns.iter().any(|column| column.primary_key)
+        && let Some(column) = columns.iter_mut().find(|column| column.ident == "id")
+    {
+        column.primary_key = true;
+    }
+
+    let mut primary_keys = columns.iter().filter(|column| column.primary_key);
+    let Some(primary_key) = primary_keys.next() else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "`Model` requires an `id` field or a field marked with `#[model(primary_key)]`",
+        ));
+    };
+    if let Some(column) = primary_keys.next() {
+        return Err(syn::Error::new_spanned(
+            &column.ident,
+            "`Model` supports a single primary key",
+        ));
+    }
+    if primary_key.skip {
+        return Err(syn::Error::new_spanned(
+            &primary_key.ident,
+            "the primary key can't be skipped",
+        ));
+    }
+
+    let primary_key_ident = &primary_key.ident;
+    let primary_key_name = primary_key.ident.to_string();
+    let primary_key_ty = &primary_key.ty;
+
+    let fields = columns
+        .iter()
+        .filter(|column| !column.skip)
+        .map(|column| column.ident.to_string());
+
+    let values = columns
+        .iter()
+        .filter(|column| !column.skip && !column.primary_key)
+        .map(|column| {
+            let ident = &column.ident;
+            let name = ident.to_string();
+            quote! {
+                (#name, ::threet_storage::rusqlite::types::Value::from(self.#ident.clone()))
+            }
+        });
+
+    let from_row = columns.iter().map(|column| {
+        let ident = &column.ident;
+        if column.skip {
+            quote! { #ident: ::std::default::Default::default() }
+        } else {
+            let name = ident.to_string();
+            quote! { #ident: row.get(#name)? }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::threet_storage::FromRow for #name #ty_generics #where_clause {
+            fn from_row(row: &::threet_storage::rusqlite::Row) -> ::threet_storage::rusqlite::Result<Self> {
+                Ok(#name {
+                    #(#from_row,)*
+                })
+            }
+        }
+
+        impl #impl_generics ::threet_storage::models::Model for #name #ty_generics #where_clause {
+            type Id = #primary_key_ty;
+
+            fn table_name() -> &'static str {
+                #table
+            }
+
+            fn fields() -> Vec<String> {
+                vec![#(#fields.to_string()),*]
+            }
+
+            fn primary_key() -> &'static str {
+                #primary_key_name
+            }
+
+            fn primary_key_value(&self) -> Self::Id {
+                self.#primary_key_ident.clone()
+            }
+
+            fn values(&self) -> Vec<(&'static str, ::threet_storage::rusqlite::types::Value)> {
+                vec![#(#values),*]
+            }
+        }
+    })
+}
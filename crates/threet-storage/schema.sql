@@ -1,10 +1,99 @@
 CREATE TABLE IF NOT EXISTS User (
     id INTEGER PRIMARY KEY,
     username TEXT,
-    password TEXT
+    password TEXT,
+    role TEXT NOT NULL DEFAULT 'member'
+);
+
+-- the ssh public keys a user authenticates with, in the
+-- openssh `<algorithm> <base64>` format without the comment
+CREATE TABLE IF NOT EXISTS UserKey (
+    user_id INTEGER NOT NULL REFERENCES User(id),
+    key TEXT NOT NULL,
+    PRIMARY KEY (user_id, key)
 );
 
 CREATE TABLE IF NOT EXISTS Channel (
     id INTEGER PRIMARY KEY,
     name TEXT
 );
+
+CREATE TABLE IF NOT EXISTS Message (
+    id INTEGER PRIMARY KEY,
+    channel_id INTEGER NOT NULL REFERENCES Channel(id),
+    user_id INTEGER NOT NULL REFERENCES User(id),
+    body TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    -- the thread root message for replies, threads are a single level deep
+    parent_id INTEGER REFERENCES Message(id),
+    edited_at INTEGER,
+    -- deleted messages are kept as tombstones with an empty body, so
+    -- their replies and the timeline around them stay in place
+    deleted_at INTEGER
+);
+
+-- the previous bodies of the edited messages
+CREATE TABLE IF NOT EXISTS MessageEdit (
+    id INTEGER PRIMARY KEY,
+    message_id INTEGER NOT NULL REFERENCES Message(id),
+    body TEXT NOT NULL,
+    edited_at INTEGER NOT NULL
+);
+
+-- a reaction per user and emoji shortcode, reacting again with
+-- the same emoji removes the reaction
+CREATE TABLE IF NOT EXISTS Reaction (
+    message_id INTEGER NOT NULL REFERENCES Message(id),
+    user_id INTEGER NOT NULL REFERENCES User(id),
+    emoji TEXT NOT NULL,
+    PRIMARY KEY (message_id, user_id, emoji)
+);
+
+-- the last message a user read in a channel, the messages
+-- after it are unread
+CREATE TABLE IF NOT EXISTS ReadMarker (
+    user_id INTEGER NOT NULL REFERENCES User(id),
+    channel_id INTEGER NOT NULL REFERENCES Channel(id),
+    message_id INTEGER NOT NULL,
+    PRIMARY KEY (user_id, channel_id)
+);
+
+-- the users mentioned by a message, keyed by the user
+-- first to list a user mentions
+CREATE TABLE IF NOT EXISTS Mention (
+    user_id INTEGER NOT NULL REFERENCES User(id),
+    message_id INTEGER NOT NULL REFERENCES Message(id),
+    PRIMARY KEY (user_id, message_id)
+);
+
+CREATE INDEX IF NOT EXISTS MentionMessageIndex ON Mention (message_id);
+
+CREATE INDEX IF NOT EXISTS MessageChannelIndex ON Message (channel_id, id);
+
+CREATE INDEX IF NOT EXISTS MessageParentIndex ON Message (parent_id, id);
+
+CREATE INDEX IF NOT EXISTS MessageEditMessageIndex ON MessageEdit (message_id, id);
+
+-- the unique usernames and channel names are indexed by the migration,
+-- the databases created before them may have duplicates
+
+-- full text index over the message bodies, the index doesn't keep a
+-- copy of the bodies, it reads them from the `Message` table
+CREATE VIRTUAL TABLE IF NOT EXISTS MessageSearch USING fts5 (
+    body,
+    content = 'Message',
+    content_rowid = 'id'
+);
+
+CREATE TRIGGER IF NOT EXISTS MessageSearchInsert AFTER INSERT ON Message BEGIN
+    INSERT INTO MessageSearch (rowid, body) VALUES (new.id, new.body);
+END;
+
+CREATE TRIGGER IF NOT EXISTS MessageSearchDelete AFTER DELETE ON Message BEGIN
+    INSERT INTO MessageSearch (MessageSearch, rowid, body) VALUES ('delete', old.id, old.body);
+END;
+
+CREATE TRIGGER IF NOT EXISTS MessageSearchUpdate AFTER UPDATE OF body ON Message BEGIN
+    INSERT INTO MessageSearch (MessageSearch, rowid, body) VALUES ('delete', old.id, old.body);
+    INSERT INTO MessageSearch (rowid, body) VALUES (new.id, new.body);
+END;
@@ -0,0 +1,144 @@
+/// returned when the database couldn't be created
+#[derive(Debug)]
+pub enum BuildError {
+    /// the builder was not given a path, and was not asked
+    /// for an in-memory or a temporary database
+    MissingLocation,
+    /// couldn't open the database connections
+    Open(StorageError),
+    /// the database is open but the schema couldn't be applied, most
+    /// likely the database was created by something else
+    Schema(StorageError),
+    /// the database was created before the names of the table were
+    /// unique, and has the given names more than once
+    Duplicates {
+        table: &'static str,
+        names: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::MissingLocation => write!(f, "cannot create database without a path"),
+            BuildError::Open(err) => write!(f, "couldn't connect to database, {}", err),
+            BuildError::Schema(err) => write!(f, "problem executing database schema, {}", err),
+            BuildError::Duplicates { table, names } => write!(
+                f,
+                "the `{}` names must be unique, rename the duplicates before upgrading: {}",
+                table,
+                names.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::MissingLocation | BuildError::Duplicates { .. } => None,
+            BuildError::Open(err) | BuildError::Schema(err) => Some(err),
+        }
+    }
+}
+
+/// returned by all the storage APIs, the variants group the sqlite errors
+/// by what the caller can do about them
+#[derive(Debug)]
+pub enum StorageError {
+    /// the query didn't match any row
+    NotFound,
+    /// the write breaks a table constraint, like a unique
+    /// column, with the sqlite message describing which one
+    ConstraintViolation(String),
+    /// the user is not allowed to change the record, like
+    /// editing a message somebody else wrote
+    PermissionDenied,
+    /// the database is locked by another connection
+    /// for longer than the busy timeout
+    Busy,
+    /// the database file couldn't be opened, read or written
+    Io(async_sqlite::Error),
+    /// the database file is malformed, or is not a database
+    Corruption(async_sqlite::Error),
+    /// any other problem reported by the database
+    Database(async_sqlite::Error),
+}
+
+impl StorageError {
+    /// returns true if the database can't be used right now, as
+    /// opposed to the query being wrong for the stored data
+    pub fn is_unavailable(&self) -> bool {
+        matches!(
+            self,
+            StorageError::Busy | StorageError::Io(_) | StorageError::Corruption(_)
+        )
+    }
+}
+
+impl From<async_sqlite::Error> for StorageError {
+    fn from(err: async_sqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+
+        let async_sqlite::Error::Rusqlite(inner) = &err else {
+            // the pool is closed, or the connection thread is gone
+            return StorageError::Io(err);
+        };
+        if let rusqlite::Error::QueryReturnedNoRows = inner {
+            return StorageError::NotFound;
+        }
+
+        match inner.sqlite_error_code() {
+            Some(ErrorCode::ConstraintViolation) => {
+                let message = match inner {
+                    rusqlite::Error::SqliteFailure(_, Some(message)) => message.clone(),
+                    _ => inner.to_string(),
+                };
+                StorageError::ConstraintViolation(message)
+            }
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => StorageError::Busy,
+            Some(
+                ErrorCode::SystemIoFailure
+                | ErrorCode::CannotOpen
+                | ErrorCode::DiskFull
+                | ErrorCode::ReadOnly
+                | ErrorCode::PermissionDenied
+                | ErrorCode::FileLockingProtocolFailed,
+            ) => StorageError::Io(err),
+            Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => {
+                StorageError::Corruption(err)
+            }
+            _ => StorageError::Database(err),
+        }
+    }
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::NotFound => write!(f, "no matching record was found"),
+            StorageError::ConstraintViolation(message) => {
+                write!(f, "constraint violation, {}", message)
+            }
+            StorageError::PermissionDenied => write!(f, "permission denied"),
+            StorageError::Busy => write!(f, "the database is busy"),
+            StorageError::Io(err) => write!(f, "couldn't access the database, {}", err),
+            StorageError::Corruption(err) => write!(f, "the database is corrupted, {}", err),
+            StorageError::Database(err) => write!(f, "database error, {}", err),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::NotFound
+            | StorageError::ConstraintViolation(_)
+            | StorageError::PermissionDenied
+            | StorageError::Busy => None,
+            StorageError::Io(err) | StorageError::Corruption(err) | StorageError::Database(err) => {
+                Some(err)
+            }
+        }
+    }
+}
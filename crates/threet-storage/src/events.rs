@@ -0,0 +1,17 @@
+use crate::models::Message;
+use crate::models::ReadMarker;
+
+/// published by the storage whenever shared data changes, sessions
+/// subscribe to those events to update live without polling
+#[derive(Debug, Clone)]
+pub enum StorageEvent {
+    MessageCreated(Message),
+    /// the message with its new body
+    MessageEdited(Message),
+    /// the message tombstone, its body is removed
+    MessageDeleted(Message),
+    /// the message with its updated reactions
+    MessageReacted(Message),
+    /// a user read a channel up to the marker message
+    ChannelRead(ReadMarker),
+}
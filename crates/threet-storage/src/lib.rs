@@ -1,54 +1,161 @@
 use std::path::Path;
 use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 
 use async_sqlite::JournalMode;
 use async_sqlite::Pool;
 use async_sqlite::PoolBuilder;
 use rusqlite::Row;
+use tokio::sync::broadcast;
 
+// lets the derive macros refer to this crate as `::threet_storage`
+// from the models defined in this crate too
+extern crate self as threet_storage;
+
+mod error;
+mod events;
 pub mod models;
+mod repository;
+
+pub use error::BuildError;
+pub use error::StorageError;
+pub use events::StorageEvent;
+pub use repository::Page;
+pub use repository::Repository;
+// re-exported for the code generated by `#[derive(Model)]`
+#[doc(hidden)]
+pub use rusqlite;
 
 use models::Model;
 
-const database_schema: &str = include_str!("../schema.sql");
+const DATABASE_SCHEMA: &str = include_str!("../schema.sql");
+
+/// the unique indexes added after the first databases were created, as
+/// `(index, table, column)`, the column values may already have duplicates
+const UNIQUE_INDEXES: [(&str, &str, &str); 2] = [
+    ("UserUsernameIndex", "User", "username"),
+    ("ChannelNameIndex", "Channel", "name"),
+];
+
+/// brings the databases created by an older version to the current
+/// schema, the tables they already have are not created again by the
+/// schema so their new columns are added here
+///
+/// the duplicates that prevent a unique index are reported, instead of
+/// failing on the index constraint, so they can be renamed by hand
+fn migrate(conn: &rusqlite::Connection) -> rusqlite::Result<Result<(), BuildError>> {
+    let has_role: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_table_info('User') WHERE name = 'role')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_role {
+        conn.execute_batch("ALTER TABLE \"User\" ADD COLUMN role TEXT NOT NULL DEFAULT 'member'")?;
+    }
+
+    for (index, table, column) in UNIQUE_INDEXES {
+        let names = conn
+            .prepare(&format!(
+                "SELECT {column} FROM \"{table}\" WHERE {column} IS NOT NULL \
+                 GROUP BY {column} HAVING COUNT(*) > 1 ORDER BY {column}"
+            ))?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        if !names.is_empty() {
+            return Ok(Err(BuildError::Duplicates { table, names }));
+        }
+
+        conn.execute_batch(&format!(
+            "CREATE UNIQUE INDEX IF NOT EXISTS {index} ON \"{table}\" ({column})"
+        ))?;
+    }
+    Ok(Ok(()))
+}
 
-/// stores the global database instance so everyone can access it
-static DATABASE: OnceLock<Database> = OnceLock::new();
+/// how many storage events can be buffered for a slow subscriber
+/// before it starts missing events
+const EVENTS_CAPACITY: usize = 256;
+
+/// used to give every in-memory and temporary database a unique
+/// name, so databases created in the same process are isolated
+static DATABASE_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 /// implemented on types that can be created
 /// from a database row, usually types that implement
 /// this triat are models
-trait FromRow: Sized {
+pub trait FromRow: Sized {
     fn from_row(row: &Row) -> rusqlite::Result<Self>;
 }
 
 fn preper_select_statement_string<T: Model>() -> String {
     let fields = T::fields().join(",");
-    format!("SELECT {} from {}", fields, T::table_name())
+    format!("SELECT {} FROM \"{}\"", fields, T::table_name())
 }
 
-pub fn set_database(db: Database) {
-    DATABASE
-        .set(db)
-        .expect("couldn't set global database instance");
-}
+/// removes the temporary database files once the last
+/// handle to the database is dropped
+#[derive(Debug)]
+struct TemporaryFile(PathBuf);
 
-pub fn get_database() -> Database {
-    DATABASE
-        .get()
-        .expect("couldn't get global database instance")
-        .clone()
+impl Drop for TemporaryFile {
+    fn drop(&mut self) {
+        // the write ahead log files are next to the database file
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.0.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Database {
     pool: Pool,
+    events: broadcast::Sender<StorageEvent>,
+    /// held only to remove the file once all the handles are dropped
+    _temporary: Option<Arc<TemporaryFile>>,
 }
 
 impl Database {
-    fn new(pool: Pool) -> Self {
-        Self { pool }
+    fn new(pool: Pool, temporary: Option<TemporaryFile>) -> Self {
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        Self {
+            pool,
+            events,
+            _temporary: temporary.map(Arc::new),
+        }
+    }
+
+    /// returns a receiver for all the storage events published
+    /// from now on
+    #[inline]
+    pub fn subscribe(&self) -> broadcast::Receiver<StorageEvent> {
+        self.events.subscribe()
+    }
+
+    /// returns the typed queries for the given model
+    #[inline]
+    pub fn repository<T: Model>(&self) -> Repository<T> {
+        Repository::new(self.clone())
+    }
+
+    /// publish an event to all the subscribers, it is fine if
+    /// there are no subscribers
+    #[inline]
+    pub(crate) fn publish(&self, event: StorageEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// closes all the pool connections, pending writes are flushed
+    /// to the disk before the connections are closed
+    pub async fn close(&self) {
+        let _ = self
+            .pool
+            .close()
+            .await
+            .inspect_err(|err| log::warn!("problem closing database pool, {:?}", err));
     }
 }
 
@@ -58,16 +165,44 @@ impl std::fmt::Debug for Database {
     }
 }
 
+/// where the database is stored
+#[derive(Default)]
+enum Location {
+    #[default]
+    Unset,
+    Path(PathBuf),
+    /// the database lives as long as the pool
+    /// connections are open
+    Memory,
+    /// a file in the temp directory that is removed once
+    /// the database is dropped
+    Temporary,
+}
+
 #[derive(Default)]
 pub struct DatabaseBuilder {
-    path: Option<PathBuf>,
+    location: Location,
     num_connections: Option<usize>,
 }
 
 impl DatabaseBuilder {
     /// to defines the file system path to the database
     pub fn path(mut self, path: impl AsRef<Path>) -> Self {
-        self.path = Some(path.as_ref().to_owned());
+        self.location = Location::Path(path.as_ref().to_owned());
+        self
+    }
+
+    /// keeps the database in memory, nothing is written to the file system
+    /// and the data is gone once the database is closed or dropped
+    pub fn in_memory(mut self) -> Self {
+        self.location = Location::Memory;
+        self
+    }
+
+    /// stores the database in a new file in the temp directory,
+    /// the file is removed once the database is dropped
+    pub fn temporary(mut self) -> Self {
+        self.location = Location::Temporary;
         self
     }
 
@@ -78,17 +213,47 @@ impl DatabaseBuilder {
         self
     }
 
-    pub async fn build(self) -> Database {
-        let pool = PoolBuilder::new()
-            .path(self.path.expect("cannot create database without a path"))
-            .num_conns(self.num_connections.unwrap_or(1))
-            .journal_mode(JournalMode::Wal)
+    pub async fn build(self) -> Result<Database, BuildError> {
+        let id = DATABASE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let builder = PoolBuilder::new().num_conns(self.num_connections.unwrap_or(1));
+
+        let (builder, temporary) = match self.location {
+            Location::Unset => return Err(BuildError::MissingLocation),
+            Location::Path(path) => (builder.path(path).journal_mode(JournalMode::Wal), None),
+            // all the pool connections must share the same memory database, a
+            // plain `:memory:` path would give each connection its own database
+            Location::Memory => {
+                let uri = format!(
+                    "file:threet-memory-{}-{}?mode=memory&cache=shared",
+                    std::process::id(),
+                    id
+                );
+                (builder.path(uri), None)
+            }
+            Location::Temporary => {
+                let path = std::env::temp_dir().join(format!(
+                    "threet-{}-{}.sqlite",
+                    std::process::id(),
+                    id
+                ));
+                let temporary = TemporaryFile(path.clone());
+                (
+                    builder.path(path).journal_mode(JournalMode::Wal),
+                    Some(temporary),
+                )
+            }
+        };
+
+        let pool = builder
             .open()
             .await
-            .expect("couldn't connect to database");
-        pool.conn(|conn| conn.execute_batch(database_schema))
-            .await
-            .expect("problem executing database schema");
-        Database::new(pool)
+            .map_err(|err| BuildError::Open(err.into()))?;
+        pool.conn(|conn| {
+            conn.execute_batch(DATABASE_SCHEMA)?;
+            migrate(conn)
+        })
+        .await
+        .map_err(|err| BuildError::Schema(err.into()))??;
+        Ok(Database::new(pool, temporary))
     }
 }
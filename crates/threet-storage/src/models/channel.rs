@@ -1,26 +1,55 @@
 use super::Model;
-use crate::FromRow;
+use crate::Database;
+use crate::StorageError;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Model)]
+#[model(table = "Channel")]
 pub struct Channel {
     id: i32,
     name: String,
 }
 
-impl FromRow for Channel {
-    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
-        let id = row.get("id")?;
-        let name = row.get("name")?;
-        Ok(Channel { id, name })
+impl Channel {
+    /// a channel that is not stored yet, the id is
+    /// assigned once it is inserted
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: 0,
+            name: String::from(name),
+        }
+    }
+
+    #[inline]
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn set_name(&mut self, name: &str) {
+        self.name = String::from(name);
     }
-}
 
-impl Model for Channel {
-    fn table_name() -> &'static str {
-        "Channel"
+    pub async fn by_name(db: Database, name: &str) -> Result<Channel, StorageError> {
+        db.repository::<Channel>()
+            .find_where("name = ?1", vec![name.to_string().into()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(StorageError::NotFound)
     }
 
-    fn fields() -> Vec<String> {
-        vec!["id".to_string(), "name".to_string()]
+    /// returns all the channels ordered by their name
+    pub async fn all(db: Database) -> Result<Vec<Channel>, StorageError> {
+        let mut channels = db
+            .repository::<Channel>()
+            .find_where("TRUE", Vec::new())
+            .await?;
+        channels.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(channels)
     }
 }
@@ -0,0 +1,60 @@
+use std::ops::Range;
+
+use rusqlite::Connection;
+
+/// the mention notifying everyone who read the channel
+pub const CHANNEL_MENTION: &str = "channel";
+
+/// returns the byte ranges of the `@username` mentions in the given
+/// body, including the `@`, a mention starts a word so emails are not
+/// mentions, and a trailing dot ends the sentence rather than the name
+pub fn find_mentions(body: &str) -> Vec<Range<usize>> {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let mut mentions = Vec::new();
+    let mut previous = None;
+
+    for (start, c) in body.char_indices() {
+        let starts_word = previous.is_none_or(|previous: char| !is_name(previous));
+        previous = Some(c);
+        if c != '@' || !starts_word {
+            continue;
+        }
+
+        let name = &body[start + 1..];
+        let len = name.find(|c| !is_name(c)).unwrap_or(name.len());
+        let len = name[..len].trim_end_matches('.').len();
+        if len > 0 {
+            mentions.push(start..start + 1 + len);
+        }
+    }
+    mentions
+}
+
+/// stores the users mentioned by the given message body, `@channel`
+/// mentions everyone with a read marker in the channel, the author
+/// is never mentioned by their own message
+pub(super) fn store_mentions(
+    conn: &Connection,
+    message_id: i64,
+    channel_id: i32,
+    author_id: u32,
+    body: &str,
+) -> rusqlite::Result<()> {
+    for range in find_mentions(body) {
+        let name = &body[range.start + 1..range.end];
+        if name == CHANNEL_MENTION {
+            conn.execute(
+                "INSERT OR IGNORE INTO Mention (message_id, user_id) \
+                 SELECT ?1, user_id FROM ReadMarker WHERE channel_id = ?2 AND user_id != ?3",
+                (message_id, channel_id, author_id),
+            )?;
+        } else {
+            conn.execute(
+                "INSERT OR IGNORE INTO Mention (message_id, user_id) \
+                 SELECT ?1, id FROM \"User\" WHERE username = ?2 AND id != ?3",
+                (message_id, name, author_id),
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,478 @@
+use std::sync::LazyLock;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use super::Channel;
+use super::MessageEdit;
+use super::Reaction;
+use super::User;
+use super::mention::store_mentions;
+use crate::Database;
+use crate::FromRow;
+use crate::StorageError;
+use crate::StorageEvent;
+
+/// the message columns, selected from the `Message` table as `m`
+/// joined with the author from the `User` table as `u`, the reactions
+/// are aggregated as `emoji:count` pairs in the order they were first added
+pub(super) const MESSAGE_COLUMNS: &str = "m.id, m.channel_id, m.user_id, u.username AS author, \
+     m.body, m.created_at, m.parent_id, m.edited_at, m.deleted_at, \
+     (SELECT COUNT(*) FROM Message r WHERE r.parent_id = m.id) AS reply_count, \
+     (SELECT group_concat(user_id, ',') FROM Mention WHERE message_id = m.id) AS mentioned, \
+     (SELECT group_concat(emoji || ':' || count, ',') FROM \
+     (SELECT emoji, COUNT(*) AS count FROM Reaction WHERE message_id = m.id \
+     GROUP BY emoji ORDER BY MIN(rowid))) AS reactions";
+
+static SELECT_MESSAGE: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        "SELECT {} FROM Message m JOIN \"User\" u ON u.id = m.user_id",
+        MESSAGE_COLUMNS
+    )
+});
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    id: i64,
+    channel_id: i32,
+    user_id: u32,
+    author: String,
+    body: String,
+    created_at: i64,
+    parent_id: Option<i64>,
+    reply_count: usize,
+    edited_at: Option<i64>,
+    deleted_at: Option<i64>,
+    reactions: Vec<Reaction>,
+    /// the mentioned users ids
+    mentioned: Vec<u32>,
+}
+
+impl Message {
+    #[inline]
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    #[inline]
+    pub fn channel_id(&self) -> i32 {
+        self.channel_id
+    }
+
+    #[inline]
+    pub fn user_id(&self) -> u32 {
+        self.user_id
+    }
+
+    /// the username of the message author
+    #[inline]
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    #[inline]
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// unix timestamp in seconds of when the message was sent
+    #[inline]
+    pub fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    /// the thread root message id if the message is a reply
+    #[inline]
+    pub fn parent_id(&self) -> Option<i64> {
+        self.parent_id
+    }
+
+    /// how many replies the message thread has
+    #[inline]
+    pub fn reply_count(&self) -> usize {
+        self.reply_count
+    }
+
+    /// counts a new reply to the message thread, used to keep a loaded
+    /// message up to date with the replies published by the storage
+    #[inline]
+    pub fn count_reply(&mut self) {
+        self.reply_count += 1;
+    }
+
+    /// when the message was last edited, `None` if it was never edited
+    #[inline]
+    pub fn edited_at(&self) -> Option<i64> {
+        self.edited_at
+    }
+
+    /// deleted messages are tombstones, their body is empty
+    #[inline]
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// the message reactions counted by emoji
+    #[inline]
+    pub fn reactions(&self) -> &[Reaction] {
+        &self.reactions
+    }
+
+    /// returns true if the message mentions the given user, directly
+    /// or through `@channel`
+    #[inline]
+    pub fn mentions(&self, user: &User) -> bool {
+        self.mentioned.contains(&user.id())
+    }
+
+    /// stores a new message in the given channel, and notifies
+    /// all the storage subscribers about the new message
+    pub async fn create(
+        db: Database,
+        channel: &Channel,
+        user: &User,
+        body: &str,
+    ) -> Result<Message, StorageError> {
+        Self::insert(db, channel.id(), user, body, None).await
+    }
+
+    /// stores a reply to the given message thread, replying to a reply
+    /// adds the new reply to the same thread
+    pub async fn reply(
+        db: Database,
+        parent: &Message,
+        user: &User,
+        body: &str,
+    ) -> Result<Message, StorageError> {
+        let root_id = parent.parent_id.unwrap_or(parent.id);
+        Self::insert(db, parent.channel_id, user, body, Some(root_id)).await
+    }
+
+    async fn insert(
+        db: Database,
+        channel_id: i32,
+        user: &User,
+        body: &str,
+        parent_id: Option<i64>,
+    ) -> Result<Message, StorageError> {
+        let user_id = user.id();
+        let body = String::from(body);
+        let created_at = unix_now();
+
+        let message = db
+            .pool
+            .conn(move |conn| {
+                let tx = conn.unchecked_transaction()?;
+                tx.execute(
+                    "INSERT INTO Message (channel_id, user_id, body, created_at, parent_id) \
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    (channel_id, user_id, &body, created_at, parent_id),
+                )?;
+                let message_id = tx.last_insert_rowid();
+                store_mentions(&tx, message_id, channel_id, user_id, &body)?;
+                let message = tx.query_one(
+                    &format!("{} WHERE m.id = ?1", *SELECT_MESSAGE),
+                    [message_id],
+                    Self::from_row,
+                )?;
+                tx.commit()?;
+                Ok(message)
+            })
+            .await?;
+
+        db.publish(StorageEvent::MessageCreated(message.clone()));
+        Ok(message)
+    }
+
+    /// replaces the message body, the previous body is kept in the message
+    /// edits, only the message author can edit it
+    pub async fn edit(
+        db: Database,
+        message_id: i64,
+        user: &User,
+        body: &str,
+    ) -> Result<Message, StorageError> {
+        let user_id = user.id();
+        let body = String::from(body);
+        let edited_at = unix_now();
+
+        let message = db
+            .pool
+            .conn(move |conn| {
+                let tx = conn.unchecked_transaction()?;
+                let (author_id, channel_id, previous): (u32, i32, String) = tx.query_one(
+                    "SELECT user_id, channel_id, body FROM Message \
+                     WHERE id = ?1 AND deleted_at IS NULL",
+                    [message_id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )?;
+                if author_id != user_id {
+                    return Ok(None);
+                }
+
+                tx.execute(
+                    "INSERT INTO MessageEdit (message_id, body, edited_at) VALUES (?1, ?2, ?3)",
+                    (message_id, previous, edited_at),
+                )?;
+                tx.execute(
+                    "UPDATE Message SET body = ?2, edited_at = ?3 WHERE id = ?1",
+                    (message_id, &body, edited_at),
+                )?;
+                // the mentions follow the new body, the users
+                // already mentioned are kept
+                store_mentions(&tx, message_id, channel_id, author_id, &body)?;
+                let message = tx.query_one(
+                    &format!("{} WHERE m.id = ?1", *SELECT_MESSAGE),
+                    [message_id],
+                    Self::from_row,
+                )?;
+                tx.commit()?;
+                Ok(Some(message))
+            })
+            .await?
+            .ok_or(StorageError::PermissionDenied)?;
+
+        db.publish(StorageEvent::MessageEdited(message.clone()));
+        Ok(message)
+    }
+
+    /// replaces the message with a tombstone, the body and its edits are
+    /// removed, the message author and the moderators can delete it
+    pub async fn delete(
+        db: Database,
+        message_id: i64,
+        user: &User,
+    ) -> Result<Message, StorageError> {
+        let user_id = user.id();
+        let is_moderator = user.is_moderator();
+        let deleted_at = unix_now();
+
+        let message = db
+            .pool
+            .conn(move |conn| {
+                let tx = conn.unchecked_transaction()?;
+                let author_id: u32 = tx.query_one(
+                    "SELECT user_id FROM Message WHERE id = ?1 AND deleted_at IS NULL",
+                    [message_id],
+                    |row| row.get(0),
+                )?;
+                if author_id != user_id && !is_moderator {
+                    return Ok(None);
+                }
+
+                tx.execute(
+                    "DELETE FROM MessageEdit WHERE message_id = ?1",
+                    [message_id],
+                )?;
+                tx.execute("DELETE FROM Reaction WHERE message_id = ?1", [message_id])?;
+                tx.execute("DELETE FROM Mention WHERE message_id = ?1", [message_id])?;
+                tx.execute(
+                    "UPDATE Message SET body = '', deleted_at = ?2 WHERE id = ?1",
+                    (message_id, deleted_at),
+                )?;
+                let message = tx.query_one(
+                    &format!("{} WHERE m.id = ?1", *SELECT_MESSAGE),
+                    [message_id],
+                    Self::from_row,
+                )?;
+                tx.commit()?;
+                Ok(Some(message))
+            })
+            .await?
+            .ok_or(StorageError::PermissionDenied)?;
+
+        db.publish(StorageEvent::MessageDeleted(message.clone()));
+        Ok(message)
+    }
+
+    /// adds the user reaction to the message, or removes it if the user
+    /// already reacted with the same emoji, deleted messages can't be reacted to
+    pub async fn react(
+        db: Database,
+        message_id: i64,
+        user: &User,
+        emoji: &str,
+    ) -> Result<Message, StorageError> {
+        if !Reaction::is_valid_emoji(emoji) {
+            return Err(StorageError::ConstraintViolation(format!(
+                "`{}` is not an emoji shortcode",
+                emoji
+            )));
+        }
+        let user_id = user.id();
+        let emoji = String::from(emoji);
+
+        let message = db
+            .pool
+            .conn(move |conn| {
+                let tx = conn.unchecked_transaction()?;
+                tx.query_one(
+                    "SELECT id FROM Message WHERE id = ?1 AND deleted_at IS NULL",
+                    [message_id],
+                    |row| row.get::<_, i64>(0),
+                )?;
+
+                let removed = tx.execute(
+                    "DELETE FROM Reaction WHERE message_id = ?1 AND user_id = ?2 AND emoji = ?3",
+                    (message_id, user_id, &emoji),
+                )?;
+                if removed == 0 {
+                    tx.execute(
+                        "INSERT INTO Reaction (message_id, user_id, emoji) VALUES (?1, ?2, ?3)",
+                        (message_id, user_id, &emoji),
+                    )?;
+                }
+                let message = tx.query_one(
+                    &format!("{} WHERE m.id = ?1", *SELECT_MESSAGE),
+                    [message_id],
+                    Self::from_row,
+                )?;
+                tx.commit()?;
+                Ok(message)
+            })
+            .await?;
+
+        db.publish(StorageEvent::MessageReacted(message.clone()));
+        Ok(message)
+    }
+
+    /// returns the newest messages and replies mentioning the given
+    /// user, ordered from the newest to the oldest
+    pub async fn mentioning(
+        db: Database,
+        user: &User,
+        limit: usize,
+    ) -> Result<Vec<Message>, StorageError> {
+        let user_id = user.id();
+        db.pool
+            .conn(move |conn| {
+                let mut statement = conn.prepare(&format!(
+                    "{} JOIN Mention n ON n.message_id = m.id WHERE n.user_id = ?1 \
+                     ORDER BY m.id DESC LIMIT ?2",
+                    *SELECT_MESSAGE
+                ))?;
+                statement
+                    .query_map((user_id, limit), Self::from_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await
+            .map_err(StorageError::from)
+    }
+
+    /// returns the previous bodies of the given message, ordered
+    /// from the oldest to the newest edit
+    pub async fn edits(db: Database, message_id: i64) -> Result<Vec<MessageEdit>, StorageError> {
+        db.repository::<MessageEdit>()
+            .find_where("message_id = ?1", vec![message_id.into()])
+            .await
+    }
+
+    /// returns the replies of the given thread root message, ordered
+    /// from the oldest to the newest
+    pub async fn thread(db: Database, root_id: i64) -> Result<Vec<Message>, StorageError> {
+        db.pool
+            .conn(move |conn| {
+                let mut statement = conn.prepare(&format!(
+                    "{} WHERE m.parent_id = ?1 ORDER BY m.id ASC",
+                    *SELECT_MESSAGE
+                ))?;
+                statement
+                    .query_map([root_id], Self::from_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await
+            .map_err(StorageError::from)
+    }
+
+    /// returns the channel messages sent after the given unix timestamp, ordered from the
+    /// oldest to the newest, if there are more than `limit` messages, the newest are returned,
+    /// the thread replies are not part of the channel history
+    pub async fn history(
+        db: Database,
+        channel_id: i32,
+        since: i64,
+        limit: usize,
+    ) -> Result<Vec<Message>, StorageError> {
+        db.pool
+            .conn(move |conn| {
+                let mut statement = conn.prepare(&format!(
+                    "SELECT * FROM ({} WHERE m.channel_id = ?1 AND m.parent_id IS NULL \
+                     AND m.created_at >= ?2 \
+                     ORDER BY m.id DESC LIMIT ?3) ORDER BY id ASC",
+                    *SELECT_MESSAGE
+                ))?;
+                statement
+                    .query_map((channel_id, since, limit as i64), Self::from_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await
+            .map_err(StorageError::from)
+    }
+
+    /// returns the given message with up to `count` channel messages before it and
+    /// `count` messages after it, ordered from the oldest to the newest, like the
+    /// channel history the thread replies are not included
+    pub async fn around(
+        db: Database,
+        channel_id: i32,
+        message_id: i64,
+        count: usize,
+    ) -> Result<Vec<Message>, StorageError> {
+        db.pool
+            .conn(move |conn| {
+                let mut statement = conn.prepare(&format!(
+                    "SELECT * FROM ({select} WHERE m.channel_id = ?1 AND m.parent_id IS NULL \
+                     AND m.id <= ?2 ORDER BY m.id DESC LIMIT ?3 + 1) \
+                     UNION ALL \
+                     SELECT * FROM ({select} WHERE m.channel_id = ?1 AND m.parent_id IS NULL \
+                     AND m.id > ?2 ORDER BY m.id ASC LIMIT ?3) \
+                     ORDER BY id ASC",
+                    select = *SELECT_MESSAGE
+                ))?;
+                statement
+                    .query_map((channel_id, message_id, count as i64), |row| {
+                        Self::from_row(row)
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await
+            .map_err(StorageError::from)
+    }
+}
+
+impl FromRow for Message {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Message {
+            id: row.get("id")?,
+            channel_id: row.get("channel_id")?,
+            user_id: row.get("user_id")?,
+            author: row.get("author")?,
+            body: row.get("body")?,
+            created_at: row.get("created_at")?,
+            parent_id: row.get("parent_id")?,
+            reply_count: row.get("reply_count")?,
+            edited_at: row.get("edited_at")?,
+            deleted_at: row.get("deleted_at")?,
+            mentioned: row
+                .get::<_, Option<String>>("mentioned")?
+                .map(|mentioned| {
+                    mentioned
+                        .split(',')
+                        .filter_map(|id| id.parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            reactions: row
+                .get::<_, Option<String>>("reactions")?
+                .as_deref()
+                .map(Reaction::parse_aggregated)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
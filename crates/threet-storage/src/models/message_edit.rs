@@ -0,0 +1,35 @@
+use super::Model;
+
+/// a previous body of an edited message, stored
+/// every time the message is edited
+#[derive(Debug, Clone, Model)]
+#[model(table = "MessageEdit")]
+pub struct MessageEdit {
+    id: i64,
+    message_id: i64,
+    body: String,
+    edited_at: i64,
+}
+
+impl MessageEdit {
+    #[inline]
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    #[inline]
+    pub fn message_id(&self) -> i64 {
+        self.message_id
+    }
+
+    /// the message body before the edit
+    #[inline]
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    #[inline]
+    pub fn edited_at(&self) -> i64 {
+        self.edited_at
+    }
+}
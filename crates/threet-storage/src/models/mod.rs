@@ -1,13 +1,51 @@
 mod channel;
+mod mention;
+mod message;
+mod message_edit;
+mod reaction;
+mod read_marker;
+mod search;
 mod user;
 
 pub use channel::Channel;
+pub use mention::CHANNEL_MENTION;
+pub use mention::find_mentions;
+pub use message::Message;
+pub use message_edit::MessageEdit;
+pub use reaction::Reaction;
+pub use read_marker::ReadMarker;
+pub use read_marker::Unread;
+pub use search::HIGHLIGHT_END;
+pub use search::HIGHLIGHT_START;
+pub use search::SearchQuery;
+pub use search::SearchResult;
+pub use threet_storage_derive::Model;
 pub use user::User;
 
-pub(crate) trait Model: Send {
+use rusqlite::ToSql;
+use rusqlite::types::Value;
+
+use crate::FromRow;
+
+/// implemented on types stored in their own table, usually
+/// through `#[derive(Model)]` which also implements `FromRow`
+pub trait Model: FromRow + Send + 'static {
+    /// the primary key type
+    type Id: ToSql + Into<Value> + Clone + Send + 'static;
+
     /// returns the table name for current item
     fn table_name() -> &'static str;
 
     /// returns the querable fields for the current item
     fn fields() -> Vec<String>;
+
+    /// returns the primary key column name
+    fn primary_key() -> &'static str;
+
+    /// returns the primary key of the current item
+    fn primary_key_value(&self) -> Self::Id;
+
+    /// returns the column values for the current item,
+    /// the primary key is not included
+    fn values(&self) -> Vec<(&'static str, Value)>;
 }
@@ -0,0 +1,44 @@
+/// how many users reacted to a message with the same emoji, the
+/// emojis are stored as shortcodes like `+1` or `tada`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reaction {
+    emoji: String,
+    count: usize,
+}
+
+impl Reaction {
+    #[inline]
+    pub fn emoji(&self) -> &str {
+        &self.emoji
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// shortcodes are lowercase ascii letters, digits, `_`, `+` and `-`,
+    /// so they can be aggregated in a single column by the message queries
+    pub fn is_valid_emoji(emoji: &str) -> bool {
+        !emoji.is_empty()
+            && emoji.len() <= 32
+            && emoji
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_+-".contains(c))
+    }
+
+    /// parses the reactions aggregated as `emoji:count` pairs separated
+    /// by commas, the pairs that don't parse are skipped
+    pub(super) fn parse_aggregated(aggregated: &str) -> Vec<Reaction> {
+        aggregated
+            .split(',')
+            .filter_map(|pair| {
+                let (emoji, count) = pair.split_once(':')?;
+                Some(Reaction {
+                    emoji: emoji.to_string(),
+                    count: count.parse().ok()?,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,158 @@
+use super::User;
+use crate::Database;
+use crate::FromRow;
+use crate::StorageError;
+use crate::StorageEvent;
+
+/// the last message a user read in a channel, thread
+/// replies are not tracked by the markers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadMarker {
+    user_id: u32,
+    channel_id: i32,
+    message_id: i64,
+}
+
+/// the messages a user didn't read yet in a channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unread {
+    channel_id: i32,
+    messages: usize,
+    mentions: usize,
+}
+
+impl ReadMarker {
+    #[inline]
+    pub fn user_id(&self) -> u32 {
+        self.user_id
+    }
+
+    #[inline]
+    pub fn channel_id(&self) -> i32 {
+        self.channel_id
+    }
+
+    #[inline]
+    pub fn message_id(&self) -> i64 {
+        self.message_id
+    }
+
+    /// returns the user marker in the given channel,
+    /// `None` if the user never read the channel
+    pub async fn last_read(
+        db: Database,
+        user: &User,
+        channel_id: i32,
+    ) -> Result<Option<ReadMarker>, StorageError> {
+        let user_id = user.id();
+        let marker = db
+            .pool
+            .conn(move |conn| {
+                conn.query_one(
+                    "SELECT user_id, channel_id, message_id FROM ReadMarker \
+                     WHERE user_id = ?1 AND channel_id = ?2",
+                    (user_id, channel_id),
+                    Self::from_row,
+                )
+            })
+            .await
+            .map_err(StorageError::from);
+
+        match marker {
+            Ok(marker) => Ok(Some(marker)),
+            Err(StorageError::NotFound) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// marks the channel as read up to the given message, the marker
+    /// never moves back so an older message doesn't mark anything unread
+    pub async fn mark(
+        db: Database,
+        user: &User,
+        channel_id: i32,
+        message_id: i64,
+    ) -> Result<ReadMarker, StorageError> {
+        let user_id = user.id();
+        let marker = db
+            .pool
+            .conn(move |conn| {
+                conn.query_one(
+                    "INSERT INTO ReadMarker (user_id, channel_id, message_id) VALUES (?1, ?2, ?3) \
+                     ON CONFLICT (user_id, channel_id) \
+                     DO UPDATE SET message_id = MAX(message_id, excluded.message_id) \
+                     RETURNING user_id, channel_id, message_id",
+                    (user_id, channel_id, message_id),
+                    Self::from_row,
+                )
+            })
+            .await?;
+
+        db.publish(StorageEvent::ChannelRead(marker.clone()));
+        Ok(marker)
+    }
+
+    /// counts the messages after the user markers in every channel, and the
+    /// unread messages mentioning the user, the user own messages are not counted
+    pub async fn unread(db: Database, user: &User) -> Result<Vec<Unread>, StorageError> {
+        let user_id = user.id();
+
+        db.pool
+            .conn(move |conn| {
+                // the counts only scan the channel messages after the
+                // marker through the channel index
+                let mut statement = conn.prepare(
+                    "SELECT c.id AS channel_id, \
+                     (SELECT COUNT(*) FROM Message m WHERE m.channel_id = c.id \
+                     AND m.id > COALESCE(r.message_id, 0) AND m.parent_id IS NULL \
+                     AND m.deleted_at IS NULL AND m.user_id != ?1) AS messages, \
+                     (SELECT COUNT(*) FROM Mention n JOIN Message m ON m.id = n.message_id \
+                     WHERE n.user_id = ?1 AND m.channel_id = c.id \
+                     AND m.id > COALESCE(r.message_id, 0)) AS mentions \
+                     FROM Channel c \
+                     LEFT JOIN ReadMarker r ON r.channel_id = c.id AND r.user_id = ?1 \
+                     ORDER BY c.id",
+                )?;
+                statement
+                    .query_map([user_id], |row| {
+                        Ok(Unread {
+                            channel_id: row.get("channel_id")?,
+                            messages: row.get("messages")?,
+                            mentions: row.get("mentions")?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await
+            .map_err(StorageError::from)
+    }
+}
+
+impl Unread {
+    #[inline]
+    pub fn channel_id(&self) -> i32 {
+        self.channel_id
+    }
+
+    /// the unread channel messages, the thread replies are not counted
+    #[inline]
+    pub fn messages(&self) -> usize {
+        self.messages
+    }
+
+    /// the unread messages and replies mentioning the user
+    #[inline]
+    pub fn mentions(&self) -> usize {
+        self.mentions
+    }
+}
+
+impl FromRow for ReadMarker {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(ReadMarker {
+            user_id: row.get("user_id")?,
+            channel_id: row.get("channel_id")?,
+            message_id: row.get("message_id")?,
+        })
+    }
+}
@@ -0,0 +1,206 @@
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+
+use super::Message;
+use super::message::MESSAGE_COLUMNS;
+use crate::Database;
+use crate::FromRow;
+use crate::StorageError;
+
+/// marks the start of a matched term in the search snippets
+pub const HIGHLIGHT_START: char = '\u{2}';
+
+/// marks the end of a matched term in the search snippets
+pub const HIGHLIGHT_END: char = '\u{3}';
+
+/// default amount of results returned by a search
+const DEFAULT_LIMIT: usize = 50;
+
+/// how many tokens the snippets show around the matched terms
+const SNIPPET_TOKENS: usize = 12;
+
+/// a full text search over the message bodies, the text terms are matched
+/// as prefixes so partial words match too, and the filters narrow down
+/// the results
+///
+/// ```ignore
+/// let query = SearchQuery::new("deploy friday").channel(general.id()).author("bob");
+/// let results = Message::search(db, query).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    text: String,
+    channel_id: Option<i32>,
+    author: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    limit: usize,
+}
+
+impl SearchQuery {
+    pub fn new(text: &str) -> Self {
+        SearchQuery {
+            text: String::from(text),
+            channel_id: None,
+            author: None,
+            since: None,
+            until: None,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+
+    /// only messages sent to the given channel
+    #[inline]
+    pub fn channel(mut self, channel_id: i32) -> Self {
+        self.channel_id = Some(channel_id);
+        self
+    }
+
+    /// only messages sent by the given username
+    #[inline]
+    pub fn author(mut self, username: &str) -> Self {
+        self.author = Some(String::from(username));
+        self
+    }
+
+    /// only messages sent at or after the given unix timestamp
+    #[inline]
+    pub fn since(mut self, timestamp: i64) -> Self {
+        self.since = Some(timestamp);
+        self
+    }
+
+    /// only messages sent before the given unix timestamp
+    #[inline]
+    pub fn until(mut self, timestamp: i64) -> Self {
+        self.until = Some(timestamp);
+        self
+    }
+
+    /// max amount of results
+    #[inline]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// the fts5 match expression for the query text, every term is quoted
+    /// so the user text can't be read as the fts5 query syntax
+    fn match_expression(&self) -> Option<String> {
+        let terms = self
+            .text
+            .split_whitespace()
+            .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+            .collect::<Vec<_>>();
+        (!terms.is_empty()).then(|| terms.join(" "))
+    }
+}
+
+/// a message matching a search, results are ordered from the most relevant
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    message: Message,
+    snippet: String,
+    rank: f64,
+}
+
+impl SearchResult {
+    #[inline]
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// the part of the message body around the matched terms, the terms
+    /// are wrapped with `HIGHLIGHT_START` and `HIGHLIGHT_END`
+    #[inline]
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+
+    /// the bm25 rank of the result, lower is more relevant
+    #[inline]
+    pub fn rank(&self) -> f64 {
+        self.rank
+    }
+
+    /// splits the snippet to its parts, the boolean is true
+    /// for the parts that matched the search terms
+    pub fn highlights(&self) -> Vec<(&str, bool)> {
+        let mut parts = Vec::new();
+        let mut rest = self.snippet.as_str();
+        while let Some(start) = rest.find(HIGHLIGHT_START) {
+            parts.push((&rest[..start], false));
+            rest = &rest[start + HIGHLIGHT_START.len_utf8()..];
+
+            let end = rest.find(HIGHLIGHT_END).unwrap_or(rest.len());
+            parts.push((&rest[..end], true));
+            rest = rest
+                .get(end + HIGHLIGHT_END.len_utf8()..)
+                .unwrap_or_default();
+        }
+        parts.push((rest, false));
+        parts.retain(|(part, _)| !part.is_empty());
+        parts
+    }
+}
+
+impl FromRow for SearchResult {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(SearchResult {
+            message: Message::from_row(row)?,
+            snippet: row.get("snippet")?,
+            rank: row.get("rank")?,
+        })
+    }
+}
+
+impl Message {
+    /// searches the message bodies, the results are ranked by their relevance
+    pub async fn search(
+        db: Database,
+        query: SearchQuery,
+    ) -> Result<Vec<SearchResult>, StorageError> {
+        let Some(expression) = query.match_expression() else {
+            return Ok(Vec::new());
+        };
+
+        let mut sql = format!(
+            "SELECT {}, snippet(MessageSearch, 0, '{}', '{}', '…', {}) AS snippet, \
+             bm25(MessageSearch) AS rank \
+             FROM MessageSearch JOIN Message m ON m.id = MessageSearch.rowid \
+             JOIN \"User\" u ON u.id = m.user_id \
+             WHERE MessageSearch MATCH ?",
+            MESSAGE_COLUMNS, HIGHLIGHT_START, HIGHLIGHT_END, SNIPPET_TOKENS
+        );
+        let mut params = vec![Value::from(expression)];
+
+        if let Some(channel_id) = query.channel_id {
+            sql.push_str(" AND m.channel_id = ?");
+            params.push(Value::from(channel_id));
+        }
+        if let Some(author) = query.author {
+            sql.push_str(" AND u.username = ?");
+            params.push(Value::from(author));
+        }
+        if let Some(since) = query.since {
+            sql.push_str(" AND m.created_at >= ?");
+            params.push(Value::from(since));
+        }
+        if let Some(until) = query.until {
+            sql.push_str(" AND m.created_at < ?");
+            params.push(Value::from(until));
+        }
+        sql.push_str(" ORDER BY rank LIMIT ?");
+        params.push(Value::from(query.limit as i64));
+
+        db.pool
+            .conn(move |conn| {
+                let mut statement = conn.prepare(&sql)?;
+                statement
+                    .query_map(params_from_iter(params), SearchResult::from_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await
+            .map_err(StorageError::from)
+    }
+}
@@ -1,32 +1,168 @@
 use sha2::Digest;
 
-use crate::{Database, FromRow};
+use super::Model;
+use crate::{Database, FromRow, StorageError};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Model)]
+#[model(table = "User")]
 pub struct User {
     id: u32,
     username: String,
+    role: String,
 }
 
 impl User {
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    #[inline]
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// admins can manage other users sessions
+    #[inline]
+    pub fn is_admin(&self) -> bool {
+        self.role == "admin"
+    }
+
+    /// moderators can delete other users messages, admins
+    /// are moderators too
+    #[inline]
+    pub fn is_moderator(&self) -> bool {
+        self.role == "moderator" || self.is_admin()
+    }
+
+    /// changes the user role, e.g `member`, `moderator` or `admin`, the
+    /// change is stored once the user is updated through the repository
+    #[inline]
+    pub fn set_role(&mut self, role: &str) {
+        self.role = String::from(role);
+    }
+
+    /// returns the user with the given credentials, wrong credentials
+    /// are reported as `StorageError::NotFound`
     pub async fn by_username_password(
         db: Database,
         username: &str,
         password: &str,
-    ) -> Option<User> {
+    ) -> Result<User, StorageError> {
+        let username = String::from(username);
+        let hashed_password = Self::digest_password(password);
+
+        db.pool
+            .conn(move |conn| {
+                conn.query_one(
+                    "SELECT id, username, role FROM \"User\" WHERE username = ?1 AND password = ?2",
+                    (username, hashed_password),
+                    Self::from_row,
+                )
+            })
+            .await
+            .map_err(StorageError::from)
+    }
+
+    /// returns the user with the given username if it authorized the given
+    /// public key, unknown keys are reported as `StorageError::NotFound`
+    pub async fn by_username_key(
+        db: Database,
+        username: &str,
+        key: &str,
+    ) -> Result<User, StorageError> {
+        let username = String::from(username);
+        let key = String::from(key);
+
+        db.pool
+            .conn(move |conn| {
+                conn.query_one(
+                    "SELECT u.id, u.username, u.role FROM \"User\" u \
+                     JOIN UserKey k ON k.user_id = u.id WHERE u.username = ?1 AND k.key = ?2",
+                    (username, key),
+                    Self::from_row,
+                )
+            })
+            .await
+            .map_err(StorageError::from)
+    }
+
+    /// authorizes the given public key to log in as the user, a key
+    /// already authorized is reported as `StorageError::ConstraintViolation`
+    pub async fn add_key(&self, db: Database, key: &str) -> Result<(), StorageError> {
+        let user_id = self.id;
+        let key = String::from(key);
+
+        db.pool
+            .conn(move |conn| {
+                conn.execute(
+                    "INSERT INTO UserKey (user_id, key) VALUES (?1, ?2)",
+                    (user_id, key),
+                )
+            })
+            .await
+            .map(|_| ())
+            .map_err(StorageError::from)
+    }
+
+    /// removes the given public key, a key the user never authorized
+    /// is reported as `StorageError::NotFound`
+    pub async fn remove_key(&self, db: Database, key: &str) -> Result<(), StorageError> {
+        let user_id = self.id;
+        let key = String::from(key);
+
+        let removed = db
+            .pool
+            .conn(move |conn| {
+                conn.execute(
+                    "DELETE FROM UserKey WHERE user_id = ?1 AND key = ?2",
+                    (user_id, key),
+                )
+            })
+            .await
+            .map_err(StorageError::from)?;
+        match removed {
+            0 => Err(StorageError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// returns the public keys the user authorized, oldest first
+    pub async fn keys(&self, db: Database) -> Result<Vec<String>, StorageError> {
+        let user_id = self.id;
+
+        db.pool
+            .conn(move |conn| {
+                let mut statement =
+                    conn.prepare("SELECT key FROM UserKey WHERE user_id = ?1 ORDER BY rowid")?;
+                statement
+                    .query_map([user_id], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await
+            .map_err(StorageError::from)
+    }
+
+    /// creates a new member with the given credentials, a taken username
+    /// is reported as `StorageError::ConstraintViolation`
+    pub async fn create(
+        db: Database,
+        username: &str,
+        password: &str,
+    ) -> Result<User, StorageError> {
         let username = String::from(username);
         let hashed_password = Self::digest_password(password);
 
         db.pool
             .conn(move |conn| {
                 conn.query_one(
-                    "SELECT id, username FROM \"User\" WHERE username = ?1 AND password = ?2",
+                    "INSERT INTO \"User\" (username, password) VALUES (?1, ?2) RETURNING id, username, role",
                     (username, hashed_password),
-                    |row| Self::from_row(row),
+                    Self::from_row,
                 )
             })
             .await
-            .ok()
+            .map_err(StorageError::from)
     }
 
     // TODO: move this function to a better position
@@ -34,11 +170,3 @@ impl User {
         format!("{:x}", sha2::Sha256::digest(password))
     }
 }
-
-impl FromRow for User {
-    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
-        let id = row.get("id")?;
-        let username = row.get("username")?;
-        Ok(User { id, username })
-    }
-}
@@ -0,0 +1,190 @@
+use std::marker::PhantomData;
+
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+
+use crate::Database;
+use crate::StorageError;
+use crate::models::Model;
+use crate::preper_select_statement_string;
+
+/// a page of rows, pages are counted from zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub index: usize,
+    pub size: usize,
+}
+
+impl Page {
+    #[inline]
+    pub fn new(index: usize, size: usize) -> Self {
+        Self { index, size }
+    }
+
+    /// the page after this one with the same size
+    #[inline]
+    pub fn next(self) -> Self {
+        Self::new(self.index + 1, self.size)
+    }
+
+    #[inline]
+    fn offset(self) -> usize {
+        self.index * self.size
+    }
+}
+
+/// typed queries for a single model, the statements are built from the
+/// model table name and fields, so models don't have to hand-write the
+/// sql for the common queries
+///
+/// ```ignore
+/// let channels = db.repository::<Channel>();
+/// let general = channels.find_where("name = ?1", vec!["general".into()]).await?;
+/// ```
+pub struct Repository<T> {
+    db: Database,
+    _model: PhantomData<fn() -> T>,
+}
+
+impl<T: Model> Repository<T> {
+    pub(crate) fn new(db: Database) -> Self {
+        Self {
+            db,
+            _model: PhantomData,
+        }
+    }
+
+    /// returns the row with the given primary key
+    pub async fn find_by_id(&self, id: T::Id) -> Result<T, StorageError> {
+        let query = format!(
+            "{} WHERE {} = ?1",
+            preper_select_statement_string::<T>(),
+            T::primary_key()
+        );
+
+        let item = self
+            .db
+            .pool
+            .conn(move |conn| conn.query_one(&query, [id], |row| T::from_row(row)))
+            .await?;
+        Ok(item)
+    }
+
+    /// returns the rows matching the given condition, the condition is a sql
+    /// expression that refers to the parameters with `?1`, `?2`, ... in order
+    pub async fn find_where(
+        &self,
+        condition: &str,
+        params: Vec<Value>,
+    ) -> Result<Vec<T>, StorageError> {
+        let query = format!(
+            "{} WHERE {} ORDER BY {}",
+            preper_select_statement_string::<T>(),
+            condition,
+            T::primary_key()
+        );
+        self.query(query, params).await
+    }
+
+    /// returns the given page of rows, ordered by the primary key
+    pub async fn list(&self, page: Page) -> Result<Vec<T>, StorageError> {
+        let query = format!(
+            "{} ORDER BY {} LIMIT ?1 OFFSET ?2",
+            preper_select_statement_string::<T>(),
+            T::primary_key()
+        );
+        let params = vec![
+            Value::from(page.size as i64),
+            Value::from(page.offset() as i64),
+        ];
+        self.query(query, params).await
+    }
+
+    /// stores a new row with the item values, the primary key is left for
+    /// the database to assign, and the stored row is returned
+    pub async fn insert(&self, item: &T) -> Result<T, StorageError> {
+        let (columns, values): (Vec<_>, Vec<_>) = item.values().into_iter().unzip();
+        let placeholders = (1..=values.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>();
+        let query = format!(
+            "INSERT INTO \"{}\" ({}) VALUES ({}) RETURNING {}",
+            T::table_name(),
+            columns.join(", "),
+            placeholders.join(", "),
+            T::fields().join(", ")
+        );
+
+        let item = self
+            .db
+            .pool
+            .conn(move |conn| {
+                conn.query_one(&query, params_from_iter(values), |row| T::from_row(row))
+            })
+            .await?;
+        Ok(item)
+    }
+
+    /// writes the item values to the row with the same primary key
+    pub async fn update(&self, item: &T) -> Result<(), StorageError> {
+        let (columns, mut values): (Vec<_>, Vec<_>) = item.values().into_iter().unzip();
+        let assignments = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| format!("{} = ?{}", column, i + 1))
+            .collect::<Vec<_>>();
+        let query = format!(
+            "UPDATE \"{}\" SET {} WHERE {} = ?{}",
+            T::table_name(),
+            assignments.join(", "),
+            T::primary_key(),
+            values.len() + 1
+        );
+        values.push(item.primary_key_value().into());
+
+        let updated = self
+            .db
+            .pool
+            .conn(move |conn| conn.execute(&query, params_from_iter(values)))
+            .await?;
+
+        match updated {
+            0 => Err(StorageError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// removes the row with the given primary key
+    pub async fn delete(&self, id: T::Id) -> Result<(), StorageError> {
+        let query = format!(
+            "DELETE FROM \"{}\" WHERE {} = ?1",
+            T::table_name(),
+            T::primary_key()
+        );
+
+        let deleted = self
+            .db
+            .pool
+            .conn(move |conn| conn.execute(&query, [id]))
+            .await?;
+
+        match deleted {
+            0 => Err(StorageError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    async fn query(&self, query: String, params: Vec<Value>) -> Result<Vec<T>, StorageError> {
+        let items = self
+            .db
+            .pool
+            .conn(move |conn| {
+                let mut statement = conn.prepare(&query)?;
+                statement
+                    .query_map(params_from_iter(params), |row| T::from_row(row))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await?;
+        Ok(items)
+    }
+}
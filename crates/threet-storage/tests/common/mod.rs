@@ -0,0 +1,26 @@
+use threet_storage::Database;
+use threet_storage::DatabaseBuilder;
+use threet_storage::models::Channel;
+use threet_storage::models::User;
+
+/// an empty in-memory database, every call gets its own database
+pub async fn database() -> Database {
+    DatabaseBuilder::default()
+        .in_memory()
+        .build()
+        .await
+        .expect("couldn't create in-memory database")
+}
+
+/// a database with two users, bob and alice, and an empty `#general` channel
+pub async fn setup() -> (Database, User, User, Channel) {
+    let db = database().await;
+    let bob = User::create(db.clone(), "bob", "hunter2").await.unwrap();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = db
+        .repository::<Channel>()
+        .insert(&Channel::new("general"))
+        .await
+        .unwrap();
+    (db, bob, alice, general)
+}
@@ -0,0 +1,111 @@
+use threet_storage::Database;
+use threet_storage::StorageError;
+use threet_storage::StorageEvent;
+use threet_storage::models::Message;
+use threet_storage::models::SearchQuery;
+use threet_storage::models::User;
+
+mod common;
+
+/// a database with two users and a message from bob
+async fn conversation() -> (Database, User, User, Message) {
+    let (db, bob, alice, general) = common::setup().await;
+    let message = Message::create(db.clone(), &general, &bob, "deploy on fridya")
+        .await
+        .unwrap();
+    (db, bob, alice, message)
+}
+
+#[tokio::test]
+async fn edits_keep_the_previous_bodies() {
+    let (db, bob, _, message) = conversation().await;
+    assert_eq!(message.edited_at(), None);
+
+    Message::edit(db.clone(), message.id(), &bob, "deploy on friday")
+        .await
+        .unwrap();
+    let edited = Message::edit(db.clone(), message.id(), &bob, "deploy on monday")
+        .await
+        .unwrap();
+    assert_eq!(edited.body(), "deploy on monday");
+    assert!(edited.edited_at().is_some());
+
+    let edits = Message::edits(db, message.id()).await.unwrap();
+    let bodies = edits.iter().map(|edit| edit.body()).collect::<Vec<_>>();
+    assert_eq!(bodies, ["deploy on fridya", "deploy on friday"]);
+}
+
+#[tokio::test]
+async fn only_the_author_can_edit() {
+    let (db, _, alice, message) = conversation().await;
+
+    let result = Message::edit(db.clone(), message.id(), &alice, "hijacked").await;
+    assert!(matches!(result, Err(StorageError::PermissionDenied)));
+    assert!(Message::edits(db, message.id()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn deleted_messages_become_tombstones() {
+    let (db, bob, _, message) = conversation().await;
+    Message::edit(db.clone(), message.id(), &bob, "deploy on friday")
+        .await
+        .unwrap();
+
+    let deleted = Message::delete(db.clone(), message.id(), &bob)
+        .await
+        .unwrap();
+    assert!(deleted.is_deleted());
+    assert_eq!(deleted.body(), "");
+
+    // the tombstone stays in the history, without its edits or search matches
+    let history = Message::history(db.clone(), message.channel_id(), 0, 10)
+        .await
+        .unwrap();
+    assert!(history[0].is_deleted());
+    assert!(
+        Message::edits(db.clone(), message.id())
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    let results = Message::search(db.clone(), SearchQuery::new("deploy"))
+        .await
+        .unwrap();
+    assert!(results.is_empty());
+
+    let result = Message::edit(db, message.id(), &bob, "back").await;
+    assert!(matches!(result, Err(StorageError::NotFound)));
+}
+
+#[tokio::test]
+async fn moderators_can_delete_other_users_messages() {
+    let (db, _, mut alice, message) = conversation().await;
+
+    let result = Message::delete(db.clone(), message.id(), &alice).await;
+    assert!(matches!(result, Err(StorageError::PermissionDenied)));
+
+    alice.set_role("moderator");
+    db.repository::<User>().update(&alice).await.unwrap();
+    let deleted = Message::delete(db, message.id(), &alice).await.unwrap();
+    assert!(deleted.is_deleted());
+}
+
+#[tokio::test]
+async fn edits_and_deletions_are_published() {
+    let (db, bob, _, message) = conversation().await;
+    let mut events = db.subscribe();
+
+    Message::edit(db.clone(), message.id(), &bob, "deploy on friday")
+        .await
+        .unwrap();
+    Message::delete(db, message.id(), &bob).await.unwrap();
+
+    match events.recv().await.unwrap() {
+        StorageEvent::MessageEdited(edited) => assert_eq!(edited.body(), "deploy on friday"),
+        event => panic!("unexpected event {:?}", event),
+    }
+    match events.recv().await.unwrap() {
+        StorageEvent::MessageDeleted(deleted) => assert!(deleted.is_deleted()),
+        event => panic!("unexpected event {:?}", event),
+    }
+}
@@ -0,0 +1,89 @@
+use threet_storage::Database;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+use threet_storage::models::ReadMarker;
+use threet_storage::models::User;
+use threet_storage::models::find_mentions;
+
+mod common;
+
+/// a database with three users and an empty channel
+async fn setup() -> (Database, [User; 3], Channel) {
+    let (db, bob, alice, general) = common::setup().await;
+    let carol = User::create(db.clone(), "carol", "pass").await.unwrap();
+    (db, [bob, alice, carol], general)
+}
+
+fn mentions(body: &str) -> Vec<&str> {
+    find_mentions(body)
+        .into_iter()
+        .map(|range| &body[range])
+        .collect()
+}
+
+#[test]
+fn finds_mentions_at_word_starts() {
+    assert_eq!(mentions("@bob ping"), ["@bob"]);
+    assert_eq!(mentions("thanks @alice."), ["@alice"]);
+    assert_eq!(mentions("@bob,@carol: hi"), ["@bob", "@carol"]);
+    assert_eq!(mentions("mail bob@example.com"), Vec::<&str>::new());
+    assert_eq!(mentions("just an @ sign"), Vec::<&str>::new());
+    assert_eq!(mentions("ünïcode @élan"), Vec::<&str>::new());
+}
+
+#[tokio::test]
+async fn stores_the_mentioned_users() {
+    let (db, [bob, alice, carol], general) = setup().await;
+
+    let message = Message::create(db.clone(), &general, &bob, "@alice @bob @nobody look")
+        .await
+        .unwrap();
+    assert!(message.mentions(&alice));
+    // the author is not mentioned by their own message
+    assert!(!message.mentions(&bob));
+    assert!(!message.mentions(&carol));
+
+    let mentioning = Message::mentioning(db, &alice, 10).await.unwrap();
+    assert_eq!(mentioning.len(), 1);
+    assert_eq!(mentioning[0].id(), message.id());
+}
+
+#[tokio::test]
+async fn channel_mentions_everyone_who_read_the_channel() {
+    let (db, [bob, alice, carol], general) = setup().await;
+    let first = Message::create(db.clone(), &general, &bob, "hi")
+        .await
+        .unwrap();
+    ReadMarker::mark(db.clone(), &alice, general.id(), first.id())
+        .await
+        .unwrap();
+
+    let message = Message::create(db, &general, &bob, "@channel deploy at 5")
+        .await
+        .unwrap();
+    assert!(message.mentions(&alice));
+    assert!(!message.mentions(&carol));
+}
+
+#[tokio::test]
+async fn mentions_follow_edits_and_deletions() {
+    let (db, [bob, alice, carol], general) = setup().await;
+    let message = Message::create(db.clone(), &general, &bob, "@alice ping")
+        .await
+        .unwrap();
+
+    let edited = Message::edit(db.clone(), message.id(), &bob, "@alice @carol ping")
+        .await
+        .unwrap();
+    assert!(edited.mentions(&carol));
+
+    Message::delete(db.clone(), message.id(), &bob)
+        .await
+        .unwrap();
+    assert!(
+        Message::mentioning(db, &alice, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,84 @@
+use sha2::Digest;
+use threet_storage::BuildError;
+use threet_storage::DatabaseBuilder;
+use threet_storage::StorageError;
+use threet_storage::models::User;
+
+#[tokio::test]
+async fn upgrades_the_databases_created_before_the_roles() {
+    let path = std::env::temp_dir().join(format!("threet-migration-{}.sqlite", std::process::id()));
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE User (id INTEGER PRIMARY KEY, username TEXT, password TEXT);
+         CREATE TABLE Channel (id INTEGER PRIMARY KEY, name TEXT);",
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO User (username, password) VALUES ('bob', ?1)",
+        [format!("{:x}", sha2::Sha256::digest("hunter2"))],
+    )
+    .unwrap();
+    drop(conn);
+
+    // opening the database again doesn't migrate it twice
+    for _ in 0..2 {
+        let db = DatabaseBuilder::default()
+            .path(&path)
+            .build()
+            .await
+            .unwrap();
+        let bob = User::by_username_password(db, "bob", "hunter2")
+            .await
+            .unwrap();
+        assert!(!bob.is_admin());
+        assert!(!bob.is_moderator());
+    }
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
+
+#[tokio::test]
+async fn reports_the_duplicate_usernames_of_older_databases() {
+    let path = std::env::temp_dir().join(format!(
+        "threet-migration-duplicates-{}.sqlite",
+        std::process::id()
+    ));
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE User (id INTEGER PRIMARY KEY, username TEXT, password TEXT);
+         CREATE TABLE Channel (id INTEGER PRIMARY KEY, name TEXT);
+         INSERT INTO User (username, password) VALUES ('bob', ''), ('bob', ''), ('alice', '');",
+    )
+    .unwrap();
+
+    let err = DatabaseBuilder::default()
+        .path(&path)
+        .build()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        &err,
+        BuildError::Duplicates { table: "User", names } if names == &["bob"]
+    ));
+    assert!(err.to_string().contains("bob"));
+
+    // once renamed the database is upgraded, and the usernames stay unique
+    conn.execute("UPDATE User SET username = 'bobby' WHERE id = 2", [])
+        .unwrap();
+    drop(conn);
+    let db = DatabaseBuilder::default()
+        .path(&path)
+        .build()
+        .await
+        .unwrap();
+    assert!(matches!(
+        User::create(db, "bob", "hunter2").await,
+        Err(StorageError::ConstraintViolation(_))
+    ));
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
@@ -0,0 +1,91 @@
+use threet_storage::Database;
+use threet_storage::StorageError;
+use threet_storage::StorageEvent;
+use threet_storage::models::Message;
+use threet_storage::models::User;
+
+mod common;
+
+/// a database with two users and a message from bob
+async fn conversation() -> (Database, User, User, Message) {
+    let (db, bob, alice, general) = common::setup().await;
+    let message = Message::create(db.clone(), &general, &bob, "shipped")
+        .await
+        .unwrap();
+    (db, bob, alice, message)
+}
+
+fn counts(message: &Message) -> Vec<(&str, usize)> {
+    message
+        .reactions()
+        .iter()
+        .map(|reaction| (reaction.emoji(), reaction.count()))
+        .collect()
+}
+
+#[tokio::test]
+async fn reactions_are_counted_by_emoji() {
+    let (db, bob, alice, message) = conversation().await;
+
+    Message::react(db.clone(), message.id(), &bob, "tada")
+        .await
+        .unwrap();
+    Message::react(db.clone(), message.id(), &alice, "+1")
+        .await
+        .unwrap();
+    let reacted = Message::react(db.clone(), message.id(), &alice, "tada")
+        .await
+        .unwrap();
+    assert_eq!(counts(&reacted), [("tada", 2), ("+1", 1)]);
+
+    let history = Message::history(db, message.channel_id(), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(counts(&history[0]), [("tada", 2), ("+1", 1)]);
+}
+
+#[tokio::test]
+async fn reacting_twice_removes_the_reaction() {
+    let (db, bob, _, message) = conversation().await;
+
+    Message::react(db.clone(), message.id(), &bob, "eyes")
+        .await
+        .unwrap();
+    let reacted = Message::react(db, message.id(), &bob, "eyes")
+        .await
+        .unwrap();
+    assert!(reacted.reactions().is_empty());
+}
+
+#[tokio::test]
+async fn rejects_invalid_shortcodes_and_deleted_messages() {
+    let (db, bob, _, message) = conversation().await;
+
+    let result = Message::react(db.clone(), message.id(), &bob, "no:pe").await;
+    assert!(matches!(result, Err(StorageError::ConstraintViolation(_))));
+
+    Message::react(db.clone(), message.id(), &bob, "+1")
+        .await
+        .unwrap();
+    let deleted = Message::delete(db.clone(), message.id(), &bob)
+        .await
+        .unwrap();
+    assert!(deleted.reactions().is_empty());
+    let result = Message::react(db, message.id(), &bob, "+1").await;
+    assert!(matches!(result, Err(StorageError::NotFound)));
+}
+
+#[tokio::test]
+async fn reactions_are_published() {
+    let (db, bob, _, message) = conversation().await;
+    let mut events = db.subscribe();
+
+    Message::react(db, message.id(), &bob, "fire")
+        .await
+        .unwrap();
+
+    match events.recv().await.unwrap() {
+        StorageEvent::MessageReacted(reacted) => assert_eq!(counts(&reacted), [("fire", 1)]),
+        event => panic!("unexpected event {:?}", event),
+    }
+}
@@ -0,0 +1,74 @@
+use threet_storage::models::Message;
+use threet_storage::models::ReadMarker;
+
+mod common;
+
+#[tokio::test]
+async fn markers_only_move_forward() {
+    let (db, bob, _, general) = common::setup().await;
+    assert_eq!(
+        ReadMarker::last_read(db.clone(), &bob, general.id())
+            .await
+            .unwrap(),
+        None
+    );
+
+    ReadMarker::mark(db.clone(), &bob, general.id(), 5)
+        .await
+        .unwrap();
+    let marker = ReadMarker::mark(db.clone(), &bob, general.id(), 3)
+        .await
+        .unwrap();
+    assert_eq!(marker.message_id(), 5);
+
+    let marker = ReadMarker::last_read(db, &bob, general.id())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(marker.message_id(), 5);
+}
+
+#[tokio::test]
+async fn counts_the_messages_after_the_marker() {
+    let (db, bob, alice, general) = common::setup().await;
+    let first = Message::create(db.clone(), &general, &alice, "morning")
+        .await
+        .unwrap();
+    ReadMarker::mark(db.clone(), &bob, general.id(), first.id())
+        .await
+        .unwrap();
+
+    let unread = Message::create(db.clone(), &general, &alice, "standup?")
+        .await
+        .unwrap();
+    Message::create(db.clone(), &general, &alice, "@bob are you there")
+        .await
+        .unwrap();
+    // replies are only counted as mentions, own messages are not counted
+    Message::reply(db.clone(), &unread, &alice, "ping @bob")
+        .await
+        .unwrap();
+    Message::create(db.clone(), &general, &bob, "here")
+        .await
+        .unwrap();
+
+    let counts = ReadMarker::unread(db, &bob).await.unwrap();
+    assert_eq!(counts.len(), 1);
+    assert_eq!(counts[0].channel_id(), general.id());
+    assert_eq!(counts[0].messages(), 2);
+    assert_eq!(counts[0].mentions(), 2);
+}
+
+#[tokio::test]
+async fn channels_never_read_are_all_unread() {
+    let (db, bob, alice, general) = common::setup().await;
+    for body in ["one", "two", "three"] {
+        Message::create(db.clone(), &general, &alice, body)
+            .await
+            .unwrap();
+    }
+
+    let counts = ReadMarker::unread(db, &bob).await.unwrap();
+    assert_eq!(counts[0].messages(), 3);
+    assert_eq!(counts[0].mentions(), 0);
+}
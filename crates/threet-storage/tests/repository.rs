@@ -0,0 +1,151 @@
+use threet_storage::Page;
+use threet_storage::StorageError;
+use threet_storage::models::Channel;
+use threet_storage::models::User;
+
+mod common;
+
+#[tokio::test]
+async fn finds_users_by_id_and_condition() {
+    let (db, bob, _, _) = common::setup().await;
+
+    let users = db.repository::<User>();
+    let found = users.find_by_id(bob.id()).await.unwrap();
+    assert_eq!(found.username(), "bob");
+
+    let found = users
+        .find_where("username = ?1", vec!["alice".to_string().into()])
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].username(), "alice");
+}
+
+#[tokio::test]
+async fn missing_rows_are_not_found() {
+    let db = common::database().await;
+    let users = db.repository::<User>();
+
+    assert!(matches!(
+        users.find_by_id(42).await,
+        Err(StorageError::NotFound)
+    ));
+    assert!(matches!(
+        users.delete(42).await,
+        Err(StorageError::NotFound)
+    ));
+}
+
+#[tokio::test]
+async fn updates_and_deletes_channels() {
+    let db = common::database().await;
+    let channels = db.repository::<Channel>();
+
+    let mut channel = channels.insert(&Channel::new("general")).await.unwrap();
+    channel.set_name("lobby");
+    channels.update(&channel).await.unwrap();
+    let found = channels.find_by_id(channel.id()).await.unwrap();
+    assert_eq!(found.name(), "lobby");
+
+    channels.delete(channel.id()).await.unwrap();
+    assert!(matches!(
+        Channel::by_name(db, "lobby").await,
+        Err(StorageError::NotFound)
+    ));
+    assert!(matches!(
+        channels.update(&channel).await,
+        Err(StorageError::NotFound)
+    ));
+}
+
+#[tokio::test]
+async fn lists_channels_by_page() {
+    let db = common::database().await;
+    let channels = db.repository::<Channel>();
+    for name in ["general", "random", "rust", "tui", "ssh"] {
+        channels.insert(&Channel::new(name)).await.unwrap();
+    }
+
+    let page = Page::new(0, 2);
+    let first = channels.list(page).await.unwrap();
+    let second = channels.list(page.next()).await.unwrap();
+    let third = channels.list(page.next().next()).await.unwrap();
+
+    let names = |channels: &[Channel]| {
+        channels
+            .iter()
+            .map(|channel| channel.name().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&first), ["general", "random"]);
+    assert_eq!(names(&second), ["rust", "tui"]);
+    assert_eq!(names(&third), ["ssh"]);
+
+    let general = Channel::by_name(db, "general").await.unwrap();
+    assert_eq!(general.id(), first[0].id());
+}
+
+#[tokio::test]
+async fn taken_usernames_are_constraint_violations() {
+    let db = common::database().await;
+    User::create(db.clone(), "bob", "hunter2").await.unwrap();
+
+    let err = User::create(db, "bob", "other").await.unwrap_err();
+    assert!(matches!(err, StorageError::ConstraintViolation(_)));
+    assert!(!err.is_unavailable());
+}
+
+#[tokio::test]
+async fn wrong_credentials_are_not_found() {
+    let db = common::database().await;
+    User::create(db.clone(), "bob", "hunter2").await.unwrap();
+
+    let user = User::by_username_password(db.clone(), "bob", "hunter2").await;
+    assert_eq!(user.unwrap().username(), "bob");
+    assert!(matches!(
+        User::by_username_password(db, "bob", "wrong").await,
+        Err(StorageError::NotFound)
+    ));
+}
+
+#[tokio::test]
+async fn users_authenticate_with_their_keys() {
+    let db = common::database().await;
+    let bob = User::create(db.clone(), "bob", "hunter2").await.unwrap();
+    User::create(db.clone(), "alice", "secret").await.unwrap();
+    let key = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBob";
+
+    bob.add_key(db.clone(), key).await.unwrap();
+    assert!(matches!(
+        bob.add_key(db.clone(), key).await,
+        Err(StorageError::ConstraintViolation(_))
+    ));
+    assert_eq!(bob.keys(db.clone()).await.unwrap(), [key]);
+
+    let user = User::by_username_key(db.clone(), "bob", key).await;
+    assert_eq!(user.unwrap().username(), "bob");
+    // the key is bob's, it doesn't log in as somebody else
+    assert!(matches!(
+        User::by_username_key(db.clone(), "alice", key).await,
+        Err(StorageError::NotFound)
+    ));
+
+    bob.remove_key(db.clone(), key).await.unwrap();
+    assert!(matches!(
+        bob.remove_key(db.clone(), key).await,
+        Err(StorageError::NotFound)
+    ));
+    assert!(matches!(
+        User::by_username_key(db, "bob", key).await,
+        Err(StorageError::NotFound)
+    ));
+}
+
+#[tokio::test]
+async fn closed_databases_are_unavailable() {
+    let db = common::database().await;
+    db.close().await;
+
+    let err = Channel::all(db).await.unwrap_err();
+    assert!(err.is_unavailable());
+}
@@ -0,0 +1,128 @@
+use threet_storage::Database;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+use threet_storage::models::SearchQuery;
+
+mod common;
+
+/// a database with two channels and a few messages from two users
+async fn conversation() -> (Database, Channel, Channel) {
+    let (db, bob, alice, general) = common::setup().await;
+    let random = db
+        .repository::<Channel>()
+        .insert(&Channel::new("random"))
+        .await
+        .unwrap();
+
+    let messages = [
+        (&general, &bob, "we should deploy on friday"),
+        (&general, &alice, "no deploys on friday please"),
+        (&general, &alice, "lunch anyone?"),
+        (&random, &bob, "deploying my cat to the couch"),
+    ];
+    for (channel, user, body) in messages {
+        Message::create(db.clone(), channel, user, body)
+            .await
+            .unwrap();
+    }
+    (db, general, random)
+}
+
+fn bodies(results: &[threet_storage::models::SearchResult]) -> Vec<&str> {
+    let mut bodies = results
+        .iter()
+        .map(|result| result.message().body())
+        .collect::<Vec<_>>();
+    bodies.sort();
+    bodies
+}
+
+#[tokio::test]
+async fn matches_terms_as_prefixes() {
+    let (db, _, _) = conversation().await;
+
+    let results = Message::search(db, SearchQuery::new("deploy"))
+        .await
+        .unwrap();
+    assert_eq!(
+        bodies(&results),
+        [
+            "deploying my cat to the couch",
+            "no deploys on friday please",
+            "we should deploy on friday",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn filters_by_channel_and_author() {
+    let (db, general, random) = conversation().await;
+
+    let query = SearchQuery::new("deploy").channel(general.id());
+    let results = Message::search(db.clone(), query).await.unwrap();
+    assert_eq!(results.len(), 2);
+
+    let query = SearchQuery::new("deploy")
+        .channel(random.id())
+        .author("alice");
+    assert!(Message::search(db.clone(), query).await.unwrap().is_empty());
+
+    let query = SearchQuery::new("friday").author("alice");
+    let results = Message::search(db, query).await.unwrap();
+    assert_eq!(bodies(&results), ["no deploys on friday please"]);
+}
+
+#[tokio::test]
+async fn filters_by_date() {
+    let (db, _, _) = conversation().await;
+
+    let query = SearchQuery::new("friday").until(0);
+    assert!(Message::search(db.clone(), query).await.unwrap().is_empty());
+
+    let query = SearchQuery::new("friday").since(0);
+    assert_eq!(Message::search(db, query).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn highlights_the_matched_terms() {
+    let (db, _, _) = conversation().await;
+
+    let results = Message::search(db, SearchQuery::new("lunch"))
+        .await
+        .unwrap();
+    assert_eq!(
+        results[0].highlights(),
+        [("lunch", true), (" anyone?", false)]
+    );
+}
+
+#[tokio::test]
+async fn query_syntax_is_matched_as_text() {
+    let (db, _, _) = conversation().await;
+
+    // quotes and operators would be a syntax error for fts5
+    let results = Message::search(db.clone(), SearchQuery::new("\"friday OR"))
+        .await
+        .unwrap();
+    assert!(results.is_empty());
+    assert!(
+        Message::search(db, SearchQuery::new("  "))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn loads_the_messages_around_a_message() {
+    let (db, general, _) = conversation().await;
+    let history = Message::history(db.clone(), general.id(), 0, 10)
+        .await
+        .unwrap();
+
+    let around = Message::around(db, general.id(), history[1].id(), 1)
+        .await
+        .unwrap();
+    let ids = around.iter().map(Message::id).collect::<Vec<_>>();
+    assert_eq!(ids, [history[0].id(), history[1].id(), history[2].id()]);
+}
@@ -0,0 +1,80 @@
+use threet_storage::Database;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+use threet_storage::models::User;
+
+mod common;
+
+/// a database with a single message in a single channel
+async fn conversation() -> (Database, User, Channel, Message) {
+    let (db, bob, _, general) = common::setup().await;
+    let root = Message::create(db.clone(), &general, &bob, "deploy?")
+        .await
+        .unwrap();
+    (db, bob, general, root)
+}
+
+#[tokio::test]
+async fn replies_are_counted_on_the_root_message() {
+    let (db, bob, general, root) = conversation().await;
+    assert_eq!(root.reply_count(), 0);
+
+    let reply = Message::reply(db.clone(), &root, &bob, "on it")
+        .await
+        .unwrap();
+    assert_eq!(reply.parent_id(), Some(root.id()));
+    assert_eq!(reply.channel_id(), general.id());
+
+    let history = Message::history(db, general.id(), 0, 10).await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].reply_count(), 1);
+}
+
+#[tokio::test]
+async fn replies_stay_out_of_the_channel_history() {
+    let (db, bob, general, root) = conversation().await;
+    Message::reply(db.clone(), &root, &bob, "on it")
+        .await
+        .unwrap();
+    Message::create(db.clone(), &general, &bob, "done")
+        .await
+        .unwrap();
+
+    let history = Message::history(db.clone(), general.id(), 0, 10)
+        .await
+        .unwrap();
+    let bodies = history.iter().map(Message::body).collect::<Vec<_>>();
+    assert_eq!(bodies, ["deploy?", "done"]);
+
+    let around = Message::around(db, general.id(), root.id(), 10)
+        .await
+        .unwrap();
+    assert_eq!(around.len(), 2);
+}
+
+#[tokio::test]
+async fn thread_lists_the_replies_oldest_first() {
+    let (db, bob, _, root) = conversation().await;
+    for body in ["first", "second", "third"] {
+        Message::reply(db.clone(), &root, &bob, body).await.unwrap();
+    }
+
+    let thread = Message::thread(db, root.id()).await.unwrap();
+    let bodies = thread.iter().map(Message::body).collect::<Vec<_>>();
+    assert_eq!(bodies, ["first", "second", "third"]);
+}
+
+#[tokio::test]
+async fn replying_to_a_reply_stays_in_the_same_thread() {
+    let (db, bob, _, root) = conversation().await;
+    let reply = Message::reply(db.clone(), &root, &bob, "on it")
+        .await
+        .unwrap();
+    let nested = Message::reply(db.clone(), &reply, &bob, "thanks")
+        .await
+        .unwrap();
+    assert_eq!(nested.parent_id(), Some(root.id()));
+
+    let thread = Message::thread(db, root.id()).await.unwrap();
+    assert_eq!(thread.len(), 2);
+}
@@ -2,45 +2,174 @@ use std::io::Write;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::LazyLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
+use std::time::Instant;
 
 use ratatui::TerminalOptions;
 use ratatui::Viewport;
 use ratatui::prelude::*;
 
+use threet_storage::Database;
+use threet_storage::StorageEvent;
 use threet_storage::models::User;
 
-use tokio::sync::Mutex;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::mpsc::channel;
+use tokio::task::JoinHandle;
 use tokio::time::MissedTickBehavior;
 use tokio::time::interval;
+use tokio::time::sleep_until;
 
 use crate::bind::BindBuffer;
 use crate::bind::Binder;
+use crate::capabilities::Capabilities;
 use crate::compositor::Compositor;
 use crate::compositor::Layout;
 use crate::event::Event;
 use crate::event::Key;
 use crate::event::KeyCode;
-use crate::job::Job;
+use crate::markdown::strip_escapes;
+use crate::notifications::Notification;
+use crate::notifications::NotificationServiceWidget;
+use crate::output::BandwidthMeter;
+use crate::output::OutputStats;
+use crate::output::RawOutput;
+use crate::output::clipboard_sequence;
+use crate::session::SessionDirectory;
+use crate::session::SessionId;
 use crate::views::AuthenticateView;
+use crate::views::ChannelsView;
+use crate::views::MentionsView;
+use crate::views::NOTIFICATION_DURATION;
+use crate::views::SearchView;
+use crate::views::SessionsView;
+use crate::views::View;
+use crate::widgets::StatusWidget;
+
+/// the events channel is consumed by the app itself, but callbacks also
+/// dispatch events while the app is processing, the channel must have room for
+/// them or the app would wait on itself
+const EVENTS_CAPACITY: usize = 32;
+
+/// max notifications displayed at the same time
+const MAX_NOTIFICATIONS: usize = 3;
+
+const TICK_INTERVAL: Duration = Duration::from_millis(350);
+
+/// the default max frames per second sent to the remote terminal
+const DEFAULT_MAX_FPS: u32 = 30;
 
 static NORMAL_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
     let mut combo = Binder::new();
     combo.add([KeyCode::Char('a'); 1], new_vertical);
+    combo.add([KeyCode::Char('s'); 1], open_sessions);
+    combo.add([KeyCode::Char('c'); 1], open_channels);
+    combo.add([KeyCode::Char('/'); 1], open_search);
+    combo.add([KeyCode::Char('m'); 1], open_mentions);
+    combo.add([KeyCode::Char(':'), KeyCode::Char('q')], quit);
+    combo.add(
+        [KeyCode::Char(':'), KeyCode::Char('b')],
+        toggle_low_bandwidth,
+    );
     combo
 });
 
+fn toggle_low_bandwidth<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        cx.state.low_bandwidth = !cx.state.low_bandwidth;
+        // the status bar and the views that tick display the mode change
+        cx.compositor.mark_all_dirty();
+    })
+}
+
+fn quit<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        // the app is already stopping if nobody receives its events
+        if cx.dispatcher.send(Event::Quit(0)).await.is_err() {
+            log::debug!("the app stopped before the quit request");
+        }
+    })
+}
+
 fn new_vertical<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
     Box::pin(async move {
         // TODO: a default view
         cx.compositor.split_view(
-            Box::new(AuthenticateView::new(cx.dispatcher.clone())),
+            Box::new(AuthenticateView::new(
+                cx.dispatcher.clone(),
+                cx.database.clone(),
+            )),
+            Layout::Vertical,
+        );
+    })
+}
+
+/// opens the sessions list, only admins are allowed to see
+/// and manage the other connected sessions
+fn open_sessions<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if !cx.state.user.as_ref().is_some_and(User::is_admin) {
+            return;
+        }
+        cx.compositor.split_view(
+            Box::new(SessionsView::new(cx.sessions.clone(), cx.capabilities)),
+            Layout::Horizontal,
+        );
+    })
+}
+
+/// opens the channels list, the chats are only
+/// available to authenticated users
+fn open_channels<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(user) = cx.state.user.clone() else {
+            return;
+        };
+        cx.compositor.split_view(
+            Box::new(ChannelsView::new(
+                cx.dispatcher.clone(),
+                cx.database.clone(),
+                user,
+                cx.sessions.clone(),
+                cx.capabilities,
+            )),
+            Layout::Vertical,
+        );
+    })
+}
+
+/// opens the messages search ready for the query to be typed
+fn open_search<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if cx.state.user.is_none() {
+            return;
+        }
+        cx.compositor.split_view(
+            Box::new(SearchView::new(cx.dispatcher.clone(), cx.database.clone())),
+            Layout::Vertical,
+        );
+        cx.state.mode = Mode::Insert;
+    })
+}
+
+/// opens the recent messages mentioning the user
+fn open_mentions<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(user) = cx.state.user.clone() else {
+            return;
+        };
+        cx.compositor.split_view(
+            Box::new(MentionsView::new(
+                cx.dispatcher.clone(),
+                cx.database.clone(),
+                user,
+            )),
             Layout::Vertical,
         );
-        cx.dispatcher.send(Event::Render).await.unwrap();
     })
 }
 
@@ -59,9 +188,15 @@ pub enum Mode {
 /// references.
 pub struct Context<'a> {
     pub state: &'a mut AppState,
-    pub jobs: &'a mut Vec<Job>,
     pub compositor: &'a mut Compositor,
     pub dispatcher: Sender<Event>,
+    pub database: Database,
+    pub sessions: Arc<dyn SessionDirectory>,
+    /// what the remote terminal is capable of displaying
+    pub capabilities: Capabilities,
+    /// the keys that triggered the callback, in insert mode
+    /// this is the single key the user typed
+    pub keys: &'a [Key],
 }
 
 /// contains the application state that is share able, this is mostly used for
@@ -71,14 +206,111 @@ pub struct AppState {
     pub mode: Mode,
     /// defines the authenticated user for the current app
     pub user: Option<User>,
+    /// the server assigned id for the current session
+    pub session: SessionId,
+    /// render as few bytes as possible, for users on slow links
+    pub low_bandwidth: bool,
+}
+
+/// the app requires information about the session it runs in, it is
+/// easier to create an `App` with the builder pattern
+#[derive(Default)]
+pub struct AppBuilder {
+    size: Option<(u16, u16)>,
+    database: Option<Database>,
+    session: Option<(SessionId, Arc<dyn SessionDirectory>)>,
+    capabilities: Capabilities,
+    output_stats: Option<Arc<OutputStats>>,
+    max_fps: Option<u32>,
+    low_bandwidth: bool,
+    user: Option<User>,
+}
+
+impl AppBuilder {
+    /// the initial terminal size in a (width, height) format
+    #[inline]
+    pub fn size(mut self, size: (u16, u16)) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// the database the app and its views read from and write to
+    #[inline]
+    pub fn database(mut self, database: Database) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// the session the app is running for, and the directory
+    /// of all the other sessions on the server
+    #[inline]
+    pub fn session(mut self, id: SessionId, directory: Arc<dyn SessionDirectory>) -> Self {
+        self.session = Some((id, directory));
+        self
+    }
+
+    /// the remote terminal capabilities, by default the terminal is
+    /// expected to support everything
+    #[inline]
+    pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// the stats shared with whoever sends the app output to the remote terminal,
+    /// used to redraw the screen when the output had to drop frames
+    #[inline]
+    pub fn output_stats(mut self, stats: Arc<OutputStats>) -> Self {
+        self.output_stats = Some(stats);
+        self
+    }
+
+    /// the max frames per second the app renders, renders requested
+    /// faster than that are coalesced into a single frame
+    #[inline]
+    pub fn max_fps(mut self, fps: u32) -> Self {
+        self.max_fps = Some(fps);
+        self
+    }
+
+    /// starts the app in low bandwidth mode, the views are not redrawn on
+    /// ticks and are drawn with less colors and plain borders
+    #[inline]
+    pub fn low_bandwidth(mut self, low_bandwidth: bool) -> Self {
+        self.low_bandwidth = low_bandwidth;
+        self
+    }
+
+    /// the user the session already authenticated as, the app
+    /// starts on the channels list instead of the login form
+    #[inline]
+    pub fn user(mut self, user: User) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// creates a new application instance that will write to the
+    /// given stdout buffer, the returned value includes a channel sender
+    /// to insert events to the app from outside
+    pub fn build<W: Write + RawOutput>(
+        self,
+        stdout: W,
+    ) -> (App<CrosstermBackend<W>>, Sender<Event>) {
+        self.build_with_backend(CrosstermBackend::new(stdout))
+    }
+
+    /// creates a new application instance that will draw to the given
+    /// backend, used to run the app on something other than a remote terminal
+    pub fn build_with_backend<B: Backend + RawOutput>(self, backend: B) -> (App<B>, Sender<Event>) {
+        App::new(backend, self)
+    }
 }
 
-pub struct App<W: Write> {
+pub struct App<B: Backend> {
     events: Receiver<Event>,
     events_sender: Sender<Event>,
-    terminal: Terminal<CrosstermBackend<W>>,
+    terminal: Terminal<B>,
     compositor: Compositor,
-    jobs: Vec<Job>,
 
     /// vector of the current keys pressed by the user
     /// to match with the combo, this vector is filled when
@@ -86,104 +318,283 @@ pub struct App<W: Write> {
     /// when a `ESC` key is recieved
     bbuffer: BindBuffer,
     state: AppState,
+    database: Database,
+    sessions: Arc<dyn SessionDirectory>,
+    notifications: NotificationServiceWidget<MAX_NOTIFICATIONS>,
+
+    /// a boolean value indicating if the tick event was comsumed, shared
+    /// with the ticker task which is aborted when the app is dropped
+    tick_consumed: Arc<AtomicBool>,
+    ticker: Option<JoinHandle<()>>,
+    /// forwards the storage events to the app events, aborted
+    /// when the app is dropped like the ticker
+    storage_forwarder: JoinHandle<()>,
+    capabilities: Capabilities,
+    output_stats: Arc<OutputStats>,
+    bandwidth: BandwidthMeter,
+
+    /// a boolean value indicating if something changed since the last frame, the
+    /// frame is rendered once `frame_interval` passed since the `last_frame`
+    dirty: bool,
+    last_frame: Instant,
+    frame_interval: Duration,
 }
 
-impl<W: Write> App<W> {
-    /// creates a new application instance that will write to the
-    /// given stdout buffer, the returned value includes a channel sender
-    /// to insert events to the app from outside
-    pub fn new(stdout: W, size: (u16, u16)) -> (Self, Sender<Event>) {
+impl<B: Backend + RawOutput> App<B> {
+    fn new(backend: B, builder: AppBuilder) -> (Self, Sender<Event>) {
+        let size = builder
+            .size
+            .expect("cannot create app without a terminal size");
+        let (session, sessions) = builder
+            .session
+            .expect("cannot create app without a session");
+        let database = builder
+            .database
+            .expect("cannot create app without a database");
+
         let area = Rect::new(0, 0, size.0, size.1);
-        let (app_tx, app_rx) = channel(1);
+        let (app_tx, app_rx) = channel(EVENTS_CAPACITY);
         let terminal = Terminal::with_options(
-            CrosstermBackend::new(stdout),
+            backend,
             TerminalOptions {
                 viewport: Viewport::Fixed(area),
             },
         )
         .unwrap();
 
-        let mut compositor = Compositor::new(area);
+        // the last line of the terminal is used by the status bar
+        let mut compositor = Compositor::new(Rect {
+            height: area.height.saturating_sub(1),
+            ..area
+        });
 
-        compositor.split_view(
-            Box::new(AuthenticateView::new(app_tx.clone())),
-            Layout::Vertical,
-        );
+        let initial_view: Box<dyn View + Sync> = match builder.user.clone() {
+            Some(user) => {
+                sessions.identify(session, user.username());
+                Box::new(ChannelsView::new(
+                    app_tx.clone(),
+                    database.clone(),
+                    user,
+                    sessions.clone(),
+                    builder.capabilities,
+                ))
+            }
+            None => Box::new(AuthenticateView::new(app_tx.clone(), database.clone())),
+        };
+        compositor.split_view(initial_view, Layout::Vertical);
+
+        // the views are told about storage changes as soon as the app is
+        // created, so nothing is missed between creating and running the app
+        let storage_forwarder = tokio::spawn({
+            let mut storage_events = database.subscribe();
+            let app_tx = app_tx.clone();
+
+            async move {
+                loop {
+                    let event = match storage_events.recv().await {
+                        Ok(event) => event,
+                        Err(RecvError::Lagged(missed)) => {
+                            log::warn!("app missed {} storage events", missed);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    };
+                    if app_tx.send(Event::Storage(event)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
 
         let state = AppState {
             mode: Mode::Normal,
-            user: None,
+            user: builder.user,
+            session,
+            low_bandwidth: builder.low_bandwidth,
         };
 
         let app = App {
             events: app_rx,
             events_sender: app_tx.clone(),
-            jobs: Vec::new(),
             bbuffer: BindBuffer::new(),
             compositor,
             terminal,
             state,
+            database,
+            sessions,
+            notifications: NotificationServiceWidget::new(),
+            tick_consumed: Arc::new(AtomicBool::new(true)),
+            ticker: None,
+            storage_forwarder,
+            capabilities: builder.capabilities,
+            output_stats: builder.output_stats.unwrap_or_default(),
+            bandwidth: BandwidthMeter::default(),
+            dirty: false,
+            last_frame: Instant::now(),
+            frame_interval: Duration::from_secs(1)
+                / builder.max_fps.unwrap_or(DEFAULT_MAX_FPS).max(1),
         };
         (app, app_tx)
     }
 
-    pub async fn run(mut self) -> anyhow::Result<()> {
+    /// runs the app until it is requested to quit or until there are no more
+    /// events to process, the returned value is the app exit status
+    pub async fn run(mut self) -> anyhow::Result<u32> {
         // initial unconditiond application render
-        self.terminal.clear().unwrap();
-        self.render();
-
-        // a boolean value indicating if the tick event was comsumed, the tick
-        // event task won't place more `Tick` events on the channel
-        // if the last `Tick` event was not consumed
-        // TODO: maybe should this be atomic bool?
-        let tick_consumed = Arc::new(Mutex::new(true));
+        self.terminal.clear()?;
+        self.render()?;
 
-        tokio::spawn({
-            let tick_consumed = tick_consumed.clone();
+        self.ticker = Some(tokio::spawn({
+            let tick_consumed = self.tick_consumed.clone();
             let app_tx = self.events_sender.clone();
 
             async move {
-                let mut interval_ = interval(Duration::from_millis(350));
+                let mut interval_ = interval(TICK_INTERVAL);
                 interval_.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
-                // FIXME!: need to kill that loop when app instance
-                // is dropped!!!
                 loop {
                     interval_.tick().await;
 
-                    let mut tick_consumed = tick_consumed.lock().await;
-
-                    if *tick_consumed {
-                        // FIXME: this will break if the app drop
-                        app_tx.send(Event::Tick).await.unwrap();
-                        *tick_consumed = false;
+                    // the tick event task won't place more `Tick` events on the
+                    // channel if the last `Tick` event was not consumed
+                    if tick_consumed.swap(false, Ordering::AcqRel) {
+                        // the app is gone, no one is left to tick
+                        if app_tx.send(Event::Tick).await.is_err() {
+                            break;
+                        }
                     }
                 }
             }
-        });
-
-        while let Some(event) = self.events.recv().await {
-            match event {
-                Event::Stdin(bytes) => self.handle_stdin(bytes).await,
-                Event::Resize(mut size) => {
-                    self.terminal
-                        .resize(Rect::new(0, 0, size.0, size.1))
-                        .unwrap();
-
-                    // reduce 1 from the area hight because the app will use that line
-                    // to render the status bar
-                    size.1 -= 1;
-
-                    // resize the compositor which wil trigger a recalculation
-                    // and unconditional render
-                    self.compositor.resize(size);
-                    self.render();
+        }));
+
+        let exit_status = loop {
+            let event = if self.dirty {
+                // the frame is rendered as soon as the frame interval allows it,
+                // events arriving until then are folded into the same frame
+                tokio::select! {
+                    biased;
+
+                    _ = sleep_until((self.last_frame + self.frame_interval).into()) => {
+                        self.render()?;
+                        continue;
+                    }
+                    event = self.events.recv() => event,
                 }
-                Event::Render => self.render(),
-                _ => {}
+            } else {
+                self.events.recv().await
             };
-        }
-        Ok(())
+
+            let Some(event) = event else {
+                break 0;
+            };
+
+            if let Some(exit_status) = self.handle_event(event).await? {
+                break exit_status;
+            }
+        };
+
+        // leave the remote terminal clean for the user shell
+        self.terminal.clear()?;
+        self.terminal.show_cursor()?;
+        Ok(exit_status)
+    }
+
+    /// processes a single event, the returned value is the exit
+    /// status if the event requested the app to quit
+    pub(crate) async fn handle_event(&mut self, event: Event) -> anyhow::Result<Option<u32>> {
+        match event {
+            Event::Stdin(bytes) => self.handle_stdin(bytes).await,
+            Event::Resize(mut size) => {
+                self.terminal.resize(Rect::new(0, 0, size.0, size.1))?;
+
+                // reduce 1 from the area hight because the app will use that line
+                // to render the status bar
+                size.1 = size.1.saturating_sub(1);
+
+                // resize the compositor which wil trigger a recalculation
+                // and render of all the views
+                self.compositor.resize(size);
+                self.dirty = true;
+            }
+            Event::Tick => {
+                self.bandwidth.sample(self.output_stats.bytes_sent());
+                if self.notifications.tick() {
+                    self.dirty = true;
+                }
+
+                // in low bandwidth mode the views are only drawn again
+                // when something happens, not because time passed
+                if self.compositor.tick().await && !self.state.low_bandwidth {
+                    self.dirty = true;
+                }
+
+                // the output dropped frames, the remote screen is out
+                // of sync until everything is redrawn
+                if self.output_stats.resync_requested() {
+                    self.dirty = true;
+                }
+                self.tick_consumed.store(true, Ordering::Release);
+            }
+            Event::Notification((notification, duration)) => {
+                self.notifications.push_notification(notification, duration);
+
+                // notifications are usually pushed by the view background
+                // tasks, which are done by now and the view may have changed
+                self.compositor.mark_all_dirty();
+                self.dirty = true;
+            }
+            Event::SetUser(user) => {
+                self.sessions.identify(self.state.session, user.username());
+                self.state.user = Some(user);
+                self.compositor.mark_all_dirty();
+                self.dirty = true;
+            }
+            Event::Storage(event) => {
+                if let StorageEvent::MessageCreated(message) = &event
+                    && let Some(user) = &self.state.user
+                    && message.mentions(user)
+                {
+                    let notification = Notification::info(
+                        format!("{} mentioned you", message.author()),
+                        strip_escapes(message.body()).into_owned(),
+                    );
+                    self.notifications
+                        .push_notification(notification, NOTIFICATION_DURATION);
+                    self.compositor.mark_all_dirty();
+                    self.dirty = true;
+                }
+                if self.compositor.storage_event(&event) {
+                    self.dirty = true;
+                }
+            }
+            Event::Render => {
+                self.compositor.mark_all_dirty();
+                self.dirty = true;
+            }
+            Event::Clipboard(text) => {
+                let sequence = clipboard_sequence(&text);
+                let notification = match self.terminal.backend_mut().write_raw(sequence.as_bytes())
+                {
+                    Ok(()) => Notification::info(
+                        "clipboard".to_string(),
+                        format!("copied {} characters", text.chars().count()),
+                    ),
+                    Err(err) => {
+                        log::warn!("couldn't write to the clipboard, {}", err);
+                        Notification::error(
+                            "clipboard error".to_string(),
+                            "couldn't copy to the clipboard".to_string(),
+                        )
+                    }
+                };
+                self.notifications
+                    .push_notification(notification, NOTIFICATION_DURATION);
+                self.compositor.mark_all_dirty();
+                self.dirty = true;
+            }
+            Event::Quit(status) => return Ok(Some(status)),
+        };
+        Ok(None)
     }
 
     #[inline]
@@ -192,6 +603,16 @@ impl<W: Write> App<W> {
             return;
         };
 
+        // escape always goes back to normal mode, views never see it
+        if key.keycode == KeyCode::Esc {
+            self.bbuffer.clear();
+            if matches!(self.state.mode, Mode::Insert) {
+                self.state.mode = Mode::Normal;
+                self.dirty = true;
+            }
+            return;
+        }
+
         // if the key was not pushed for some reason, or if the recorder
         // is empty, we have no point processing the record
         if !self.bbuffer.push(key) || self.bbuffer.is_mepty() {
@@ -216,16 +637,103 @@ impl<W: Write> App<W> {
                 state: &mut self.state,
                 compositor: &mut self.compositor,
                 dispatcher: self.events_sender.clone(),
+                database: self.database.clone(),
+                sessions: self.sessions.clone(),
+                capabilities: self.capabilities,
+                keys: self.bbuffer.as_ref(),
             };
             callback(cx).await;
             self.bbuffer.clear();
+
+            // the callback most likely changed the focused view, which
+            // might be a different view than the one that returned it
+            self.compositor.mark_dirty();
+            self.dirty = true;
+        } else if matches!(self.state.mode, Mode::Insert) {
+            // there are no combos in insert mode, a key the
+            // view didn't want is just ignored
+            self.bbuffer.clear();
         }
     }
 
+    /// returns the next pending event without waiting for one
+    #[cfg(feature = "testing")]
+    #[inline]
+    pub(crate) fn try_next_event(&mut self) -> Option<Event> {
+        self.events.try_recv().ok()
+    }
+
+    /// waits for the next event, `None` if there will be no more events
+    #[cfg(feature = "testing")]
     #[inline]
-    fn render(&mut self) {
+    pub(crate) async fn next_event(&mut self) -> Option<Event> {
+        self.events.recv().await
+    }
+
+    /// returns the backend the app draws to
+    #[cfg(feature = "testing")]
+    #[inline]
+    pub(crate) fn backend(&self) -> &B {
+        self.terminal.backend()
+    }
+
+    #[cfg(feature = "testing")]
+    #[inline]
+    pub(crate) fn backend_mut(&mut self) -> &mut B {
+        self.terminal.backend_mut()
+    }
+
+    #[inline]
+    pub(crate) fn render(&mut self) -> std::io::Result<()> {
+        self.dirty = false;
+        self.last_frame = Instant::now();
+
+        if self.output_stats.take_resync() {
+            self.terminal.clear()?;
+        }
+
+        let low_bandwidth = self.state.low_bandwidth;
+        let capabilities = if low_bandwidth {
+            self.capabilities.reduced()
+        } else {
+            self.capabilities
+        };
+        self.compositor
+            .set_ascii_borders(low_bandwidth || !capabilities.unicode);
+        let bytes_per_minute = self.bandwidth.per_minute();
+
         self.terminal
-            .draw(|frame| self.compositor.render(frame.area(), frame.buffer_mut()))
-            .unwrap();
+            .draw(|frame| {
+                let [views_area, status_area] =
+                    ratatui::layout::Layout::vertical([Constraint::Fill(1), Constraint::Length(1)])
+                        .areas(frame.area());
+                self.compositor.render(views_area, frame.buffer_mut());
+
+                let status = StatusWidget::new(self.compositor.current_view().name())
+                    .separator(capabilities.glyph("\u{e0b0}", ""))
+                    .low_bandwidth(low_bandwidth)
+                    .bytes_per_minute(bytes_per_minute);
+                frame.render_widget(status, status_area);
+
+                if self.notifications.should_render() {
+                    frame.render_widget(&self.notifications, views_area);
+                }
+
+                // views render with any color they like, the colors are
+                // converted at the end to what the terminal can display
+                capabilities.downsample(frame.buffer_mut());
+            })
+            .map(|_| ())
+    }
+}
+
+impl<B: Backend> Drop for App<B> {
+    fn drop(&mut self) {
+        // the ticker holds a sender to the app events, it would
+        // have kept running forever if not stopped here
+        if let Some(ticker) = self.ticker.take() {
+            ticker.abort();
+        }
+        self.storage_forwarder.abort();
     }
 }
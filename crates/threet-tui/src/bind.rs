@@ -105,7 +105,7 @@ impl BindBuffer {
             self.0.push(key);
             return true;
         }
-        return false;
+        false
     }
 
     /// clear all the pressed keys in the recorder
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use ratatui::buffer::Buffer;
+use ratatui::style::Color;
+
+/// the standard xterm values of the 16 ansi colors, used
+/// to find the nearest ansi color of a rgb color
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// the channel values used by the 6x6x6 color cube of the 256 colors palette
+const CUBE_STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// how many colors the remote terminal can display
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ColorSupport {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// describes what the remote terminal is capable of displaying, derived
+/// from the `TERM` the client sent with its pty request and from its environment
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Capabilities {
+    pub color: ColorSupport,
+    /// the terminal can display unicode characters
+    pub unicode: bool,
+    /// the terminal font most likely has the powerline glyphs
+    pub powerline: bool,
+    /// the terminal most likely sets its clipboard with the OSC 52 sequence
+    pub clipboard: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            color: ColorSupport::TrueColor,
+            unicode: true,
+            powerline: true,
+            clipboard: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// detect the capabilities from the `TERM` value and the `COLORTERM` and locale
+    /// environment variables, `utf8_mode` is the pty `IUTF8` mode if the client sent it
+    pub fn detect(term: &str, env: &HashMap<String, String>, utf8_mode: Option<bool>) -> Self {
+        let colorterm = env.get("COLORTERM").map(String::as_str).unwrap_or_default();
+        let color = if matches!(colorterm, "truecolor" | "24bit") || term.ends_with("-direct") {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        };
+
+        // the first locale variable that is set wins, same as libc does
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|name| env.get(*name).filter(|value| !value.is_empty()));
+        let unicode = match (locale, utf8_mode) {
+            (Some(locale), _) => {
+                let locale = locale.to_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            }
+            (None, Some(utf8)) => utf8,
+            // most clients don't forward the locale, modern terminals are unicode
+            (None, None) => true,
+        };
+
+        // the linux console font doesn't have the powerline glyphs
+        let powerline = unicode && term != "linux" && !term.starts_with("vt");
+
+        // the consoles have no clipboard, and the vte terminals, like
+        // gnome terminal, ignore the sequence, most other emulators accept it
+        let clipboard = !matches!(term, "linux" | "dumb")
+            && !term.starts_with("vt")
+            && !env.contains_key("VTE_VERSION");
+
+        Capabilities {
+            color,
+            unicode,
+            powerline,
+            clipboard,
+        }
+    }
+
+    /// converts the given color to the nearest color the terminal can display
+    pub fn color(&self, color: Color) -> Color {
+        match (self.color, color) {
+            (ColorSupport::TrueColor, color) => color,
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_256(r, g, b)),
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => rgb_to_16(r, g, b),
+            (ColorSupport::Ansi16, Color::Indexed(i)) if i >= 16 => {
+                let (r, g, b) = indexed_to_rgb(i);
+                rgb_to_16(r, g, b)
+            }
+            (_, color) => color,
+        }
+    }
+
+    /// downsample all the colors in the given buffer to colors the terminal can display
+    pub fn downsample(&self, buf: &mut Buffer) {
+        if self.color == ColorSupport::TrueColor {
+            return;
+        }
+
+        for cell in buf.content.iter_mut() {
+            cell.fg = self.color(cell.fg);
+            cell.bg = self.color(cell.bg);
+        }
+    }
+
+    /// the capabilities used in low bandwidth mode, the 16 colors palette and
+    /// plain glyphs need less bytes to draw than what the terminal can display
+    #[inline]
+    pub fn reduced(&self) -> Self {
+        Capabilities {
+            color: self.color.min(ColorSupport::Ansi16),
+            unicode: self.unicode,
+            powerline: false,
+            clipboard: self.clipboard,
+        }
+    }
+
+    /// returns the given glyph if the terminal can display powerline
+    /// glyphs, or the ascii fallback if it can't
+    #[inline]
+    pub fn glyph<'a>(&self, glyph: &'a str, fallback: &'a str) -> &'a str {
+        if self.powerline { glyph } else { fallback }
+    }
+}
+
+#[inline]
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+fn rgb_to_16(r: u8, g: u8, b: u8) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb, (r, g, b)))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+/// returns the nearest color of the 256 colors palette, either
+/// from the color cube or from the grayscale ramp
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    let step = |v: u8| {
+        CUBE_STEPS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, step)| (**step as i32 - v as i32).abs())
+            .map(|(i, _)| i as u8)
+            .unwrap_or_default()
+    };
+    let (ri, gi, bi) = (step(r), step(g), step(b));
+    let cube = (
+        CUBE_STEPS[ri as usize],
+        CUBE_STEPS[gi as usize],
+        CUBE_STEPS[bi as usize],
+    );
+
+    let average = ((r as u32 + g as u32 + b as u32) / 3) as u8;
+    let gray_index = (average.saturating_sub(8) / 10).min(23);
+    let gray = 8 + gray_index * 10;
+
+    if distance((gray, gray, gray), (r, g, b)) < distance(cube, (r, g, b)) {
+        232 + gray_index
+    } else {
+        16 + 36 * ri + 6 * gi + bi
+    }
+}
+
+fn indexed_to_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..16 => ANSI_COLORS[i as usize].1,
+        16..232 => {
+            let i = i - 16;
+            (
+                CUBE_STEPS[(i / 36) as usize],
+                CUBE_STEPS[(i / 6 % 6) as usize],
+                CUBE_STEPS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let gray = 8 + (i - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
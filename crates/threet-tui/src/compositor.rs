@@ -1,10 +1,28 @@
 // most of the code here is inspired by the helix editor
+use std::any::Any;
+
 use ratatui::prelude::*;
+use ratatui::symbols::border;
 use ratatui::widgets::Block;
 use ratatui::widgets::Borders;
 
+use threet_storage::StorageEvent;
+
 use crate::views::View;
 
+/// borders drawn with plain ascii characters, a single byte per cell
+/// instead of the three bytes the box drawing characters need
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
 slotmap::new_key_type! {
     pub struct ViewId;
 }
@@ -37,16 +55,6 @@ impl Node {
             area: Rect::default(),
         }
     }
-
-    #[inline]
-    fn is_view(&self) -> bool {
-        matches!(self.data, NodeData::View(..))
-    }
-
-    #[inline]
-    fn is_container(&self) -> bool {
-        matches!(self.data, NodeData::Container(..))
-    }
 }
 
 enum NodeData {
@@ -56,11 +64,31 @@ enum NodeData {
 
 struct NodeViewData {
     view: Box<dyn View>,
+    /// the last render of the view, reused as is
+    /// until the view is marked as dirty
+    cache: Buffer,
+    dirty: bool,
 }
 
 impl NodeViewData {
     pub fn new(view: Box<dyn View>) -> Self {
-        Self { view }
+        Self {
+            view,
+            cache: Buffer::empty(Rect::default()),
+            dirty: true,
+        }
+    }
+
+    /// renders the view into its cache if the view is dirty or if its
+    /// area changed, otherwise the cache holds what the view would render
+    fn render_cached(&mut self, area: Rect) -> &Buffer {
+        if self.dirty || self.cache.area != area {
+            self.cache.resize(area);
+            self.cache.reset();
+            self.view.render(area, &mut self.cache);
+            self.dirty = false;
+        }
+        &self.cache
     }
 }
 
@@ -85,14 +113,14 @@ impl NodeContainerData {
 /// it will just render its content to the view, if the leaf node is also a container, it will divide the given
 /// area to its own leafs etc...
 struct Tree {
-    nodes: slotmap::HopSlotMap<ViewId, Node>,
+    nodes: slotmap::SlotMap<ViewId, Node>,
     root: ViewId,
     focuse: ViewId,
 }
 
 impl Tree {
     pub fn new(area: Rect) -> Self {
-        let mut nodes = slotmap::HopSlotMap::with_key();
+        let mut nodes = slotmap::SlotMap::with_key();
         let root = Node::container(Layout::Vertical);
         let root = nodes.insert(root);
         nodes[root].parent = root;
@@ -105,49 +133,10 @@ impl Tree {
         }
     }
 
-    /// returns how many views the tree has opened
-    fn views_count(&self) -> usize {
-        self.nodes.values().filter(|node| node.is_view()).count()
-    }
-
     fn swap(&mut self, view: Box<dyn View>) {
         let view_node = self.get_focuse_mut();
         view_node.view = view;
-    }
-
-    fn push(&mut self, view: Box<dyn View>) -> ViewId {
-        let parent = self.nodes[self.focuse].parent;
-        let mut node = Node::view(view);
-        node.parent = parent;
-
-        let node = self.nodes.insert(node);
-
-        match self.nodes[parent] {
-            Node {
-                area,
-                data: NodeData::Container(ref mut ctr),
-                ..
-            } => {
-                // the pushed view need to be inserted below/after current
-                // focuse view, if there are no childs it means the index is `0`, if there
-                // are childs, we look for the focused view position and add `1`
-                let position = if ctr.childs.is_empty() {
-                    0
-                } else {
-                    ctr.childs
-                        .iter()
-                        .position(|view_id| *view_id == self.focuse)
-                        .unwrap()
-                        + 1
-                };
-                ctr.childs.insert(position, node);
-                self.recalculate(parent, area);
-            }
-            _ => panic!("unexpected, node parent is not a container"),
-        };
-
-        self.focuse = node;
-        node
+        view_node.dirty = true;
     }
 
     /// split the currently focused view into 2 with respect
@@ -222,11 +211,6 @@ impl Tree {
         self.recalculate(parent, area);
     }
 
-    /// remove the current focuse view
-    fn remove(&mut self) {
-        todo!();
-    }
-
     #[inline]
     fn get_focuse(&self) -> &NodeViewData {
         match &self.nodes[self.focuse].data {
@@ -243,17 +227,6 @@ impl Tree {
         }
     }
 
-    fn get_container_mut(&mut self) -> &mut NodeContainerData {
-        let parent = self.nodes[self.focuse].parent;
-        match &mut self.nodes[parent] {
-            Node {
-                data: NodeData::Container(ctr),
-                ..
-            } => ctr,
-            _ => unreachable!(),
-        }
-    }
-
     #[inline(always)]
     fn resize(&mut self, size: (u16, u16)) {
         self.recalculate(self.root, Rect::new(0, 0, size.0, size.1));
@@ -277,7 +250,7 @@ impl Tree {
                             let height = area.height / ctr.childs.len() as u16;
                             let mut offset = area.y;
 
-                            for (i, child) in ctr.childs.iter().enumerate() {
+                            for child in &ctr.childs {
                                 let area = Rect::new(area.x, offset, area.width, height);
                                 stack.push((*child, area));
                                 offset += height;
@@ -287,7 +260,7 @@ impl Tree {
                             let width = area.width / ctr.childs.len() as u16;
                             let mut offset = area.x;
 
-                            for (i, child) in ctr.childs.iter().enumerate() {
+                            for child in &ctr.childs {
                                 let area = Rect::new(offset, area.y, width, area.height);
                                 stack.push((*child, area));
                                 offset += width;
@@ -299,7 +272,7 @@ impl Tree {
                 // with the new calculated one by the parent
                 Node {
                     area,
-                    data: NodeData::View(view),
+                    data: NodeData::View(_),
                     ..
                 } => *area = area_,
             }
@@ -325,14 +298,14 @@ struct TreeIter<'a> {
 }
 
 impl<'a> Iterator for TreeIter<'a> {
-    type Item = (Rect, &'a NodeViewData);
+    type Item = (ViewId, Rect, &'a NodeViewData);
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let view = self.stack.pop()?;
-            let node = &self.tree.nodes[view];
+            let id = self.stack.pop()?;
+            let node = &self.tree.nodes[id];
 
             match &node.data {
-                NodeData::View(view) => return Some((node.area, view)),
+                NodeData::View(view) => return Some((id, node.area, view)),
                 NodeData::Container(ctr) => {
                     self.stack.extend(&ctr.childs);
                 }
@@ -343,9 +316,11 @@ impl<'a> Iterator for TreeIter<'a> {
 
 /// the compositor is responsible to display and render requested
 /// views to the terminal
-#[repr(transparent)]
 pub struct Compositor {
     tree: Tree,
+    /// draw the borders between the views with ascii characters
+    /// instead of the box drawing characters
+    ascii_borders: bool,
 }
 
 impl Compositor {
@@ -353,9 +328,15 @@ impl Compositor {
     pub fn new(area: Rect) -> Self {
         Self {
             tree: Tree::new(area),
+            ascii_borders: false,
         }
     }
 
+    #[inline]
+    pub fn set_ascii_borders(&mut self, ascii_borders: bool) {
+        self.ascii_borders = ascii_borders;
+    }
+
     #[inline(always)]
     pub fn swap(&mut self, view: Box<dyn View>) {
         self.tree.swap(view);
@@ -382,11 +363,75 @@ impl Compositor {
         self.tree.get_focuse_mut().view.as_mut()
     }
 
+    /// returns the current focused view as the concrete view type `T`, `None`
+    /// if the focused view is of a different type, used by key bind callbacks
+    /// that need to change the view they were returned from
+    #[inline]
+    pub fn current_view_as_mut<T: View>(&mut self) -> Option<&mut T> {
+        let view: &mut dyn Any = self.current_view_mut();
+        view.downcast_mut::<T>()
+    }
+
+    /// marks the focused view as dirty, it will be rendered again on the next frame
+    #[inline]
+    pub fn mark_dirty(&mut self) {
+        self.tree.get_focuse_mut().dirty = true;
+    }
+
+    /// marks all the views as dirty, used when the views may have
+    /// changed without the compositor knowing which one
+    pub fn mark_all_dirty(&mut self) {
+        for node in self.tree.nodes.values_mut() {
+            if let NodeData::View(data) = &mut node.data {
+                data.dirty = true;
+            }
+        }
+    }
+
+    /// ticks all the opened views so they can update their internal state, the
+    /// returned boolean indicate if any of the views changed and should be rendered
+    pub async fn tick(&mut self) -> bool {
+        let mut changed = false;
+        for node in self.tree.nodes.values_mut() {
+            if let NodeData::View(data) = &mut node.data
+                && data.view.tick().await
+            {
+                data.dirty = true;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// passes the storage event to all the opened views, the returned
+    /// boolean indicate if any of the views changed and should be rendered
+    pub fn storage_event(&mut self, event: &StorageEvent) -> bool {
+        let mut changed = false;
+        for node in self.tree.nodes.values_mut() {
+            if let NodeData::View(data) = &mut node.data
+                && data.view.storage_event(event)
+            {
+                data.dirty = true;
+                changed = true;
+            }
+        }
+        changed
+    }
+
     /// renders the views into the given buffer, compositor doesn't accept area because
     /// it will use whatever it has calculated in the tree
+    ///
+    /// only the dirty views are rendered again, the other views are copied from
+    /// what they rendered last time
     #[inline(always)]
     pub fn render(&mut self, area: Rect, buffer: &mut Buffer) {
-        for (view_area, view) in &self.tree {
+        let views = self
+            .tree
+            .into_iter()
+            .map(|(id, view_area, _)| (id, view_area))
+            .collect::<Vec<_>>();
+
+        for (id, view_area) in views {
             let mut borders = Borders::empty();
 
             if area.y != view_area.y {
@@ -396,12 +441,17 @@ impl Compositor {
                 borders |= Borders::RIGHT;
             }
 
-            let block = Block::new().borders(borders);
+            let mut block = Block::new().borders(borders);
+            if self.ascii_borders {
+                block = block.border_set(ASCII_BORDER);
+            }
             let inner_area = block.inner(view_area);
 
             // TODO: show lines instead of blocks
             block.render(view_area, buffer);
-            view.view.render(inner_area, buffer);
+            if let NodeData::View(view) = &mut self.tree.nodes[id].data {
+                buffer.merge(view.render_cached(inner_area));
+            }
         }
     }
 }
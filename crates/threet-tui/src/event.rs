@@ -1,6 +1,7 @@
 use core::str;
 use std::time::Duration;
 
+use threet_storage::StorageEvent;
 use threet_storage::models::User;
 
 use crate::notifications::Notification;
@@ -26,9 +27,27 @@ pub enum KeyCode {
 pub struct Modifier(u32);
 
 impl Modifier {
-    const NONE: Modifier = Modifier(0x0);
-    const SHIFT: Modifier = Modifier(0x1);
-    const CTRL: Modifier = Modifier(0x2);
+    pub const NONE: Modifier = Modifier(0x0);
+    pub const SHIFT: Modifier = Modifier(0x1);
+    pub const CTRL: Modifier = Modifier(0x2);
+    pub const ALT: Modifier = Modifier(0x4);
+
+    /// reads the modifiers parameter of a control sequence, it
+    /// is one more than the shift, alt and ctrl bits
+    fn from_param(param: u32) -> Modifier {
+        let bits = param.saturating_sub(1);
+        let mut modifiers = Modifier::NONE;
+        if bits & 0x1 != 0 {
+            modifiers = modifiers | Modifier::SHIFT;
+        }
+        if bits & 0x2 != 0 {
+            modifiers = modifiers | Modifier::ALT;
+        }
+        if bits & 0x4 != 0 {
+            modifiers = modifiers | Modifier::CTRL;
+        }
+        modifiers
+    }
 
     #[inline(always)]
     pub fn contains(&self, modifier: Modifier) -> bool {
@@ -63,31 +82,22 @@ impl Key {
         }
 
         match bytes[0] {
-            b'\x1b' => {
-                if bytes.len() == 1 {
-                    Some(KeyCode::Esc.into())
-                } else {
-                    if bytes[1] == b'[' {
-                        if bytes.len() == 2 {
-                            None
-                        } else {
-                            match bytes[2] {
-                                b'D' => Some(KeyCode::Left.into()),
-                                b'C' => Some(KeyCode::Right.into()),
-                                b'A' => Some(KeyCode::Up.into()),
-                                b'B' => Some(KeyCode::Down.into()),
-                                b'H' => Some(KeyCode::Home.into()),
-                                b'F' => Some(KeyCode::End.into()),
-                                _ => None,
-                            }
-                        }
-                    } else {
-                        None
-                    }
+            b'\x1b' => match bytes.get(1) {
+                None => Some(KeyCode::Esc.into()),
+                Some(b'[') => Key::from_sequence(&bytes[2..]),
+                Some(b'O') if bytes.len() > 2 => Key::from_sequence(&bytes[2..]),
+                // the terminals send the keys pressed with alt after an escape
+                Some(_) => {
+                    let key = Key::from_bytes(&bytes[1..])?;
+                    Some(Key {
+                        keycode: key.keycode,
+                        modifiers: key.modifiers | Modifier::ALT,
+                    })
                 }
-            }
+            },
             b'\r' => Some(KeyCode::Enter.into()),
             b'\t' => Some(KeyCode::Tab.into()),
+            b' ' => Some(KeyCode::Space.into()),
             0x7f => Some(KeyCode::Backspace.into()),
             0x0 => Some(Key {
                 keycode: KeyCode::Enter,
@@ -115,6 +125,44 @@ impl Key {
             }
         }
     }
+
+    /// reads the keys the terminals send as a control sequence, the arrows or
+    /// the keys with modifiers like `ESC [ 1 ; 5 D` for ctrl-left, `ESC [ 13 ; 2 u`
+    /// for shift-enter or `ESC [ 27 ; 2 ; 13 ~` for the xterm shift-enter
+    fn from_sequence(sequence: &[u8]) -> Option<Key> {
+        let (last, params) = sequence.split_last()?;
+        let params = str::from_utf8(params).ok()?;
+        let param = |i: usize| params.split(';').nth(i).and_then(|p| p.parse::<u32>().ok());
+
+        let keycode = match last {
+            b'D' => KeyCode::Left,
+            b'C' => KeyCode::Right,
+            b'A' => KeyCode::Up,
+            b'B' => KeyCode::Down,
+            b'H' => KeyCode::Home,
+            b'F' => KeyCode::End,
+            b'u' => KeyCode::from_code(param(0)?)?,
+            b'~' if param(0) == Some(27) => KeyCode::from_code(param(2)?)?,
+            _ => return None,
+        };
+        Some(Key {
+            keycode,
+            modifiers: param(1).map_or(Modifier::NONE, Modifier::from_param),
+        })
+    }
+}
+
+impl KeyCode {
+    /// returns the key of the given unicode code point
+    fn from_code(code: u32) -> Option<KeyCode> {
+        match code {
+            9 => Some(KeyCode::Tab),
+            13 => Some(KeyCode::Enter),
+            27 => Some(KeyCode::Esc),
+            127 => Some(KeyCode::Backspace),
+            code => char::from_u32(code).map(KeyCode::Char),
+        }
+    }
 }
 
 impl From<KeyCode> for Key {
@@ -146,5 +194,20 @@ pub enum Event {
     /// allow setting the user from outside the application
     /// or from a view
     SetUser(User),
+
+    /// something changed in the storage, like a new message, the
+    /// views get the event to update what they display
+    Storage(StorageEvent),
+
+    /// requests a new frame with all the views rendered again, used when
+    /// a view changed from outside of a key bind (like a background task),
+    /// renders are coalesced so sending many of them is cheap
     Render,
+
+    /// copies the given text to the clipboard of the remote terminal
+    Clipboard(String),
+
+    /// stops the app with the given exit status, the exit status
+    /// is reported back to the remote client
+    Quit(u32),
 }
@@ -0,0 +1,75 @@
+use std::sync::LazyLock;
+
+use ratatui::prelude::*;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::FontStyle;
+use syntect::highlighting::Theme;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+
+use crate::capabilities::Capabilities;
+use crate::capabilities::ColorSupport;
+
+/// the grammars embedded in the binary, loaded on the first highlight
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// the theme of the terminals displaying 256 colors or more
+const THEME: &str = "base16-ocean.dark";
+
+/// the theme of the 16 colors terminals, the solarized accents
+/// are close to the ansi colors they are converted to
+const ANSI_THEME: &str = "Solarized (dark)";
+
+fn theme(capabilities: &Capabilities) -> &'static Theme {
+    match capabilities.color {
+        ColorSupport::Ansi16 => &THEMES.themes[ANSI_THEME],
+        ColorSupport::Ansi256 | ColorSupport::TrueColor => &THEMES.themes[THEME],
+    }
+}
+
+/// highlights the given code lines written in the given language, like
+/// `rust` or `sql`, returns none if there is no grammar for the language,
+/// the colors are the ones the terminal can display and the background
+/// is left to the terminal
+pub fn highlight(
+    lines: &[&str],
+    language: &str,
+    capabilities: &Capabilities,
+) -> Option<Vec<Vec<Span<'static>>>> {
+    let syntax = SYNTAXES.find_syntax_by_token(language)?;
+    let mut highlighter = HighlightLines::new(syntax, theme(capabilities));
+
+    let mut highlighted = Vec::with_capacity(lines.len());
+    for line in lines {
+        // the grammars expect the lines with their newline
+        let line = format!("{}\n", line);
+        let regions = match highlighter.highlight_line(&line, &SYNTAXES) {
+            Ok(regions) => regions,
+            Err(err) => {
+                log::warn!("couldn't highlight `{}` code, {}", language, err);
+                return None;
+            }
+        };
+
+        let spans = regions
+            .into_iter()
+            .map(|(style, text)| (style, text.trim_end_matches('\n')))
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(style, text)| {
+                let color = style.foreground;
+                let mut span_style =
+                    Style::new().fg(capabilities.color(Color::Rgb(color.r, color.g, color.b)));
+                if style.font_style.contains(FontStyle::BOLD) {
+                    span_style = span_style.bold();
+                }
+                if style.font_style.contains(FontStyle::ITALIC) {
+                    span_style = span_style.italic();
+                }
+                Span::styled(text.to_string(), span_style)
+            });
+        highlighted.push(spans.collect());
+    }
+    Some(highlighted)
+}
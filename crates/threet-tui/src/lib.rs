@@ -1,21 +1,39 @@
 mod app;
 mod bind;
+mod capabilities;
 mod compositor;
 mod event;
-mod job;
+mod highlight;
+mod markdown;
 mod notifications;
+mod output;
+mod session;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod utils;
 mod views;
 mod widgets;
 
 pub use app::App;
+pub use app::AppBuilder;
+pub use capabilities::Capabilities;
+pub use capabilities::ColorSupport;
 pub use event::Event;
+pub use markdown::strip_escapes;
+pub use notifications::Notification;
+pub use notifications::NotificationKind;
+pub use output::OutputStats;
+pub use output::RawOutput;
+pub use session::Presence;
+pub use session::SessionDirectory;
+pub use session::SessionId;
+pub use session::SessionInfo;
 
 /// call builder methods on a builder types if given condition
 /// is true, the macro takes a pair of condition and the builder method
 /// to call
 ///
-/// ```
+/// ```ignore
 /// let block = conditional_build(
 ///     Block::bordered(),
 ///     (x < y, (title_top("y is bigger")) else title_top("x is bigger")),
@@ -0,0 +1,596 @@
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::Chars;
+
+use ratatui::prelude::*;
+use unicode_width::UnicodeWidthChar;
+use unicode_width::UnicodeWidthStr;
+
+use threet_storage::models::find_mentions;
+
+use crate::capabilities::Capabilities;
+use crate::highlight::highlight;
+
+/// the style of the inline code and the code blocks
+const CODE_STYLE: Style = Style::new().fg(Color::Yellow);
+
+const LINK_STYLE: Style = Style::new()
+    .fg(Color::Blue)
+    .add_modifier(Modifier::UNDERLINED);
+
+const MENTION_STYLE: Style = Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+/// the style of the markup kept in the output, like the quotes
+/// bar, the lists markers and the links urls
+const MARKUP_STYLE: Style = Style::new().fg(Color::DarkGray);
+
+/// the fence opening and closing the code blocks
+const CODE_FENCE: &str = "```";
+
+/// how many spaces a tab is expanded to
+const TAB_WIDTH: usize = 4;
+
+/// returns true for the characters that change the terminal state
+/// instead of being displayed, and for the bidi controls that
+/// make the displayed text read differently than it is written
+fn is_unsafe(c: char) -> bool {
+    (c.is_control() && c != '\n') || matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// removes the terminal escape sequences and the control characters
+/// from the given user content, so a message can't move the cursor,
+/// change the title or the clipboard of the terminals displaying it,
+/// the newlines are kept and the tabs are expanded to spaces
+pub fn strip_escapes(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_unsafe) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\t' => stripped.extend(std::iter::repeat_n(' ', TAB_WIDTH)),
+            '\x1b' => match chars.next() {
+                Some('[') => skip_csi(&mut chars),
+                Some(']' | 'P' | 'X' | '^' | '_') => skip_string(&mut chars),
+                // the other sequences are a single character long
+                _ => {}
+            },
+            // the 8-bit forms of the sequences above
+            '\u{9b}' => skip_csi(&mut chars),
+            '\u{90}' | '\u{98}' | '\u{9d}' | '\u{9e}' | '\u{9f}' => skip_string(&mut chars),
+            c if is_unsafe(c) => {}
+            c => stripped.push(c),
+        }
+    }
+    Cow::Owned(stripped)
+}
+
+/// skips the parameters and the final byte of a control sequence,
+/// the sequence ends early on a character it can't contain
+fn skip_csi(chars: &mut Peekable<Chars>) {
+    while let Some(&c) = chars.peek() {
+        match c {
+            '\x20'..='\x3f' => {
+                chars.next();
+            }
+            '\x40'..='\x7e' => {
+                chars.next();
+                return;
+            }
+            _ => return,
+        }
+    }
+}
+
+/// skips a control string, like an operating system command,
+/// up to its string terminator or bell
+fn skip_string(chars: &mut Peekable<Chars>) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\x07' | '\u{9c}' => return,
+            '\x1b' if chars.peek() == Some(&'\\') => {
+                chars.next();
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// returns the byte ranges of the http urls in the given text,
+/// without the punctuation following them
+pub fn find_urls(text: &str) -> Vec<Range<usize>> {
+    let mut urls = Vec::new();
+    let mut offset = 0;
+    while let Some(found) = text[offset..].find("http") {
+        let start = offset + found;
+        let rest = &text[start..];
+        let at_word_start = text[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        if !at_word_start || !(rest.starts_with("http://") || rest.starts_with("https://")) {
+            offset = start + "http".len();
+            continue;
+        }
+
+        let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let url =
+            rest[..len].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '>', '\'', '"']);
+        if url.len() > "https://".len() {
+            urls.push(start..start + url.len());
+        }
+        offset = start + len;
+    }
+    urls
+}
+
+/// formats the messages bodies, the body is parsed as a markdown
+/// subset: bold, italic, inline code, code blocks, quotes, lists
+/// and links, then wrapped to the given width
+///
+/// unlike markdown each line of the body is displayed on its own line,
+/// the way it was typed, the escape sequences are stripped
+#[derive(Default)]
+pub struct MessageFormatter {
+    width: usize,
+    prefix: Vec<Span<'static>>,
+    indent: usize,
+    /// the code blocks are highlighted when the terminal capabilities are known
+    capabilities: Option<Capabilities>,
+}
+
+impl MessageFormatter {
+    pub fn new(width: u16) -> Self {
+        MessageFormatter {
+            width: width as usize,
+            ..Default::default()
+        }
+    }
+
+    /// spans displayed before the body on the first line, like the
+    /// message author
+    pub fn prefix(mut self, prefix: Vec<Span<'static>>) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// how many columns the lines after the first one are indented
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// highlights the code blocks with a language, like ```` ```rust ````,
+    /// with the colors the terminal can display
+    pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    pub fn format(&self, body: &str) -> Vec<Line<'static>> {
+        let body = strip_escapes(body);
+        let mut lines = FormattedLines {
+            formatter: self,
+            lines: Vec::new(),
+            prefix: Some(self.prefix.clone()),
+        };
+
+        let mut rows = body.lines();
+        while let Some(row) = rows.next() {
+            // a code block is displayed as written, up to its closing fence
+            if let Some(language) = fence_language(row) {
+                let code = rows
+                    .by_ref()
+                    .take_while(|row| fence_language(row).is_none())
+                    .collect::<Vec<_>>();
+                let highlighted = self
+                    .capabilities
+                    .filter(|_| !language.is_empty())
+                    .and_then(|capabilities| highlight(&code, language, &capabilities));
+                let code = highlighted.unwrap_or_else(|| {
+                    code.iter()
+                        .map(|row| vec![Span::styled(row.to_string(), CODE_STYLE)])
+                        .collect()
+                });
+
+                lines.push_prefix();
+                for spans in code {
+                    lines.push(Vec::new(), Vec::new(), spans, true);
+                }
+                continue;
+            }
+
+            if let Some(quote) = row.strip_prefix('>') {
+                let quote = quote.strip_prefix(' ').unwrap_or(quote);
+                let bar = vec![Span::styled("│ ", MARKUP_STYLE)];
+                let spans = inline_spans(quote, Style::new().italic());
+                lines.push(bar.clone(), bar, spans, false);
+                continue;
+            }
+
+            if let Some((marker, item)) = list_item(row) {
+                let hang = vec![Span::raw(" ".repeat(marker.width()))];
+                let marker = vec![Span::styled(marker.to_string(), MARKUP_STYLE)];
+                lines.push(marker, hang, inline_spans(item, Style::new()), false);
+                continue;
+            }
+
+            lines.push(
+                Vec::new(),
+                Vec::new(),
+                inline_spans(row, Style::new()),
+                false,
+            );
+        }
+
+        lines.push_prefix();
+        lines.lines
+    }
+}
+
+/// the lines of a formatted message, the first line starts with the
+/// formatter prefix and the next ones are indented
+struct FormattedLines<'a> {
+    formatter: &'a MessageFormatter,
+    lines: Vec<Line<'static>>,
+    /// the prefix, until the first line is pushed
+    prefix: Option<Vec<Span<'static>>>,
+}
+
+impl FormattedLines<'_> {
+    /// pushes the prefix on its own line, if no line was pushed yet
+    fn push_prefix(&mut self) {
+        if let Some(prefix) = self.prefix.take() {
+            self.lines.push(Line::from(prefix));
+        }
+    }
+
+    /// pushes the given spans wrapped to the formatter width, the first
+    /// line starts with the `lead` spans and the next ones with `hang`
+    fn push(
+        &mut self,
+        lead: Vec<Span<'static>>,
+        hang: Vec<Span<'static>>,
+        spans: Vec<Span<'static>>,
+        hard: bool,
+    ) {
+        let indent = Span::raw(" ".repeat(self.formatter.indent));
+        let mut first = self.prefix.take().unwrap_or_else(|| vec![indent.clone()]);
+        first.extend(lead);
+        let mut next = vec![indent];
+        next.extend(hang);
+
+        let available = |line: &[Span]| {
+            let used = line.iter().map(Span::width).sum::<usize>();
+            self.formatter.width.saturating_sub(used).max(1)
+        };
+        let wrapped = wrap(spans, available(&first), available(&next), hard);
+        for (i, spans) in wrapped.into_iter().enumerate() {
+            let mut line = if i == 0 { first.clone() } else { next.clone() };
+            line.extend(spans);
+            self.lines.push(Line::from(line));
+        }
+    }
+}
+
+/// returns the language of the code block the given line opens,
+/// empty if it has none, or none if the line is not a code fence
+fn fence_language(row: &str) -> Option<&str> {
+    let language = row.trim_start().strip_prefix(CODE_FENCE)?;
+    Some(language.split_whitespace().next().unwrap_or_default())
+}
+
+/// returns the code of the code blocks of the given message body,
+/// the escape sequences are stripped like when it's displayed
+pub fn code_blocks(body: &str) -> Vec<String> {
+    let body = strip_escapes(body);
+    let mut blocks = Vec::new();
+    let mut rows = body.lines();
+    while let Some(row) = rows.next() {
+        if fence_language(row).is_some() {
+            let code = rows
+                .by_ref()
+                .take_while(|row| fence_language(row).is_none())
+                .collect::<Vec<_>>();
+            blocks.push(code.join("\n"));
+        }
+    }
+    blocks
+}
+
+/// splits the given list item line in its marker, with the spaces
+/// around it, and its text, like `- item` or `1. item`
+fn list_item(row: &str) -> Option<(&str, &str)> {
+    let text = row.trim_start();
+    let marker_len = match text.chars().next()? {
+        '-' | '*' | '+' => 1,
+        c if c.is_ascii_digit() => {
+            let digits = text.find(|c: char| !c.is_ascii_digit())?;
+            text[digits..]
+                .starts_with(['.', ')'])
+                .then_some(digits + 1)?
+        }
+        _ => return None,
+    };
+    if !text[marker_len..].starts_with(' ') {
+        return None;
+    }
+
+    let split = row.len() - text.len() + marker_len + 1;
+    Some(row.split_at(split))
+}
+
+/// the spans of a single line of text with its inline markup, the
+/// newlines are displayed as spaces and the escape sequences are stripped
+pub fn inline_spans(text: &str, style: Style) -> Vec<Span<'static>> {
+    let text = strip_escapes(text).replace('\n', " ");
+    let mut spans = Vec::new();
+    parse_inline(&text, style, &mut spans);
+    spans
+}
+
+fn parse_inline(text: &str, style: Style, spans: &mut Vec<Span<'static>>) {
+    // the start of the text without markup, up to the markup parsed next
+    let mut plain = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let previous = text[..i].chars().next_back();
+
+        if let Some(escaped) = rest.strip_prefix('\\')
+            && escaped.starts_with(|c: char| c.is_ascii_punctuation())
+        {
+            // the escaped character is displayed as is
+            plain_spans(&text[plain..i], style, spans);
+            plain = i + 1;
+            i += 2;
+            continue;
+        }
+
+        let len = if let Some(code) = rest.strip_prefix('`')
+            && let Some(end) = code.find('`')
+            && end > 0
+        {
+            plain_spans(&text[plain..i], style, spans);
+            spans.push(Span::styled(
+                code[..end].to_string(),
+                style.patch(CODE_STYLE),
+            ));
+            end + 2
+        } else if let Some(len) =
+            emphasis(rest, previous, "**").or_else(|| emphasis(rest, previous, "__"))
+        {
+            plain_spans(&text[plain..i], style, spans);
+            parse_inline(&rest[2..len - 2], style.bold(), spans);
+            len
+        } else if let Some(len) =
+            emphasis(rest, previous, "*").or_else(|| emphasis(rest, previous, "_"))
+        {
+            plain_spans(&text[plain..i], style, spans);
+            parse_inline(&rest[1..len - 1], style.italic(), spans);
+            len
+        } else if let Some((label, url, len)) = link(rest) {
+            plain_spans(&text[plain..i], style, spans);
+            parse_inline(label, style.patch(LINK_STYLE), spans);
+            // the url is always displayed, a link can't hide where it goes
+            if label != url {
+                spans.push(Span::styled(
+                    format!(" ({})", url),
+                    style.patch(MARKUP_STYLE),
+                ));
+            }
+            len
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+
+        i += len;
+        plain = i;
+    }
+    plain_spans(&text[plain..], style, spans);
+}
+
+/// the length of the emphasis starting the given text, with its
+/// delimiters, the underscores don't emphasize inside words,
+/// like the ones of `snake_case`
+fn emphasis(text: &str, previous: Option<char>, delimiter: &str) -> Option<usize> {
+    let inner = text.strip_prefix(delimiter)?;
+    let underscore = delimiter.starts_with('_');
+    if inner.starts_with(char::is_whitespace)
+        || inner.starts_with(delimiter)
+        || (underscore && previous.is_some_and(char::is_alphanumeric))
+    {
+        return None;
+    }
+
+    let mut offset = 0;
+    while let Some(found) = inner[offset..].find(delimiter) {
+        let end = offset + found;
+        let after = inner[end + delimiter.len()..].chars().next();
+        let after_space = inner[..end].ends_with(char::is_whitespace);
+        let inside_word = underscore && after.is_some_and(char::is_alphanumeric);
+        // a single delimiter doesn't close on a double one
+        let doubled = delimiter.len() == 1 && inner[end + 1..].starts_with(delimiter);
+        if end > 0 && !after_space && !inside_word && !doubled {
+            return Some(end + delimiter.len() * 2);
+        }
+        offset = end + delimiter.len();
+        if delimiter.len() == 1 {
+            offset += inner[offset..]
+                .find(|c: char| !delimiter.starts_with(c))
+                .unwrap_or(inner.len() - offset);
+        }
+    }
+    None
+}
+
+/// the label, url and length of the link starting the given
+/// text, like `[label](https://example.com)`
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let inner = text.strip_prefix('[')?;
+    let label_end = inner.find("](")?;
+    let label = &inner[..label_end];
+    let url_start = label_end + 2;
+    let url_len = inner[url_start..].find(')')?;
+    let url = &inner[url_start..url_start + url_len];
+    if label.is_empty()
+        || label.contains('[')
+        || url.is_empty()
+        || url.contains(char::is_whitespace)
+    {
+        return None;
+    }
+    Some((label, url, 1 + url_start + url_len + 1))
+}
+
+/// the spans of the text without markup, the mentions and
+/// the urls are highlighted
+fn plain_spans(text: &str, style: Style, spans: &mut Vec<Span<'static>>) {
+    let urls = find_urls(text);
+    let mut highlights = find_mentions(text)
+        .into_iter()
+        .filter(|mention| {
+            !urls
+                .iter()
+                .any(|url| url.start < mention.end && mention.start < url.end)
+        })
+        .map(|mention| (mention, MENTION_STYLE))
+        .chain(urls.iter().cloned().map(|url| (url, LINK_STYLE)))
+        .collect::<Vec<_>>();
+    highlights.sort_by_key(|(range, _)| range.start);
+
+    let mut written = 0;
+    for (range, highlight) in highlights {
+        if written < range.start {
+            spans.push(Span::styled(text[written..range.start].to_string(), style));
+        }
+        spans.push(Span::styled(
+            text[range.clone()].to_string(),
+            style.patch(highlight),
+        ));
+        written = range.end;
+    }
+    if written < text.len() {
+        spans.push(Span::styled(text[written..].to_string(), style));
+    }
+}
+
+/// wraps the given spans, the first line is given `first_width` columns
+/// and the next ones `width`, the lines are broken between words unless
+/// `hard` is set or a word doesn't fit on a line
+fn wrap(
+    spans: Vec<Span<'static>>,
+    first_width: usize,
+    width: usize,
+    hard: bool,
+) -> Vec<Vec<Span<'static>>> {
+    let mut wrapper = Wrapper {
+        lines: vec![Vec::new()],
+        used: 0,
+        available: first_width,
+        width,
+    };
+
+    for span in spans {
+        for word in split_words(&span.content, hard) {
+            if wrapper.used + word.width() > wrapper.available && wrapper.used > 0 {
+                wrapper.new_line();
+            }
+            // the wrapped lines don't start with the spaces they were broken at
+            if word.trim().is_empty() && wrapper.used == 0 && wrapper.lines.len() > 1 {
+                continue;
+            }
+
+            // the words longer than a line are broken anywhere, the
+            // characters wider than a line are displayed anyway
+            let mut rest = word;
+            while rest.width() > wrapper.available - wrapper.used {
+                let split = match split_at_width(rest, wrapper.available - wrapper.used) {
+                    0 => rest.chars().next().map_or(rest.len(), char::len_utf8),
+                    split => split,
+                };
+                wrapper.push(&rest[..split], span.style);
+                rest = &rest[split..];
+                wrapper.new_line();
+            }
+            if !rest.is_empty() {
+                wrapper.push(rest, span.style);
+            }
+        }
+    }
+    wrapper.lines
+}
+
+struct Wrapper {
+    lines: Vec<Vec<Span<'static>>>,
+    /// the columns used on the last line
+    used: usize,
+    /// the columns of the last line
+    available: usize,
+    width: usize,
+}
+
+impl Wrapper {
+    fn push(&mut self, text: &str, style: Style) {
+        self.used += text.width();
+        self.lines
+            .last_mut()
+            .unwrap()
+            .push(Span::styled(text.to_string(), style));
+    }
+
+    fn new_line(&mut self) {
+        // the spaces the line was broken at are not displayed
+        let line = self.lines.last_mut().unwrap();
+        while line
+            .last()
+            .is_some_and(|span| span.content.trim().is_empty())
+        {
+            line.pop();
+        }
+        self.lines.push(Vec::new());
+        self.used = 0;
+        self.available = self.width;
+    }
+}
+
+/// splits the given text in words and the spaces between them,
+/// or in a single piece if it's not broken between words
+fn split_words(text: &str, hard: bool) -> Vec<&str> {
+    if hard {
+        return vec![text];
+    }
+
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut space = None;
+    for (i, c) in text.char_indices() {
+        let is_space = c.is_whitespace();
+        if space.is_some_and(|space| space != is_space) {
+            words.push(&text[start..i]);
+            start = i;
+        }
+        space = Some(is_space);
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// the byte index the given text is split at to fit in the given width
+fn split_at_width(text: &str, width: usize) -> usize {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return i;
+        }
+    }
+    text.len()
+}
@@ -9,10 +9,6 @@ use ratatui::widgets::BorderType;
 use ratatui::widgets::Clear;
 use ratatui::widgets::Paragraph;
 
-use tokio::sync::mpsc::Sender;
-
-use crate::Event;
-
 #[derive(Debug, Clone)]
 pub enum NotificationKind {
     Info,
@@ -51,6 +47,16 @@ impl Notification {
     pub fn error(title: String, content: String) -> Self {
         Self::new(title, content, NotificationKind::Error)
     }
+
+    #[inline]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    #[inline]
+    pub fn content(&self) -> &str {
+        &self.content
+    }
 }
 
 impl Widget for &Notification {
@@ -58,11 +64,18 @@ impl Widget for &Notification {
     where
         Self: Sized,
     {
+        // the warnings and errors stand out by their border color
+        let border = match self.kind {
+            NotificationKind::Info => Style::new(),
+            NotificationKind::Warning => Style::new().yellow(),
+            NotificationKind::Error => Style::new().red(),
+        };
         Widget::render(Clear, area, buf);
         Paragraph::new(self.content.as_str())
             .block(
                 Block::bordered()
                     .border_type(BorderType::Thick)
+                    .border_style(border)
                     .title_top(self.title.as_str()),
             )
             .render(area, buf);
@@ -81,14 +94,12 @@ impl Widget for &Notification {
 /// directly to ratatui by reference
 pub struct NotificationServiceWidget<const N: usize> {
     stack: VecDeque<(Notification, Duration, Instant)>,
-    app_tx: Sender<Event>,
 }
 
 impl<const N: usize> NotificationServiceWidget<N> {
-    pub fn new(app_tx: Sender<Event>) -> Self {
+    pub fn new() -> Self {
         Self {
             stack: VecDeque::with_capacity(N),
-            app_tx,
         }
     }
 
@@ -102,8 +113,10 @@ impl<const N: usize> NotificationServiceWidget<N> {
             .push_front((notification, duration, Instant::now()));
     }
 
+    /// removes the expired notifications, the returned boolean
+    /// indicate if the screen should be rendered again
     #[inline]
-    pub async fn tick(&mut self) {
+    pub fn tick(&mut self) -> bool {
         let start = self.stack.len();
         self.stack
             .retain(|(_, duration, instant)| instant.elapsed() < *duration);
@@ -111,14 +124,18 @@ impl<const N: usize> NotificationServiceWidget<N> {
         // if the start length is different it is because
         // we removed notifications so we should rerender
         // the screen
-        if start != self.stack.len() {
-            self.app_tx.send(Event::Render).await.unwrap();
-        }
+        start != self.stack.len()
     }
 
     #[inline]
     pub fn should_render(&self) -> bool {
-        self.stack.len() != 0
+        !self.stack.is_empty()
+    }
+}
+
+impl<const N: usize> Default for NotificationServiceWidget<N> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ratatui::backend::CrosstermBackend;
+use ratatui::backend::TestBackend;
+
+/// the window the bandwidth meter averages the sent bytes over
+const BANDWIDTH_WINDOW: Duration = Duration::from_secs(60);
+
+/// the backends the app can write the escape sequences the frames
+/// don't carry to, like the sequence setting the terminal clipboard
+///
+/// unlike the frames, which are diffs an output may drop for a newer
+/// one, the raw sequences must always reach the terminal
+pub trait RawOutput {
+    fn write_raw(&mut self, bytes: &[u8]) -> std::io::Result<()>;
+}
+
+impl<W: Write + RawOutput> RawOutput for CrosstermBackend<W> {
+    fn write_raw(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.writer_mut().write_raw(bytes)
+    }
+}
+
+/// the test backend has no terminal, the sequences go nowhere
+impl RawOutput for TestBackend {
+    fn write_raw(&mut self, _bytes: &[u8]) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// the OSC 52 sequence setting the terminal clipboard to the given text,
+/// the text is base64 encoded so it can't end the sequence early
+pub fn clipboard_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+/// shared between the app and whoever delivers the app output to the remote
+/// terminal, the output records what was sent, and can ask the app to redraw
+/// the whole screen when it had to drop frames the terminal never received
+#[derive(Debug, Default)]
+pub struct OutputStats {
+    bytes_sent: AtomicU64,
+    frames_sent: AtomicU64,
+    frames_dropped: AtomicU64,
+    resync: AtomicBool,
+}
+
+impl OutputStats {
+    #[inline]
+    pub fn record_sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.frames_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// records the dropped frames and requests the app to redraw
+    /// everything, the frames are diffs so the remote screen is out of sync
+    #[inline]
+    pub fn record_dropped(&self, frames: usize) {
+        self.frames_dropped
+            .fetch_add(frames as u64, Ordering::Relaxed);
+        self.resync.store(true, Ordering::Release);
+    }
+
+    /// returns true if the remote screen is out of sync, frames written
+    /// before the app redraw everything should not be sent
+    #[inline]
+    pub fn resync_requested(&self) -> bool {
+        self.resync.load(Ordering::Acquire)
+    }
+
+    /// clears the resync request, the returned boolean indicate
+    /// if a resync was requested
+    #[inline]
+    pub fn take_resync(&self) -> bool {
+        self.resync.swap(false, Ordering::AcqRel)
+    }
+
+    #[inline]
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn frames_sent(&self) -> u64 {
+        self.frames_sent.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn frames_dropped(&self) -> u64 {
+        self.frames_dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// measures how many bytes were sent in the last minute, from
+/// samples of the total bytes sent taken every once in a while
+#[derive(Debug, Default)]
+pub struct BandwidthMeter {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl BandwidthMeter {
+    /// records the total bytes sent so far, samples older
+    /// than the measured window are removed
+    pub fn sample(&mut self, bytes_sent: u64) {
+        let now = Instant::now();
+        while self
+            .samples
+            .front()
+            .is_some_and(|(instant, _)| now.duration_since(*instant) > BANDWIDTH_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, bytes_sent));
+    }
+
+    /// returns the bytes sent in the last minute, the rate is extrapolated
+    /// if the meter has been sampling for less than a minute
+    pub fn per_minute(&self) -> u64 {
+        let (Some((first, first_bytes)), Some((last, last_bytes))) =
+            (self.samples.front(), self.samples.back())
+        else {
+            return 0;
+        };
+
+        let elapsed = last.duration_since(*first);
+        if elapsed.is_zero() {
+            return 0;
+        }
+
+        let bytes = last_bytes.saturating_sub(*first_bytes) as f64;
+        (bytes * BANDWIDTH_WINDOW.as_secs_f64() / elapsed.as_secs_f64()) as u64
+    }
+}
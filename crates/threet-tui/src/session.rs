@@ -0,0 +1,130 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+
+/// after how long without any input a user is considered idle
+const IDLE_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// after how long without any input a user is considered away
+const AWAY_AFTER: Duration = Duration::from_secs(30 * 60);
+
+/// unique identifier of a connected session, assigned by the
+/// server when the ssh session is opened
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SessionId(pub u64);
+
+impl std::fmt::Display for SessionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// the presence of a connected session, derived from
+/// the last time the session had any activity
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Presence {
+    Online,
+    Idle,
+    Away,
+}
+
+impl Presence {
+    pub fn from_idle(idle: Duration) -> Presence {
+        if idle < IDLE_AFTER {
+            Presence::Online
+        } else if idle < AWAY_AFTER {
+            Presence::Idle
+        } else {
+            Presence::Away
+        }
+    }
+
+    /// the indicator displayed next to the user name in member lists, terminals
+    /// that can't display unicode get an ascii indicator instead
+    #[inline]
+    pub fn symbol(&self, unicode: bool) -> &'static str {
+        match (self, unicode) {
+            (Presence::Online, true) => "●",
+            (Presence::Idle, true) => "◐",
+            (Presence::Away, true) => "○",
+            (Presence::Online, false) => "+",
+            (Presence::Idle, false) => "~",
+            (Presence::Away, false) => "-",
+        }
+    }
+
+    #[inline]
+    pub fn style(&self) -> Style {
+        match self {
+            Presence::Online => Style::new().green(),
+            Presence::Idle => Style::new().yellow(),
+            Presence::Away => Style::new().dark_gray(),
+        }
+    }
+}
+
+impl std::fmt::Display for Presence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Presence::Online => write!(f, "online"),
+            Presence::Idle => write!(f, "idle"),
+            Presence::Away => write!(f, "away"),
+        }
+    }
+}
+
+/// a snapshot of a single connected session
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub id: SessionId,
+    /// the authenticated user name, `None` if the session
+    /// didn't authenticate yet
+    pub username: Option<String>,
+    pub peer: SocketAddr,
+    pub connected_at: SystemTime,
+    pub last_activity: Instant,
+    pub size: (u16, u16),
+}
+
+impl SessionInfo {
+    #[inline]
+    pub fn presence(&self) -> Presence {
+        Presence::from_idle(self.last_activity.elapsed())
+    }
+
+    /// returns for how long the session is connected
+    #[inline]
+    pub fn uptime(&self) -> Duration {
+        self.connected_at.elapsed().unwrap_or_default()
+    }
+}
+
+/// the session directory is implemented by whoever owns the sessions (the server), the app
+/// uses it to display who is online and to let admins manage the other sessions
+pub trait SessionDirectory: Send + Sync {
+    /// returns a snapshot of all the connected sessions
+    fn sessions(&self) -> Vec<SessionInfo>;
+
+    /// attach the authenticated user name to the given session
+    fn identify(&self, id: SessionId, username: &str);
+
+    /// disconnect the given session, the returned boolean
+    /// indicate if the session was found
+    fn terminate(&self, id: SessionId) -> bool;
+
+    /// returns the presence of the given user, if the user has multiple
+    /// sessions the most present one is returned, `None` if the user is offline
+    fn presence(&self, username: &str) -> Option<Presence> {
+        self.sessions()
+            .iter()
+            .filter(|session| session.username.as_deref() == Some(username))
+            .map(|session| session.last_activity.elapsed())
+            .min()
+            .map(Presence::from_idle)
+    }
+}
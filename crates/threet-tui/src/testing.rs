@@ -0,0 +1,254 @@
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+
+use threet_storage::Database;
+use threet_storage::DatabaseBuilder;
+
+use crate::app::App;
+use crate::app::AppBuilder;
+use crate::event::Event;
+use crate::session::SessionDirectory;
+use crate::session::SessionId;
+use crate::session::SessionInfo;
+
+/// how long `settle` waits for a background task to report back
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// how long `flush` waits for more events before it is done
+const FLUSH_IDLE: Duration = Duration::from_millis(50);
+
+/// a session directory that only knows about the sessions it was
+/// given, used to run the app without a server
+#[derive(Default)]
+pub struct TestSessions {
+    sessions: Mutex<Vec<SessionInfo>>,
+}
+
+impl TestSessions {
+    /// adds a session connected from localhost with the given id
+    pub fn add(&self, id: SessionId, username: Option<&str>) {
+        self.sessions.lock().unwrap().push(SessionInfo {
+            id,
+            username: username.map(str::to_string),
+            peer: SocketAddr::from((Ipv4Addr::LOCALHOST, 22)),
+            connected_at: SystemTime::now(),
+            last_activity: Instant::now(),
+            size: (0, 0),
+        });
+    }
+}
+
+impl SessionDirectory for TestSessions {
+    fn sessions(&self) -> Vec<SessionInfo> {
+        self.sessions.lock().unwrap().clone()
+    }
+
+    fn identify(&self, id: SessionId, username: &str) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = sessions.iter_mut().find(|session| session.id == id) {
+            session.username = Some(username.to_string());
+        }
+    }
+
+    fn terminate(&self, id: SessionId) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        let len = sessions.len();
+        sessions.retain(|session| session.id != id);
+        len != sessions.len()
+    }
+}
+
+/// runs the app headless on ratatui's `TestBackend`, the events are fed one
+/// by one and processed right away so tests can assert on what was drawn
+/// after each step without a ssh client or a running event loop
+///
+/// ```ignore
+/// let mut app = TestApp::new((80, 24));
+/// app.type_str("i").await;
+/// app.type_str("admin").await;
+/// assert!(app.contains("admin"));
+/// ```
+pub struct TestApp {
+    app: App<TestBackend>,
+    database: Database,
+    sessions: Arc<TestSessions>,
+    exit_status: Option<u32>,
+    clipboard: Option<String>,
+}
+
+impl TestApp {
+    /// creates a new app with the given (width, height) size for session `#1`,
+    /// every app gets its own empty in-memory database
+    pub async fn new(size: (u16, u16)) -> Self {
+        Self::with_builder(size, AppBuilder::default()).await
+    }
+
+    /// creates a new app from the given builder, the size, the session
+    /// and the database of the builder are overridden
+    pub async fn with_builder(size: (u16, u16), builder: AppBuilder) -> Self {
+        let database = DatabaseBuilder::default()
+            .in_memory()
+            .build()
+            .await
+            .expect("couldn't create in-memory database");
+
+        let id = SessionId(1);
+        let sessions = Arc::new(TestSessions::default());
+        sessions.add(id, None);
+
+        let (mut app, _) = builder
+            .size(size)
+            .session(id, sessions.clone())
+            .database(database.clone())
+            .build_with_backend(TestBackend::new(size.0, size.1));
+        app.render().expect("test backend never fails to draw");
+
+        TestApp {
+            app,
+            database,
+            sessions,
+            exit_status: None,
+            clipboard: None,
+        }
+    }
+
+    /// the database the app was created with
+    #[inline]
+    pub fn database(&self) -> Database {
+        self.database.clone()
+    }
+
+    /// the session directory the app was created with
+    #[inline]
+    pub fn sessions(&self) -> &TestSessions {
+        &self.sessions
+    }
+
+    /// processes the given event, and every event it dispatched
+    /// while processing, then draws a frame
+    pub async fn send(&mut self, event: Event) {
+        let mut next = Some(event);
+        while let Some(event) = next {
+            // the test backend has no terminal clipboard to look at
+            if let Event::Clipboard(text) = &event {
+                self.clipboard = Some(text.clone());
+            }
+            let status = self
+                .app
+                .handle_event(event)
+                .await
+                .expect("test backend never fails to draw");
+            if status.is_some() {
+                self.exit_status = status;
+            }
+            next = self.app.try_next_event();
+        }
+
+        self.app.render().expect("test backend never fails to draw");
+    }
+
+    /// sends the given bytes as a single stdin chunk, like a terminal
+    /// does for a single key press or an escape sequence
+    pub async fn press(&mut self, bytes: &[u8]) {
+        self.send(Event::Stdin(bytes.to_vec())).await;
+    }
+
+    /// types the given text, a key press for each character
+    pub async fn type_str(&mut self, text: &str) {
+        let mut buf = [0; 4];
+        for c in text.chars() {
+            self.press(c.encode_utf8(&mut buf).as_bytes()).await;
+        }
+    }
+
+    /// resizes the terminal, like a client changing its window size
+    pub async fn resize(&mut self, size: (u16, u16)) {
+        self.app.backend_mut().resize(size.0, size.1);
+        self.send(Event::Resize(size)).await;
+    }
+
+    /// waits for the next event dispatched by a background task, like the
+    /// view tasks that report back once they are done, and processes it
+    pub async fn settle(&mut self) {
+        let event = tokio::time::timeout(SETTLE_TIMEOUT, self.app.next_event())
+            .await
+            .expect("no event was dispatched while settling");
+        if let Some(event) = event {
+            self.send(event).await;
+        }
+    }
+
+    /// processes all the events dispatched by background tasks until no
+    /// more events arrive for a short while, like the storage events of
+    /// the messages a test created
+    pub async fn flush(&mut self) {
+        while let Ok(Some(event)) = tokio::time::timeout(FLUSH_IDLE, self.app.next_event()).await {
+            self.send(event).await;
+        }
+    }
+
+    /// the exit status if the app was asked to quit
+    #[inline]
+    pub fn exit_status(&self) -> Option<u32> {
+        self.exit_status
+    }
+
+    /// the text last copied to the clipboard
+    #[inline]
+    pub fn clipboard(&self) -> Option<&str> {
+        self.clipboard.as_deref()
+    }
+
+    /// the last drawn frame
+    #[inline]
+    pub fn buffer(&self) -> &Buffer {
+        self.app.backend().buffer()
+    }
+
+    /// the last drawn frame as text lines, without styles
+    pub fn lines(&self) -> Vec<String> {
+        let buffer = self.buffer();
+        let area = buffer.area;
+        (area.top()..area.bottom())
+            .map(|y| {
+                (area.left()..area.right())
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    /// returns true if the given text is somewhere on the last drawn frame
+    pub fn contains(&self, text: &str) -> bool {
+        self.lines().iter().any(|line| line.contains(text))
+    }
+
+    /// asserts the last drawn frame matches the expected lines, the lines
+    /// are compared without styles, and the frame is printed on failure
+    #[track_caller]
+    pub fn assert_snapshot<I, S>(&self, expected: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let lines = self.lines();
+        let expected = expected
+            .into_iter()
+            .map(|line| line.as_ref().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            expected,
+            "the drawn frame doesn't match the snapshot:\n{}",
+            lines.join("\n")
+        );
+    }
+}
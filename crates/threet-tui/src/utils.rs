@@ -1,3 +1,7 @@
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
 use ratatui::layout::Constraint;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
@@ -19,3 +23,59 @@ pub fn get_middle_area(dem: (u16, u16), area: Rect) -> Rect {
     .areas(middle);
     middle
 }
+
+/// formats the given bytes count in a short human
+/// readable format, e.g `512B`, `12.4K`, `3.1M`
+pub fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{}B", bytes),
+        1024..1048576 => format!("{:.1}K", bytes as f64 / 1024.0),
+        _ => format!("{:.1}M", bytes as f64 / 1048576.0),
+    }
+}
+
+/// formats the given duration in a short human readable
+/// format, e.g `3d`, `4h`, `12m`, `8s`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// formats the given unix timestamp as the time of
+/// the day in utc, e.g `09:42`
+pub fn format_time(timestamp: i64) -> String {
+    let secs = timestamp.rem_euclid(86400);
+    format!("{:02}:{:02}", secs / 3600, secs % 3600 / 60)
+}
+
+/// parses a short duration like the ones `format_duration`
+/// returns, e.g `30m`, `1h`, `2d`, a plain number is in seconds
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().ok()?;
+
+    let secs = match unit {
+        "s" | "" => amount,
+        "m" => amount * 60,
+        "h" => amount * 60 * 60,
+        "d" => amount * 60 * 60 * 24,
+        _ => return None,
+    };
+    Some(Duration::from_secs(secs))
+}
+
+/// returns the current time as a unix timestamp in seconds
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
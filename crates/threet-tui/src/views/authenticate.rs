@@ -11,13 +11,15 @@ use ratatui::widgets::Paragraph;
 use tokio::sync::mpsc::Sender;
 use tokio::task::JoinHandle;
 
-use threet_storage::get_database;
+use threet_storage::Database;
+use threet_storage::StorageError;
 use threet_storage::models::User;
 
 use crate::app::Mode;
 use crate::bind::BindCallback;
 use crate::event::Event;
 use crate::event::Key;
+use crate::event::KeyCode;
 use crate::notifications::Notification;
 use crate::utils::get_middle_area;
 use crate::widgets::ButtonWidget;
@@ -31,8 +33,6 @@ use super::View;
 
 mod combos;
 
-use combos::NORMAL_MODE_COMBOS;
-
 #[derive(Default, Clone)]
 enum FocuseArea {
     #[default]
@@ -42,16 +42,6 @@ enum FocuseArea {
 }
 
 impl FocuseArea {
-    #[inline]
-    fn is_username_field(&self) -> bool {
-        matches!(self, FocuseArea::UsernameField)
-    }
-
-    #[inline]
-    fn is_password_field(&self) -> bool {
-        matches!(self, FocuseArea::PasswordField)
-    }
-
     #[inline]
     fn is_authenticate_button(&self) -> bool {
         matches!(self, FocuseArea::AuthenticateButton)
@@ -78,6 +68,7 @@ impl FocuseIterator for FocuseArea {
 
 pub struct AuthenticateView {
     app_tx: Sender<Event>,
+    database: Database,
     focuse: Focuse<FocuseArea>,
 
     // authentication task will contain the task handler for the
@@ -90,7 +81,7 @@ pub struct AuthenticateView {
 }
 
 impl AuthenticateView {
-    pub fn new(app_tx: Sender<Event>) -> Self {
+    pub fn new(app_tx: Sender<Event>, database: Database) -> Self {
         let username = FieldBuilder::default()
             .min(2)
             .max(16)
@@ -104,6 +95,7 @@ impl AuthenticateView {
 
         AuthenticateView {
             app_tx,
+            database,
             username,
             password,
             authentication_task: None,
@@ -117,25 +109,34 @@ impl AuthenticateView {
             let username = self.username.value().to_string();
             let password = self.password.value().to_string();
             let app_tx = self.app_tx.clone();
+            let database = self.database.clone();
 
             async move {
-                match User::by_username_password(get_database(), &username, &password).await {
-                    Some(user) => {
-                        app_tx.send(Event::SetUser(user)).await.unwrap();
-                    }
-                    None => {
-                        let notification = Notification::error(
+                let notification =
+                    match User::by_username_password(database, &username, &password).await {
+                        Ok(user) => {
+                            app_tx.send(Event::SetUser(user)).await.unwrap();
+                            return;
+                        }
+                        Err(StorageError::NotFound) => Notification::warning(
                             "authentiation error".to_string(),
                             "couldn't authentication with given credentials".to_string(),
-                        );
-                        // the notification message should also trigger an unconditional
-                        // render to display the notification
-                        app_tx
-                            .send(Event::Notification((notification, Duration::from_secs(5))))
-                            .await
-                            .unwrap();
-                    }
-                }
+                        ),
+                        Err(err) => {
+                            log::error!("couldn't authenticate `{}`, {}", username, err);
+                            Notification::error(
+                                "database unavailable".to_string(),
+                                "couldn't reach the database, try again later".to_string(),
+                            )
+                        }
+                    };
+
+                // the notification message should also trigger an unconditional
+                // render to display the notification
+                app_tx
+                    .send(Event::Notification((notification, Duration::from_secs(5))))
+                    .await
+                    .unwrap();
             }
         }));
     }
@@ -163,7 +164,13 @@ impl View for AuthenticateView {
 
         match mode {
             Mode::Normal => combos::NORMAL_MODE_COMBOS.get(keys),
-            Mode::Insert => todo!(),
+            // tab and enter move between the fields in insert mode
+            // too, any other key is typed into the focused field
+            Mode::Insert => match keys.last().map(|key| &key.keycode) {
+                Some(KeyCode::Tab | KeyCode::Enter) => combos::NORMAL_MODE_COMBOS.get(keys),
+                Some(_) => Some(&combos::INSERT_MODE_CALLBACK),
+                None => None,
+            },
         }
     }
 
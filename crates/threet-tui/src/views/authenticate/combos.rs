@@ -3,17 +3,110 @@ use std::sync::LazyLock;
 
 use crate::app::Context;
 use crate::app::Mode;
+use crate::bind::BindCallback;
 use crate::bind::Binder;
 use crate::event::KeyCode;
+use crate::notifications::Notification;
+use crate::views::notify;
+
+use super::AuthenticateView;
+use super::FocuseArea;
 
 pub static NORMAL_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
     let mut combos = Binder::new();
     combos.add([KeyCode::Char('i'); 1], change_to_insert_mode);
+    combos.add([KeyCode::Char('j'); 1], focuse_next);
+    combos.add([KeyCode::Tab; 1], focuse_next);
+    combos.add([KeyCode::Char('k'); 1], focuse_previous);
+    combos.add([KeyCode::Enter; 1], submit);
     combos
 });
 
+/// insert mode has no combos, every key the user types is handled by this callback
+pub static INSERT_MODE_CALLBACK: BindCallback = insert_key;
+
 fn change_to_insert_mode<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
     Box::pin(async move {
         cx.state.mode = Mode::Insert;
     })
 }
+
+fn focuse_next<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<AuthenticateView>() {
+            view.focuse.next();
+        }
+    })
+}
+
+fn focuse_previous<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<AuthenticateView>() {
+            view.focuse.previous();
+        }
+    })
+}
+
+/// moves the focuse to the next area, or starts the
+/// authentication if the button is focused
+fn submit<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<AuthenticateView>() else {
+            return;
+        };
+
+        if view.focuse.is_authenticate_button() {
+            cx.state.mode = Mode::Normal;
+            // too short credentials can't match any user
+            if !view.username.valid() || !view.password.valid() {
+                let notification = Notification::warning(
+                    "invalid credentials".to_string(),
+                    "the username or the password is too short".to_string(),
+                );
+                notify(&cx.dispatcher, notification).await;
+                return;
+            }
+            view.start_authentication_task();
+        } else {
+            view.focuse.next();
+        }
+    })
+}
+
+fn insert_key<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(key) = cx.keys.last() else {
+            return;
+        };
+        let Some(view) = cx.compositor.current_view_as_mut::<AuthenticateView>() else {
+            return;
+        };
+
+        let field = match view.focuse.current() {
+            FocuseArea::UsernameField => &mut view.username,
+            FocuseArea::PasswordField => &mut view.password,
+            FocuseArea::AuthenticateButton => return,
+        };
+
+        match key.keycode {
+            KeyCode::Char(c) => {
+                field.push_char(c);
+            }
+            KeyCode::Space => {
+                field.push_char(' ');
+            }
+            KeyCode::Backspace => {
+                field.remove_char();
+            }
+            KeyCode::Left => {
+                field.move_left();
+            }
+            KeyCode::Right => {
+                field.move_right();
+            }
+            KeyCode::Home => field.move_home(),
+            KeyCode::End => field.move_end(),
+            _ => {}
+        }
+    })
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use ratatui::widgets::Paragraph;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+
+use threet_storage::Database;
+use threet_storage::StorageEvent;
+use threet_storage::models::Channel;
+use threet_storage::models::ReadMarker;
+use threet_storage::models::Unread;
+use threet_storage::models::User;
+
+use crate::app::Context;
+use crate::app::Mode;
+use crate::bind::BindCallback;
+use crate::bind::Binder;
+use crate::capabilities::Capabilities;
+use crate::event::Event;
+use crate::event::Key;
+use crate::event::KeyCode;
+use crate::notifications::Notification;
+use crate::session::Presence;
+use crate::session::SessionDirectory;
+
+use super::ChatView;
+use super::View;
+use super::notify;
+use super::request_render;
+
+static NORMAL_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Char('j'); 1], select_next);
+    combos.add([KeyCode::Down; 1], select_next);
+    combos.add([KeyCode::Char('k'); 1], select_previous);
+    combos.add([KeyCode::Up; 1], select_previous);
+    combos.add([KeyCode::Enter; 1], open_selected);
+    combos
+});
+
+fn select_next<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChannelsView>() {
+            let last = view.channels.lock().unwrap().len().saturating_sub(1);
+            view.selected = (view.selected + 1).min(last);
+        }
+    })
+}
+
+fn select_previous<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChannelsView>() {
+            view.selected = view.selected.saturating_sub(1);
+        }
+    })
+}
+
+/// replaces the channels list with the selected channel chat
+fn open_selected<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChannelsView>() else {
+            return;
+        };
+        let Some(channel) = view.selected() else {
+            return;
+        };
+        let Some(user) = cx.state.user.clone() else {
+            return;
+        };
+
+        let chat = ChatView::new(
+            cx.dispatcher.clone(),
+            cx.database.clone(),
+            channel,
+            user,
+            cx.capabilities,
+        );
+        cx.compositor.swap(Box::new(chat));
+    })
+}
+
+/// lists the channels with the user unread messages, the
+/// selected channel chat is opened in place of the list
+pub struct ChannelsView {
+    dispatcher: Sender<Event>,
+    database: Database,
+    user: User,
+    channels: Arc<Mutex<Vec<Channel>>>,
+    /// the unread counts by channel id
+    unread: Arc<Mutex<HashMap<i32, Unread>>>,
+    unread_task: Option<JoinHandle<()>>,
+    selected: usize,
+    sessions: Arc<dyn SessionDirectory>,
+    capabilities: Capabilities,
+    /// the members as of the last tick, the view is rendered
+    /// again only when someone's presence changed
+    members: Vec<(String, Presence)>,
+}
+
+impl ChannelsView {
+    pub fn new(
+        dispatcher: Sender<Event>,
+        database: Database,
+        user: User,
+        sessions: Arc<dyn SessionDirectory>,
+        capabilities: Capabilities,
+    ) -> Self {
+        let channels = Arc::<Mutex<Vec<Channel>>>::default();
+
+        tokio::spawn({
+            let channels = channels.clone();
+            let database = database.clone();
+            let dispatcher = dispatcher.clone();
+            async move {
+                match Channel::all(database).await {
+                    Ok(loaded) => {
+                        *channels.lock().unwrap() = loaded;
+                        request_render(&dispatcher).await;
+                    }
+                    Err(err) => {
+                        log::warn!("couldn't load the channels, {}", err);
+                        let notification = Notification::error(
+                            "channels error".to_string(),
+                            "couldn't load the channels".to_string(),
+                        );
+                        notify(&dispatcher, notification).await;
+                    }
+                }
+            }
+        });
+
+        let mut view = ChannelsView {
+            dispatcher,
+            database,
+            user,
+            channels,
+            unread: Arc::default(),
+            unread_task: None,
+            selected: 0,
+            sessions,
+            capabilities,
+            members: Vec::new(),
+        };
+        view.load_unread();
+        view
+    }
+
+    /// counts the unread messages again, a count that is still
+    /// loading is replaced since it's already outdated
+    fn load_unread(&mut self) {
+        if let Some(task) = self.unread_task.take() {
+            task.abort();
+        }
+
+        self.unread_task = Some(tokio::spawn({
+            let database = self.database.clone();
+            let user = self.user.clone();
+            let unread = self.unread.clone();
+            let dispatcher = self.dispatcher.clone();
+
+            async move {
+                match ReadMarker::unread(database, &user).await {
+                    Ok(counts) => {
+                        *unread.lock().unwrap() = counts
+                            .into_iter()
+                            .map(|count| (count.channel_id(), count))
+                            .collect();
+                        request_render(&dispatcher).await;
+                    }
+                    // the list is still usable without the counts
+                    Err(err) => log::warn!("couldn't count the unread messages, {}", err),
+                }
+            }
+        }));
+    }
+
+    /// returns the connected users with their presence, sorted by name
+    fn members(&self) -> Vec<(String, Presence)> {
+        let mut usernames = self
+            .sessions
+            .sessions()
+            .into_iter()
+            .filter_map(|session| session.username)
+            .collect::<Vec<_>>();
+        usernames.sort();
+        usernames.dedup();
+        usernames
+            .into_iter()
+            .filter_map(|username| {
+                let presence = self.sessions.presence(&username)?;
+                Some((username, presence))
+            })
+            .collect()
+    }
+
+    fn selected(&self) -> Option<Channel> {
+        let channels = self.channels.lock().unwrap();
+        let index = self.selected.min(channels.len().saturating_sub(1));
+        channels.get(index).cloned()
+    }
+}
+
+#[async_trait]
+impl View for ChannelsView {
+    fn name(&self) -> &str {
+        "channels"
+    }
+
+    async fn handle_keys<'a>(&self, keys: &[Key], mode: Mode) -> Option<&'a BindCallback> {
+        match mode {
+            Mode::Normal => NORMAL_MODE_COMBOS.get(keys),
+            Mode::Insert => None,
+        }
+    }
+
+    fn storage_event(&mut self, event: &StorageEvent) -> bool {
+        match event {
+            StorageEvent::MessageCreated(_) | StorageEvent::MessageDeleted(_) => {
+                self.load_unread();
+            }
+            StorageEvent::ChannelRead(marker) if marker.user_id() == self.user.id() => {
+                self.load_unread();
+            }
+            _ => {}
+        }
+        // the view is rendered again once the counts are loaded
+        false
+    }
+
+    /// the members presence changes with their activity
+    async fn tick(&mut self) -> bool {
+        let members = self.members();
+        if members == self.members {
+            return false;
+        }
+        self.members = members;
+        true
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let channels = self.channels.lock().unwrap();
+        let unread = self.unread.lock().unwrap();
+        let selected = self.selected.min(channels.len().saturating_sub(1));
+
+        let lines = channels
+            .iter()
+            .enumerate()
+            .map(|(i, channel)| {
+                let mut spans = vec![Span::raw(format!("#{}", channel.name()))];
+                if let Some(count) = unread.get(&channel.id()) {
+                    if count.messages() > 0 {
+                        spans.push(Span::styled(
+                            format!(" {}", count.messages()),
+                            Style::new().bold(),
+                        ));
+                    }
+                    if count.mentions() > 0 {
+                        spans.push(Span::styled(
+                            format!(" @{}", count.mentions()),
+                            Style::new().red().bold(),
+                        ));
+                    }
+                }
+                let line = Line::from(spans);
+                if i == selected {
+                    line.style(Style::new().black().on_yellow())
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let members = self.members();
+        let [channels_area, members_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(members.len() as u16 + 1),
+        ])
+        .areas(area);
+
+        Paragraph::new(lines)
+            .block(Block::new().title_top(format!(" {} channels ", channels.len())))
+            .render(channels_area, buf);
+
+        let lines = members
+            .iter()
+            .map(|(username, presence)| {
+                Line::from(vec![
+                    Span::styled(presence.symbol(self.capabilities.unicode), presence.style()),
+                    Span::raw(format!(" {}", username)),
+                ])
+            })
+            .collect::<Vec<_>>();
+        Paragraph::new(lines)
+            .block(Block::new().title_top(format!(" {} online ", members.len())))
+            .render(members_area, buf);
+    }
+}
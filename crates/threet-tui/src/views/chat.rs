@@ -1,19 +1,886 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
 use std::sync::LazyLock;
+use std::sync::Mutex;
 
 use async_trait::async_trait;
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use ratatui::widgets::Clear;
+use ratatui::widgets::Paragraph;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
 
-use super::View;
+use threet_storage::Database;
+use threet_storage::StorageError;
+use threet_storage::StorageEvent;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+use threet_storage::models::ReadMarker;
+use threet_storage::models::User;
 
+use crate::app::Context;
 use crate::app::Mode;
 use crate::bind::BindCallback;
 use crate::bind::Binder;
+use crate::capabilities::Capabilities;
+use crate::event::Event;
 use crate::event::Key;
+use crate::event::KeyCode;
+use crate::markdown::MessageFormatter;
+use crate::markdown::code_blocks;
+use crate::markdown::find_urls;
+use crate::markdown::strip_escapes;
+use crate::notifications::Notification;
+use crate::widgets::TextArea;
+
+use super::ThreadView;
+use super::View;
+use super::copy_to_clipboard;
+use super::message_header;
+use super::message_spans;
+use super::notify;
+use super::request_render;
+
+/// how many of the newest messages are loaded when the chat opens
+const HISTORY_SIZE: usize = 200;
+
+/// how many messages are loaded before and after a message the chat jumps to
+const JUMP_CONTEXT: usize = 50;
+
+/// how many columns the messages lines after the first one are
+/// indented, the body and the reactions are aligned with the author
+const BODY_INDENT: usize = 6;
+
+/// how many lines the composer grows to before scrolling
+const COMPOSER_HEIGHT: u16 = 6;
+
+static NORMAL_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Char('i'); 1], change_to_insert_mode);
+    combos.add([KeyCode::Char('j'); 1], select_next);
+    combos.add([KeyCode::Down; 1], select_next);
+    combos.add([KeyCode::Char('k'); 1], select_previous);
+    combos.add([KeyCode::Up; 1], select_previous);
+    combos.add([KeyCode::End; 1], follow);
+    combos.add([KeyCode::Char('u'); 1], jump_to_unread);
+    combos.add([KeyCode::Enter; 1], open_thread);
+    combos.add([KeyCode::Char('e'); 1], edit_selected);
+    combos.add([KeyCode::Char('d'), KeyCode::Char('d')], delete_selected);
+    combos.add([KeyCode::Char('r'); 1], open_picker);
+    combos.add([KeyCode::Char('y'), KeyCode::Char('y')], copy_message);
+    combos.add([KeyCode::Char('y'), KeyCode::Char('b')], copy_code_block);
+    combos.add([KeyCode::Char('y'), KeyCode::Char('u')], copy_url);
+    combos
+});
+
+/// the keys of the reactions picker, opened over the chat
+static PICKER_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Char('h'); 1], picker_previous);
+    combos.add([KeyCode::Left; 1], picker_previous);
+    combos.add([KeyCode::Char('l'); 1], picker_next);
+    combos.add([KeyCode::Right; 1], picker_next);
+    combos.add([KeyCode::Enter; 1], react);
+    combos.add([KeyCode::Char('q'); 1], close_picker);
+    combos.add([KeyCode::Char('r'); 1], close_picker);
+    combos
+});
+
+/// the reactions offered by the picker, as their stored
+/// shortcode and the emoji displayed for it
+const REACTIONS: [(&str, &str); 8] = [
+    ("+1", "\u{1f44d}"),
+    ("joy", "\u{1f602}"),
+    ("tada", "\u{1f389}"),
+    ("eyes", "\u{1f440}"),
+    ("rocket", "\u{1f680}"),
+    ("fire", "\u{1f525}"),
+    ("pray", "\u{1f64f}"),
+    ("sparkling_heart", "\u{1f496}"),
+];
+
+static INSERT_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Enter; 1], send);
+    combos
+});
+
+/// every other key typed in insert mode goes to the composer
+static INSERT_MODE_CALLBACK: BindCallback = insert_key;
+
+fn change_to_insert_mode<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        // an edit left for normal mode is dropped, the
+        // composer is back to writing new messages
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChatView>()
+            && view.editing.take().is_some()
+        {
+            view.composer.clear();
+        }
+        cx.state.mode = Mode::Insert;
+    })
+}
+
+fn select_next<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() {
+            let mut timeline = view.timeline.lock().unwrap();
+            // moving past the newest message follows the new messages again
+            timeline.selected = timeline
+                .selected
+                .map(|selected| selected + 1)
+                .filter(|selected| *selected < timeline.messages.len());
+        }
+    })
+}
+
+fn select_previous<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() {
+            let mut timeline = view.timeline.lock().unwrap();
+            timeline.selected = match timeline.selected {
+                Some(selected) => Some(selected.saturating_sub(1)),
+                None => timeline.messages.len().checked_sub(1),
+            };
+        }
+    })
+}
+
+fn follow<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() {
+            let live = view.timeline.lock().unwrap().live;
+            if live {
+                view.timeline.lock().unwrap().selected = None;
+                view.mark_read();
+            } else {
+                // the chat jumped to an old message, the newest
+                // messages are not loaded yet
+                view.load_history();
+            }
+        }
+    })
+}
+
+/// selects the first message after the read marker the chat
+/// opened with, loading the messages around it if needed
+fn jump_to_unread<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+        let reload = {
+            let mut timeline = view.timeline.lock().unwrap();
+            let Some(last_read) = timeline.last_read else {
+                return;
+            };
+            match timeline.first_unread() {
+                // the oldest loaded message is unread too, the
+                // first unread message is not loaded yet
+                Some(0) => Some(last_read + 1),
+                Some(first) => {
+                    timeline.selected = Some(first);
+                    None
+                }
+                None => None,
+            }
+        };
+        if let Some(first) = reload {
+            view.load(Some(first));
+        }
+    })
+}
+
+/// opens the selected message thread next to the chat
+fn open_thread<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+        let Some(root) = view.selected_message() else {
+            return;
+        };
+
+        let thread = ThreadView::new(
+            cx.dispatcher.clone(),
+            cx.database.clone(),
+            view.channel.clone(),
+            root,
+        );
+        cx.compositor
+            .split_view(Box::new(thread), crate::compositor::Layout::Vertical);
+    })
+}
+
+/// loads the selected message in the composer to edit
+/// it, only the message author can edit it
+fn edit_selected<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+        let Some(message) = view.selected_message() else {
+            return;
+        };
+        if message.is_deleted() {
+            return;
+        }
+        if cx.state.user.as_ref().map(User::id) != Some(message.user_id()) {
+            let notification = Notification::warning(
+                "chat".to_string(),
+                "you can only edit your own messages".to_string(),
+            );
+            notify(&cx.dispatcher, notification).await;
+            return;
+        }
+
+        view.composer.set_value(message.body());
+        view.editing = Some(message.id());
+        cx.state.mode = Mode::Insert;
+    })
+}
+
+/// deletes the selected message, the message author and
+/// the moderators can delete it
+fn delete_selected<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+        let Some(message) = view.selected_message() else {
+            return;
+        };
+        let Some(user) = cx.state.user.clone() else {
+            return;
+        };
+        if message.is_deleted() {
+            return;
+        }
 
-pub struct ChatView {}
+        // the tombstone is displayed once the storage publishes it
+        let database = view.database.clone();
+        let dispatcher = cx.dispatcher.clone();
+        tokio::spawn(async move {
+            let notification = match Message::delete(database, message.id(), &user).await {
+                Ok(_) => return,
+                Err(StorageError::PermissionDenied) => Notification::warning(
+                    "chat".to_string(),
+                    "you can only delete your own messages".to_string(),
+                ),
+                Err(err) => {
+                    log::warn!("couldn't delete message {}, {}", message.id(), err);
+                    Notification::error(
+                        "chat error".to_string(),
+                        "couldn't delete the message".to_string(),
+                    )
+                }
+            };
+            notify(&dispatcher, notification).await;
+        });
+    })
+}
+
+/// copies the selected message body to the clipboard, as it was written
+fn copy_message<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+        let Some(message) = view.selected_message() else {
+            return;
+        };
+        if message.is_deleted() {
+            return;
+        }
+        copy_to_clipboard(
+            &cx.dispatcher,
+            cx.capabilities,
+            strip_escapes(message.body()).into_owned(),
+        )
+        .await;
+    })
+}
+
+/// copies a code block of the selected message to the clipboard, as it
+/// was written, the first block first and the next one on every repeat
+fn copy_code_block<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+        let Some(message) = view.selected_message() else {
+            return;
+        };
+
+        let message_id = message.id();
+        let mut blocks = code_blocks(message.body());
+        let index = match view.copied_block {
+            Some((id, index)) if id == message_id && index + 1 < blocks.len() => index + 1,
+            _ => 0,
+        };
+
+        match blocks.len() {
+            0 => {
+                let notification = Notification::warning(
+                    "chat".to_string(),
+                    "the message has no code block".to_string(),
+                );
+                notify(&cx.dispatcher, notification).await;
+            }
+            count => {
+                view.copied_block = Some((message_id, index));
+                if count > 1 {
+                    let notification = Notification::info(
+                        "chat".to_string(),
+                        format!("code block {} of {}", index + 1, count),
+                    );
+                    notify(&cx.dispatcher, notification).await;
+                }
+                let code = blocks.swap_remove(index);
+                copy_to_clipboard(&cx.dispatcher, cx.capabilities, code).await;
+            }
+        }
+    })
+}
+
+/// copies the first url of the selected message to the clipboard
+fn copy_url<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+        let Some(message) = view.selected_message() else {
+            return;
+        };
+
+        let body = strip_escapes(message.body());
+        match find_urls(&body).into_iter().next() {
+            Some(url) => {
+                copy_to_clipboard(&cx.dispatcher, cx.capabilities, body[url].to_string()).await
+            }
+            None => {
+                let notification =
+                    Notification::warning("chat".to_string(), "the message has no url".to_string());
+                notify(&cx.dispatcher, notification).await;
+            }
+        }
+    })
+}
+
+/// opens the reactions picker for the selected message
+fn open_picker<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChatView>()
+            && view
+                .selected_message()
+                .is_some_and(|message| !message.is_deleted())
+        {
+            view.picker = Some(0);
+        }
+    })
+}
+
+fn close_picker<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() {
+            view.picker = None;
+        }
+    })
+}
+
+fn picker_next<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() {
+            view.picker = view.picker.map(|picked| (picked + 1) % REACTIONS.len());
+        }
+    })
+}
+
+fn picker_previous<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() {
+            view.picker = view
+                .picker
+                .map(|picked| (picked + REACTIONS.len() - 1) % REACTIONS.len());
+        }
+    })
+}
+
+/// reacts to the selected message with the picked emoji, reacting
+/// again with the same emoji removes the reaction
+fn react<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+        let Some(picked) = view.picker.take() else {
+            return;
+        };
+        let (Some(message), Some(user)) = (view.selected_message(), cx.state.user.clone()) else {
+            return;
+        };
+
+        // the reactions are updated once the storage publishes them
+        let (emoji, _) = REACTIONS[picked];
+        let database = view.database.clone();
+        let dispatcher = cx.dispatcher.clone();
+        tokio::spawn(async move {
+            if let Err(err) = Message::react(database, message.id(), &user, emoji).await {
+                log::warn!("couldn't react to message {}, {}", message.id(), err);
+                let notification = Notification::error(
+                    "chat error".to_string(),
+                    "couldn't react to the message".to_string(),
+                );
+                notify(&dispatcher, notification).await;
+            }
+        });
+    })
+}
+
+/// sends the composer content as a new message, or as
+/// the new body of the message being edited
+fn send<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+        let body = view.composer.value().trim().to_string();
+        if body.is_empty() {
+            return;
+        }
+        let Some(user) = cx.state.user.clone() else {
+            let notification = Notification::warning(
+                "chat".to_string(),
+                "login first to send messages".to_string(),
+            );
+            notify(&cx.dispatcher, notification).await;
+            return;
+        };
+        view.composer.submit();
+
+        // the message is displayed once the storage publishes it
+        let channel = view.channel.clone();
+        let database = view.database.clone();
+        let dispatcher = cx.dispatcher.clone();
+
+        if let Some(message_id) = view.editing.take() {
+            tokio::spawn(async move {
+                if let Err(err) = Message::edit(database, message_id, &user, &body).await {
+                    log::warn!("couldn't edit message {}, {}", message_id, err);
+                    let notification = Notification::error(
+                        "chat error".to_string(),
+                        "couldn't edit the message".to_string(),
+                    );
+                    notify(&dispatcher, notification).await;
+                }
+            });
+            return;
+        }
+
+        tokio::spawn(async move {
+            if let Err(err) = Message::create(database, &channel, &user, &body).await {
+                log::warn!("couldn't send message to #{}, {}", channel.name(), err);
+                let notification = Notification::error(
+                    "chat error".to_string(),
+                    "couldn't send the message".to_string(),
+                );
+                notify(&dispatcher, notification).await;
+            }
+        });
+    })
+}
+
+fn insert_key<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(key) = cx.keys.last() else {
+            return;
+        };
+        let Some(view) = cx.compositor.current_view_as_mut::<ChatView>() else {
+            return;
+        };
+
+        view.composer.handle_key(key);
+    })
+}
+
+/// the channel messages the chat displays, shared with
+/// the background task loading them
+#[derive(Default)]
+struct Timeline {
+    messages: Vec<Message>,
+    /// the selected message index, `None` follows the newest message
+    selected: Option<usize>,
+    /// the newest channel message is loaded, so new messages
+    /// can be appended as they are sent
+    live: bool,
+    loading: bool,
+    /// the last read message when the chat opened, the
+    /// messages after it are drawn after a divider
+    last_read: Option<i64>,
+}
+
+impl Timeline {
+    /// appends a new message, messages already in the timeline
+    /// are ignored since they may arrive while loading
+    fn push(&mut self, message: Message) -> bool {
+        if !self.live
+            || self
+                .messages
+                .last()
+                .is_some_and(|last| last.id() >= message.id())
+        {
+            return false;
+        }
+        self.messages.push(message);
+        true
+    }
+
+    /// counts a new reply to the thread of a loaded message
+    fn count_reply(&mut self, reply: &Message) -> bool {
+        let Some(root) = self
+            .messages
+            .iter_mut()
+            .find(|message| Some(message.id()) == reply.parent_id())
+        else {
+            return false;
+        };
+        root.count_reply();
+        true
+    }
+
+    /// the index of the first loaded message after the read marker
+    fn first_unread(&self) -> Option<usize> {
+        let last_read = self.last_read?;
+        self.messages
+            .iter()
+            .position(|message| message.id() > last_read)
+    }
+
+    /// replaces a loaded message with its edited or deleted version
+    fn replace(&mut self, changed: &Message) -> bool {
+        let Some(message) = self
+            .messages
+            .iter_mut()
+            .find(|message| message.id() == changed.id())
+        else {
+            return false;
+        };
+        *message = changed.clone();
+        true
+    }
+}
+
+/// displays a channel messages as they are sent, and a
+/// composer to send new messages to the channel
+pub struct ChatView {
+    dispatcher: Sender<Event>,
+    database: Database,
+    channel: Channel,
+    user: User,
+    timeline: Arc<Mutex<Timeline>>,
+    loader: Option<JoinHandle<()>>,
+    composer: TextArea,
+    /// the message the composer edits, `None` when
+    /// the composer writes a new message
+    editing: Option<i64>,
+    /// the reaction picked in the reactions picker, `None`
+    /// when the picker is closed
+    picker: Option<usize>,
+    /// the message and the index of the code block copied last,
+    /// copying again from the same message copies the next block
+    copied_block: Option<(i64, usize)>,
+    capabilities: Capabilities,
+    /// the formatted bodies by message id, formatting highlights the
+    /// code blocks which is too slow to do again on every render
+    formatted: Mutex<HashMap<i64, FormattedBody>>,
+}
+
+/// a message body formatted to the given width
+struct FormattedBody {
+    body: String,
+    width: u16,
+    lines: Vec<Line<'static>>,
+}
 
 impl ChatView {
-    pub fn new() -> Self {
-        ChatView {}
+    /// opens the chat on the newest messages of the given channel, the
+    /// messages the user didn't read yet are marked by a divider
+    pub fn new(
+        dispatcher: Sender<Event>,
+        database: Database,
+        channel: Channel,
+        user: User,
+        capabilities: Capabilities,
+    ) -> Self {
+        let timeline = Arc::<Mutex<Timeline>>::default();
+
+        tokio::spawn({
+            let database = database.clone();
+            let user = user.clone();
+            let channel_id = channel.id();
+            let timeline = timeline.clone();
+            let dispatcher = dispatcher.clone();
+
+            async move {
+                match ReadMarker::last_read(database, &user, channel_id).await {
+                    Ok(marker) => {
+                        timeline.lock().unwrap().last_read =
+                            marker.as_ref().map(ReadMarker::message_id);
+                        request_render(&dispatcher).await;
+                    }
+                    Err(err) => log::warn!("couldn't load the read marker, {}", err),
+                }
+            }
+        });
+
+        let mut view = ChatView {
+            dispatcher,
+            database,
+            channel,
+            user,
+            timeline,
+            loader: None,
+            composer: TextArea::new(COMPOSER_HEIGHT),
+            editing: None,
+            picker: None,
+            copied_block: None,
+            capabilities,
+            formatted: Mutex::default(),
+        };
+        view.load_history();
+        view
+    }
+
+    /// loads the messages around the given message and selects it
+    pub fn jump_to(&mut self, message_id: i64) {
+        self.load(Some(message_id));
+    }
+
+    /// marks the channel as read up to the newest message, once the
+    /// chat displays it, the divider stays where the chat opened
+    fn mark_read(&self) {
+        let newest = {
+            let timeline = self.timeline.lock().unwrap();
+            if !timeline.live || timeline.selected.is_some() {
+                return;
+            }
+            match timeline.messages.last() {
+                Some(message) => message.id(),
+                None => return,
+            }
+        };
+        spawn_mark_read(
+            self.database.clone(),
+            self.user.clone(),
+            self.channel.id(),
+            newest,
+        );
+    }
+
+    fn selected_message(&self) -> Option<Message> {
+        let timeline = self.timeline.lock().unwrap();
+        timeline
+            .selected
+            .and_then(|selected| timeline.messages.get(selected))
+            .cloned()
+    }
+
+    /// the emoji of the given shortcode, the terminals that can't
+    /// display unicode get the shortcode itself
+    fn emoji<'a>(&self, shortcode: &'a str) -> Cow<'a, str> {
+        let emoji = REACTIONS
+            .iter()
+            .find(|(other, _)| *other == shortcode)
+            .map(|(_, emoji)| *emoji);
+        match emoji {
+            Some(emoji) if self.capabilities.unicode => Cow::Borrowed(emoji),
+            _ => Cow::Owned(format!(":{}:", shortcode)),
+        }
+    }
+
+    /// the message body formatted to the given width, formatted again
+    /// only if the body was edited or the width changed
+    fn formatted_body(&self, message: &Message, width: u16) -> Vec<Line<'static>> {
+        let mut formatted = self.formatted.lock().unwrap();
+        if let Some(cached) = formatted.get(&message.id())
+            && cached.width == width
+            && cached.body == message.body()
+        {
+            return cached.lines.clone();
+        }
+
+        let lines = MessageFormatter::new(width)
+            .prefix(message_header(message))
+            .indent(BODY_INDENT)
+            .capabilities(self.capabilities)
+            .format(message.body());
+        formatted.insert(
+            message.id(),
+            FormattedBody {
+                body: message.body().to_string(),
+                width,
+                lines: lines.clone(),
+            },
+        );
+        lines
+    }
+
+    /// the formatted message lines wrapped to the given width,
+    /// followed by its reactions line if anyone reacted to it
+    fn message_lines<'a>(&self, message: &'a Message, selected: bool, width: u16) -> Vec<Line<'a>> {
+        let mut lines = if message.is_deleted() {
+            vec![Line::from(message_spans(message))]
+        } else {
+            self.formatted_body(message, width)
+        };
+
+        let last = lines
+            .last_mut()
+            .expect("the formatted messages have a line");
+        if message.edited_at().is_some() && !message.is_deleted() {
+            last.spans
+                .push(Span::styled(" (edited)", Style::new().dark_gray()));
+        }
+        match message.reply_count() {
+            0 => {}
+            1 => last
+                .spans
+                .push(Span::styled(" 1 reply", Style::new().cyan())),
+            n => last
+                .spans
+                .push(Span::styled(format!(" {} replies", n), Style::new().cyan())),
+        }
+        if selected {
+            for line in &mut lines {
+                line.style = Style::new().black().on_yellow();
+            }
+        }
+        if message.reactions().is_empty() {
+            return lines;
+        }
+
+        let mut reactions = vec![Span::raw(" ".repeat(BODY_INDENT - 1))];
+        for reaction in message.reactions() {
+            reactions.push(Span::raw(" "));
+            reactions.push(Span::raw(self.emoji(reaction.emoji())));
+            reactions.push(Span::styled(
+                format!(" {}", reaction.count()),
+                Style::new().dark_gray(),
+            ));
+        }
+        lines.push(Line::from(reactions));
+        lines
+    }
+
+    fn load_history(&mut self) {
+        self.load(None);
+    }
+
+    /// replaces the timeline with the newest messages, or with the
+    /// messages around the given message
+    fn load(&mut self, around: Option<i64>) {
+        if let Some(loader) = self.loader.take() {
+            loader.abort();
+        }
+
+        // the cached bodies are for the messages being replaced
+        self.formatted.lock().unwrap().clear();
+        {
+            let mut timeline = self.timeline.lock().unwrap();
+            timeline.messages.clear();
+            timeline.selected = None;
+            // the messages sent while loading the newest messages are
+            // kept, and merged with the loaded ones
+            timeline.live = around.is_none();
+            timeline.loading = true;
+        }
+
+        self.loader = Some(tokio::spawn({
+            let database = self.database.clone();
+            let user = self.user.clone();
+            let channel = self.channel.clone();
+            let timeline = self.timeline.clone();
+            let dispatcher = self.dispatcher.clone();
+
+            async move {
+                let loaded = match around {
+                    Some(id) => {
+                        Message::around(database.clone(), channel.id(), id, JUMP_CONTEXT).await
+                    }
+                    None => Message::history(database.clone(), channel.id(), 0, HISTORY_SIZE).await,
+                };
+
+                let messages = match loaded {
+                    Ok(messages) => messages,
+                    Err(err) => {
+                        log::warn!("couldn't load #{} messages, {}", channel.name(), err);
+                        timeline.lock().unwrap().loading = false;
+                        let notification = Notification::error(
+                            "chat error".to_string(),
+                            format!("couldn't load #{} messages", channel.name()),
+                        );
+                        notify(&dispatcher, notification).await;
+                        return;
+                    }
+                };
+
+                let newest = {
+                    let mut timeline = timeline.lock().unwrap();
+                    let sent_while_loading = std::mem::replace(&mut timeline.messages, messages);
+                    timeline.loading = false;
+
+                    if let Some(id) = around {
+                        // the message may be gone, then the one after it is selected
+                        timeline.selected = timeline
+                            .messages
+                            .iter()
+                            .position(|message| message.id() >= id);
+                        // less messages after the selected one than requested
+                        // means the newest message is loaded
+                        let after = timeline.messages.len()
+                            - timeline.selected.map_or(0, |selected| selected + 1);
+                        timeline.live = after < JUMP_CONTEXT;
+                    } else {
+                        for message in sent_while_loading {
+                            timeline.push(message);
+                        }
+                    }
+                    match timeline.selected {
+                        None if timeline.live => timeline.messages.last().map(Message::id),
+                        _ => None,
+                    }
+                };
+                if let Some(newest) = newest {
+                    spawn_mark_read(database, user, channel.id(), newest);
+                }
+                request_render(&dispatcher).await;
+            }
+        }));
+    }
+}
+
+/// stores the read marker in the background, a marker that couldn't
+/// be stored only leaves the messages unread so it's only logged
+fn spawn_mark_read(database: Database, user: User, channel_id: i32, message_id: i64) {
+    tokio::spawn(async move {
+        if let Err(err) = ReadMarker::mark(database, &user, channel_id, message_id).await {
+            log::warn!("couldn't mark channel {} as read, {}", channel_id, err);
+        }
+    });
+}
+
+/// adds the lines of a message before the given lines, the
+/// first lines are dropped past the given height
+fn prepend<'a>(lines: &mut VecDeque<Line<'a>>, message: Vec<Line<'a>>, height: usize) {
+    for line in message.into_iter().rev() {
+        lines.push_front(line);
+    }
+    while lines.len() > height {
+        lines.pop_front();
     }
 }
 
@@ -23,9 +890,139 @@ impl View for ChatView {
         "chat"
     }
 
-    fn render(&self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {}
+    async fn handle_keys<'a>(&self, keys: &[Key], mode: Mode) -> Option<&'a BindCallback> {
+        match mode {
+            Mode::Normal if self.picker.is_some() => PICKER_COMBOS.get(keys),
+            Mode::Normal => NORMAL_MODE_COMBOS.get(keys),
+            Mode::Insert => INSERT_MODE_COMBOS.get(keys).or(Some(&INSERT_MODE_CALLBACK)),
+        }
+    }
+
+    fn storage_event(&mut self, event: &StorageEvent) -> bool {
+        match event {
+            StorageEvent::MessageCreated(message) if message.channel_id() == self.channel.id() => {
+                let mut timeline = self.timeline.lock().unwrap();
+                // replies are displayed in their thread, the
+                // chat only shows the thread replies count
+                let changed = match message.parent_id() {
+                    Some(_) => timeline.count_reply(message),
+                    None => timeline.push(message.clone()),
+                };
+                drop(timeline);
+                if changed {
+                    self.mark_read();
+                }
+                changed
+            }
+            StorageEvent::MessageEdited(message)
+            | StorageEvent::MessageDeleted(message)
+            | StorageEvent::MessageReacted(message)
+                if message.channel_id() == self.channel.id() =>
+            {
+                self.timeline.lock().unwrap().replace(message)
+            }
+            _ => false,
+        }
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::new().title_top(format!(" #{} ", self.channel.name()).bold());
+        let inner = block.inner(area);
+        let [messages_area, composer_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(self.composer.height(inner.width)),
+        ])
+        .areas(inner);
+        block.render(area, buf);
+
+        let timeline = self.timeline.lock().unwrap();
+        if timeline.loading && timeline.messages.is_empty() {
+            Paragraph::new("loading")
+                .centered()
+                .render(messages_area, buf);
+        } else if !timeline.messages.is_empty() {
+            let height = messages_area.height as usize;
+            let len = timeline.messages.len();
+            let first_unread = timeline.first_unread();
+            let lines_of = |i: usize| {
+                let mut lines = Vec::new();
+                if first_unread == Some(i) {
+                    lines.push(
+                        Line::from(" new messages ")
+                            .centered()
+                            .style(Style::new().red()),
+                    );
+                }
+                lines.extend(self.message_lines(
+                    &timeline.messages[i],
+                    timeline.selected == Some(i),
+                    messages_area.width,
+                ));
+                lines
+            };
+
+            // the selected message is kept in the middle, without a
+            // selection the newest messages are at the bottom, the
+            // messages with reactions take more than a line
+            let anchor = timeline.selected.unwrap_or(len - 1);
+            let above = match timeline.selected {
+                Some(_) => height / 2,
+                None => height,
+            };
+            let mut lines = VecDeque::from(lines_of(anchor));
+            let (mut before, mut after) = (anchor, anchor + 1);
+            while before > 0 && lines.len() < above {
+                before -= 1;
+                prepend(&mut lines, lines_of(before), height);
+            }
+            while after < len && lines.len() < height {
+                lines.extend(lines_of(after));
+                lines.truncate(height);
+                after += 1;
+            }
+            while before > 0 && lines.len() < height {
+                before -= 1;
+                prepend(&mut lines, lines_of(before), height);
+            }
+            if lines.len() > height {
+                lines.drain(..lines.len() - height);
+            }
+
+            // fewer messages than lines are drawn at the bottom
+            let [_, lines_area] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Length(lines.len() as u16)])
+                    .areas(messages_area);
+            Paragraph::new(Vec::from(lines)).render(lines_area, buf);
+        }
+
+        if let Some(selected) = self.picker {
+            let [_, picker_area] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).areas(messages_area);
+            let spans = REACTIONS
+                .iter()
+                .enumerate()
+                .flat_map(|(i, (shortcode, _))| {
+                    let emoji = Span::raw(self.emoji(shortcode));
+                    let emoji = if i == selected {
+                        emoji.style(Style::new().black().on_yellow())
+                    } else {
+                        emoji
+                    };
+                    [Span::raw(" "), emoji]
+                });
+            Clear.render(picker_area, buf);
+            Paragraph::new(Line::from(spans.collect::<Vec<_>>()))
+                .block(Block::bordered().title_top(" react "))
+                .render(picker_area, buf);
+        }
 
-    async fn handle_keys<'a>(&self, key: &[Key], mode: Mode) -> Option<&'a BindCallback> {
-        None
+        let placeholder = match self.editing {
+            Some(_) => "edit message...".to_string(),
+            None => format!("message #{}...", self.channel.name()),
+        };
+        self.composer
+            .widget()
+            .placeholder(&placeholder)
+            .render(composer_area, buf);
     }
 }
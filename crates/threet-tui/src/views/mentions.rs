@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use ratatui::widgets::Paragraph;
+use tokio::sync::mpsc::Sender;
+
+use threet_storage::Database;
+use threet_storage::StorageEvent;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+use threet_storage::models::User;
+
+use crate::app::Context;
+use crate::app::Mode;
+use crate::bind::BindCallback;
+use crate::bind::Binder;
+use crate::event::Event;
+use crate::event::Key;
+use crate::event::KeyCode;
+use crate::notifications::Notification;
+
+use super::ChatView;
+use super::View;
+use super::message_spans;
+use super::notify;
+use super::request_render;
+
+/// how many of the newest mentions are listed
+const MENTIONS_SIZE: usize = 50;
+
+static NORMAL_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Char('j'); 1], select_next);
+    combos.add([KeyCode::Down; 1], select_next);
+    combos.add([KeyCode::Char('k'); 1], select_previous);
+    combos.add([KeyCode::Up; 1], select_previous);
+    combos.add([KeyCode::Enter; 1], open_selected);
+    combos
+});
+
+fn select_next<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<MentionsView>() {
+            view.selected = view.selected.saturating_add(1);
+        }
+    })
+}
+
+fn select_previous<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<MentionsView>() {
+            view.selected = view.selected.saturating_sub(1);
+        }
+    })
+}
+
+/// replaces the mentions with the selected mention channel chat,
+/// the chat is scrolled to the mention
+fn open_selected<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<MentionsView>() else {
+            return;
+        };
+        let Some((channel, message_id)) = view.selected() else {
+            return;
+        };
+
+        let mut chat = ChatView::new(
+            cx.dispatcher.clone(),
+            cx.database.clone(),
+            channel,
+            view.user.clone(),
+            cx.capabilities,
+        );
+        chat.jump_to(message_id);
+        cx.compositor.swap(Box::new(chat));
+    })
+}
+
+/// the listed mentions, shared with the task loading them
+#[derive(Default)]
+struct Mentions {
+    messages: Vec<Message>,
+    /// the channels by their id, to display the mentions channel name
+    channels: HashMap<i32, Channel>,
+    loading: bool,
+}
+
+/// lists the newest messages mentioning the user, the new
+/// mentions are added on top as they are sent
+pub struct MentionsView {
+    user: User,
+    mentions: Arc<Mutex<Mentions>>,
+    selected: usize,
+}
+
+impl MentionsView {
+    pub fn new(dispatcher: Sender<Event>, database: Database, user: User) -> Self {
+        let mentions = Arc::new(Mutex::new(Mentions {
+            loading: true,
+            ..Default::default()
+        }));
+
+        tokio::spawn({
+            let mentions = mentions.clone();
+            let user = user.clone();
+
+            async move {
+                let loaded = async {
+                    let channels = Channel::all(database.clone())
+                        .await?
+                        .into_iter()
+                        .map(|channel| (channel.id(), channel))
+                        .collect::<HashMap<_, _>>();
+                    let messages = Message::mentioning(database, &user, MENTIONS_SIZE).await?;
+                    Ok::<_, threet_storage::StorageError>((messages, channels))
+                };
+
+                match loaded.await {
+                    Ok((messages, channels)) => {
+                        let mut mentions = mentions.lock().unwrap();
+                        // the mentions sent while loading are kept on top
+                        let sent_while_loading = std::mem::take(&mut mentions.messages);
+                        mentions.messages = messages;
+                        for message in sent_while_loading.into_iter().rev() {
+                            if !mentions
+                                .messages
+                                .iter()
+                                .any(|other| other.id() == message.id())
+                            {
+                                mentions.messages.insert(0, message);
+                            }
+                        }
+                        mentions.channels = channels;
+                        mentions.loading = false;
+                    }
+                    Err(err) => {
+                        log::warn!("couldn't load the mentions, {}", err);
+                        mentions.lock().unwrap().loading = false;
+                        let notification = Notification::error(
+                            "mentions error".to_string(),
+                            "couldn't load the mentions".to_string(),
+                        );
+                        notify(&dispatcher, notification).await;
+                        return;
+                    }
+                }
+                request_render(&dispatcher).await;
+            }
+        });
+
+        MentionsView {
+            user,
+            mentions,
+            selected: 0,
+        }
+    }
+
+    /// the selected mention channel and the message shown in the channel,
+    /// replies are shown in the channel as their thread root message
+    fn selected(&self) -> Option<(Channel, i64)> {
+        let mentions = self.mentions.lock().unwrap();
+        let index = self.selected.min(mentions.messages.len().saturating_sub(1));
+        let message = mentions.messages.get(index)?;
+        let channel = mentions.channels.get(&message.channel_id())?.clone();
+        Some((channel, message.parent_id().unwrap_or(message.id())))
+    }
+}
+
+#[async_trait]
+impl View for MentionsView {
+    fn name(&self) -> &str {
+        "mentions"
+    }
+
+    async fn handle_keys<'a>(&self, keys: &[Key], mode: Mode) -> Option<&'a BindCallback> {
+        match mode {
+            Mode::Normal => NORMAL_MODE_COMBOS.get(keys),
+            Mode::Insert => None,
+        }
+    }
+
+    fn storage_event(&mut self, event: &StorageEvent) -> bool {
+        let mut mentions = self.mentions.lock().unwrap();
+        match event {
+            StorageEvent::MessageCreated(message) if message.mentions(&self.user) => {
+                mentions.messages.insert(0, message.clone());
+                mentions.messages.truncate(MENTIONS_SIZE);
+                true
+            }
+            StorageEvent::MessageEdited(message) | StorageEvent::MessageReacted(message) => {
+                match mentions
+                    .messages
+                    .iter_mut()
+                    .find(|other| other.id() == message.id())
+                {
+                    Some(other) => {
+                        *other = message.clone();
+                        true
+                    }
+                    None => false,
+                }
+            }
+            // the deleted messages don't mention anyone anymore
+            StorageEvent::MessageDeleted(message) => {
+                let len = mentions.messages.len();
+                mentions.messages.retain(|other| other.id() != message.id());
+                mentions.messages.len() != len
+            }
+            _ => false,
+        }
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let mentions = self.mentions.lock().unwrap();
+        let block =
+            Block::new().title_top(format!(" {} mentions ", mentions.messages.len()).bold());
+
+        if mentions.loading && mentions.messages.is_empty() {
+            Paragraph::new("loading")
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+        if mentions.messages.is_empty() {
+            Paragraph::new("no mentions yet")
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let selected = self.selected.min(mentions.messages.len().saturating_sub(1));
+        let lines = mentions.messages.iter().enumerate().map(|(i, message)| {
+            let channel = mentions
+                .channels
+                .get(&message.channel_id())
+                .map(Channel::name)
+                .unwrap_or("?");
+
+            let mut spans = vec![Span::styled(
+                format!("#{} ", channel),
+                Style::new().dark_gray(),
+            )];
+            spans.extend(message_spans(message));
+            let line = Line::from(spans);
+            if i == selected {
+                line.style(Style::new().on_dark_gray())
+            } else {
+                line
+            }
+        });
+
+        Paragraph::new(lines.collect::<Vec<_>>())
+            .block(block)
+            .render(area, buf);
+    }
+}
@@ -1,17 +1,97 @@
 use std::any::Any;
 use std::ops::Deref;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use ratatui::prelude::*;
+use tokio::sync::mpsc::Sender;
+
+use threet_storage::StorageEvent;
+use threet_storage::models::Message;
 
 mod authenticate;
+mod channels;
 mod chat;
+mod mentions;
+mod search;
+mod sessions;
+mod thread;
 
 pub use authenticate::AuthenticateView;
+pub use channels::ChannelsView;
+pub use chat::ChatView;
+pub use mentions::MentionsView;
+pub use search::SearchView;
+pub use sessions::SessionsView;
+pub use thread::ThreadView;
 
 use crate::app::Mode;
 use crate::bind::BindCallback;
+use crate::capabilities::Capabilities;
+use crate::event::Event;
 use crate::event::Key;
+use crate::markdown::inline_spans;
+use crate::notifications::Notification;
+use crate::utils::format_time;
+
+/// how long the notifications pushed by the views are displayed
+pub(crate) const NOTIFICATION_DURATION: Duration = Duration::from_secs(5);
+
+/// pushes a notification from a view background task, the app may be
+/// gone by the time the task is done, then there is no one to notify
+async fn notify(dispatcher: &Sender<Event>, notification: Notification) {
+    let _ = dispatcher
+        .send(Event::Notification((notification, NOTIFICATION_DURATION)))
+        .await;
+}
+
+/// copies the given text to the terminal clipboard, the user
+/// is told if the terminal can't set its clipboard
+async fn copy_to_clipboard(dispatcher: &Sender<Event>, capabilities: Capabilities, text: String) {
+    if !capabilities.clipboard {
+        let notification = Notification::warning(
+            "clipboard".to_string(),
+            "the terminal doesn't support copying".to_string(),
+        );
+        notify(dispatcher, notification).await;
+        return;
+    }
+    let _ = dispatcher.send(Event::Clipboard(text)).await;
+}
+
+/// the time and author of a message as displayed in the chats
+fn message_header(message: &Message) -> Vec<Span<'static>> {
+    vec![
+        Span::styled(format_time(message.created_at()), Style::new().dark_gray()),
+        Span::raw(" "),
+        Span::styled(message.author().to_string(), Style::new().bold()),
+        Span::raw(" "),
+    ]
+}
+
+/// the time, author and body of a message on a single line, deleted
+/// messages are displayed as a tombstone
+fn message_spans(message: &Message) -> Vec<Span<'static>> {
+    let mut spans = message_header(message);
+    if message.is_deleted() {
+        spans.push(Span::styled(
+            "message deleted",
+            Style::new().dark_gray().italic(),
+        ));
+        return spans;
+    }
+    spans.extend(inline_spans(message.body(), Style::new()));
+    if message.edited_at().is_some() {
+        spans.push(Span::styled(" (edited)", Style::new().dark_gray()));
+    }
+    spans
+}
+
+/// asks the app to render again once a view background task
+/// changed the view, the app may be gone like in `notify`
+async fn request_render(dispatcher: &Sender<Event>) {
+    let _ = dispatcher.send(Event::Render).await;
+}
 
 /// each view has a single focuse area, users can change their focuse
 /// usually when they are in Normal mode via TAB | j | k keys, this iterator
@@ -86,6 +166,15 @@ pub trait View: Any + Send + 'static {
     /// the returned boolean indicate if the app should rerender
     async fn handle_keys<'a>(&self, keys: &[Key], mode: Mode) -> Option<&'a BindCallback>;
 
-    /// called on every tick so the view can update its internal state
-    async fn tick(&mut self) {}
+    /// called on every tick so the view can update its internal state, the
+    /// returned boolean indicate if the view changed and should be rendered again
+    async fn tick(&mut self) -> bool {
+        false
+    }
+
+    /// called for every storage event, the returned boolean indicate
+    /// if the view changed and should be rendered again
+    fn storage_event(&mut self, _event: &StorageEvent) -> bool {
+        false
+    }
 }
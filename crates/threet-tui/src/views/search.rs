@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use ratatui::widgets::Paragraph;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+
+use threet_storage::Database;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+use threet_storage::models::SearchQuery;
+use threet_storage::models::SearchResult;
+
+use crate::app::Context;
+use crate::app::Mode;
+use crate::bind::BindCallback;
+use crate::bind::Binder;
+use crate::event::Event;
+use crate::event::Key;
+use crate::event::KeyCode;
+use crate::markdown::strip_escapes;
+use crate::notifications::Notification;
+use crate::utils::format_time;
+use crate::utils::parse_duration;
+use crate::utils::unix_now;
+use crate::widgets::Field;
+use crate::widgets::FieldBuilder;
+
+use super::ChatView;
+use super::View;
+use super::notify;
+use super::request_render;
+
+static NORMAL_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Char('i'); 1], change_to_insert_mode);
+    combos.add([KeyCode::Char('j'); 1], select_next);
+    combos.add([KeyCode::Down; 1], select_next);
+    combos.add([KeyCode::Char('k'); 1], select_previous);
+    combos.add([KeyCode::Up; 1], select_previous);
+    combos.add([KeyCode::Enter; 1], open_selected);
+    combos
+});
+
+static INSERT_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Enter; 1], search);
+    combos
+});
+
+/// every other key typed in insert mode goes to the query
+static INSERT_MODE_CALLBACK: BindCallback = insert_key;
+
+fn change_to_insert_mode<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        cx.state.mode = Mode::Insert;
+    })
+}
+
+fn select_next<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<SearchView>() {
+            view.selected = view.selected.saturating_add(1);
+        }
+    })
+}
+
+fn select_previous<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<SearchView>() {
+            view.selected = view.selected.saturating_sub(1);
+        }
+    })
+}
+
+/// runs the typed query and goes back to normal mode to go over the results
+fn search<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<SearchView>() {
+            cx.state.mode = Mode::Normal;
+            view.start_search_task();
+        }
+    })
+}
+
+/// replaces the search with the selected result channel chat,
+/// the chat is scrolled to the result message
+fn open_selected<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<SearchView>() else {
+            return;
+        };
+        let Some((channel, message_id)) = view.selected() else {
+            return;
+        };
+        let Some(user) = cx.state.user.clone() else {
+            return;
+        };
+
+        let mut chat = ChatView::new(
+            cx.dispatcher.clone(),
+            cx.database.clone(),
+            channel,
+            user,
+            cx.capabilities,
+        );
+        chat.jump_to(message_id);
+        cx.compositor.swap(Box::new(chat));
+    })
+}
+
+fn insert_key<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(key) = cx.keys.last() else {
+            return;
+        };
+        let Some(view) = cx.compositor.current_view_as_mut::<SearchView>() else {
+            return;
+        };
+
+        match key.keycode {
+            KeyCode::Char(c) => {
+                view.query.push_char(c);
+            }
+            KeyCode::Space => {
+                view.query.push_char(' ');
+            }
+            KeyCode::Backspace => {
+                view.query.remove_char();
+            }
+            _ => {}
+        }
+    })
+}
+
+/// the typed query split to the searched text and its filters, the filters
+/// are typed as `in:<channel>`, `from:<username>`, `since:<duration>` and
+/// `before:<duration>`, with durations like `30m`, `2h`, `7d`
+#[derive(Debug, Default)]
+struct ParsedQuery {
+    text: String,
+    channel: Option<String>,
+    author: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+}
+
+impl ParsedQuery {
+    fn parse(query: &str) -> Self {
+        let mut parsed = ParsedQuery::default();
+        let mut terms = Vec::new();
+        let now = unix_now();
+        let ago = |value: &str| parse_duration(value).map(|ago| now - ago.as_secs() as i64);
+
+        for term in query.split_whitespace() {
+            match term.split_once(':') {
+                Some(("in", channel)) if !channel.is_empty() => {
+                    parsed.channel = Some(channel.trim_start_matches('#').to_string());
+                }
+                Some(("from", author)) if !author.is_empty() => {
+                    parsed.author = Some(author.trim_start_matches('@').to_string());
+                }
+                Some(("since", value)) if ago(value).is_some() => parsed.since = ago(value),
+                Some(("before", value)) if ago(value).is_some() => parsed.until = ago(value),
+                _ => terms.push(term),
+            }
+        }
+        parsed.text = terms.join(" ");
+        parsed
+    }
+}
+
+/// the last search results, shared with the task searching
+#[derive(Default)]
+struct Results {
+    results: Vec<SearchResult>,
+    /// the channels by their id, to display the results channel name
+    channels: HashMap<i32, Channel>,
+    searched: bool,
+}
+
+/// searches the messages of all the channels, the results are
+/// ranked by relevance and show the matched part of the message
+pub struct SearchView {
+    dispatcher: Sender<Event>,
+    database: Database,
+    query: Field,
+    results: Arc<Mutex<Results>>,
+    search_task: Option<JoinHandle<()>>,
+    selected: usize,
+}
+
+impl SearchView {
+    pub fn new(dispatcher: Sender<Event>, database: Database) -> Self {
+        SearchView {
+            dispatcher,
+            database,
+            query: FieldBuilder::default().build(),
+            results: Arc::default(),
+            search_task: None,
+            selected: 0,
+        }
+    }
+
+    fn start_search_task(&mut self) {
+        if let Some(task) = self.search_task.take() {
+            task.abort();
+        }
+        self.selected = 0;
+
+        let parsed = ParsedQuery::parse(self.query.value());
+        let database = self.database.clone();
+        let results = self.results.clone();
+        let dispatcher = self.dispatcher.clone();
+
+        self.search_task = Some(tokio::spawn(async move {
+            let searched = async {
+                let channels = Channel::all(database.clone())
+                    .await?
+                    .into_iter()
+                    .map(|channel| (channel.id(), channel))
+                    .collect::<HashMap<_, _>>();
+
+                let mut query = SearchQuery::new(&parsed.text);
+                if let Some(name) = &parsed.channel {
+                    match channels.values().find(|channel| channel.name() == name) {
+                        Some(channel) => query = query.channel(channel.id()),
+                        None => return Ok((Vec::new(), channels)),
+                    }
+                }
+                if let Some(author) = &parsed.author {
+                    query = query.author(author);
+                }
+                if let Some(since) = parsed.since {
+                    query = query.since(since);
+                }
+                if let Some(until) = parsed.until {
+                    query = query.until(until);
+                }
+
+                let found = Message::search(database, query).await?;
+                Ok::<_, threet_storage::StorageError>((found, channels))
+            };
+
+            match searched.await {
+                Ok((found, channels)) => {
+                    *results.lock().unwrap() = Results {
+                        results: found,
+                        channels,
+                        searched: true,
+                    };
+                    request_render(&dispatcher).await;
+                }
+                Err(err) => {
+                    log::warn!("couldn't search the messages, {}", err);
+                    let notification = Notification::error(
+                        "search error".to_string(),
+                        "couldn't search the messages".to_string(),
+                    );
+                    notify(&dispatcher, notification).await;
+                }
+            }
+        }));
+    }
+
+    fn is_search_task_running(&self) -> bool {
+        self.search_task
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// the selected result channel and message id, the replies
+    /// are shown in the channel as their thread root message
+    fn selected(&self) -> Option<(Channel, i64)> {
+        let results = self.results.lock().unwrap();
+        let index = self.selected.min(results.results.len().saturating_sub(1));
+        let message = results.results.get(index)?.message();
+        let channel = results.channels.get(&message.channel_id())?.clone();
+        Some((channel, message.parent_id().unwrap_or(message.id())))
+    }
+}
+
+#[async_trait]
+impl View for SearchView {
+    fn name(&self) -> &str {
+        "search"
+    }
+
+    async fn handle_keys<'a>(&self, keys: &[Key], mode: Mode) -> Option<&'a BindCallback> {
+        match mode {
+            Mode::Normal => NORMAL_MODE_COMBOS.get(keys),
+            Mode::Insert => INSERT_MODE_COMBOS.get(keys).or(Some(&INSERT_MODE_CALLBACK)),
+        }
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::new().title_top(" search ".bold());
+        let [query_area, results_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(block.inner(area));
+        block.render(area, buf);
+
+        self.query
+            .widget()
+            .placeholder("search... in:channel from:user since:7d")
+            .render(query_area, buf);
+
+        let results = self.results.lock().unwrap();
+        if self.is_search_task_running() {
+            Paragraph::new("searching")
+                .centered()
+                .render(results_area, buf);
+            return;
+        }
+        if results.searched && results.results.is_empty() {
+            Paragraph::new("no results")
+                .centered()
+                .render(results_area, buf);
+            return;
+        }
+
+        let selected = self.selected.min(results.results.len().saturating_sub(1));
+        let lines = results.results.iter().enumerate().map(|(i, result)| {
+            let message = result.message();
+            let channel = results
+                .channels
+                .get(&message.channel_id())
+                .map(Channel::name)
+                .unwrap_or("?");
+
+            let mut spans = vec![
+                Span::styled(format!("#{} ", channel), Style::new().dark_gray()),
+                Span::styled(format_time(message.created_at()), Style::new().dark_gray()),
+                Span::raw(" "),
+                Span::styled(message.author(), Style::new().bold()),
+                Span::raw(" "),
+            ];
+            spans.extend(result.highlights().into_iter().map(|(part, matched)| {
+                let part = strip_escapes(part).replace('\n', " ");
+                if matched {
+                    Span::styled(part, Style::new().yellow().bold())
+                } else {
+                    Span::raw(part)
+                }
+            }));
+
+            let line = Line::from(spans);
+            if i == selected {
+                line.style(Style::new().on_dark_gray())
+            } else {
+                line
+            }
+        });
+
+        Paragraph::new(lines.collect::<Vec<_>>()).render(results_area, buf);
+    }
+}
@@ -0,0 +1,179 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use ratatui::widgets::Cell;
+use ratatui::widgets::Row;
+use ratatui::widgets::Table;
+
+use crate::app::Context;
+use crate::app::Mode;
+use crate::bind::BindCallback;
+use crate::bind::Binder;
+use crate::capabilities::Capabilities;
+use crate::event::Key;
+use crate::event::KeyCode;
+use crate::session::Presence;
+use crate::session::SessionDirectory;
+use crate::session::SessionInfo;
+use crate::utils::format_duration;
+
+use super::View;
+
+static NORMAL_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Char('j'); 1], select_next);
+    combos.add([KeyCode::Down; 1], select_next);
+    combos.add([KeyCode::Char('k'); 1], select_previous);
+    combos.add([KeyCode::Up; 1], select_previous);
+    combos.add([KeyCode::Char('x'); 1], terminate_selected);
+    combos
+});
+
+fn select_next<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<SessionsView>() {
+            let last = view.directory.sessions().len().saturating_sub(1);
+            view.selected = (view.selected + 1).min(last);
+        }
+    })
+}
+
+fn select_previous<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(view) = cx.compositor.current_view_as_mut::<SessionsView>() {
+            view.selected = view.selected.saturating_sub(1);
+        }
+    })
+}
+
+fn terminate_selected<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let current = cx.state.session;
+        if let Some(view) = cx.compositor.current_view_as_mut::<SessionsView>() {
+            // an admin terminating its own session is most likely a mistake
+            if let Some(session) = view.selected().filter(|session| session.id != current) {
+                log::info!("terminating session {} ({})", session.id, session.peer);
+                view.directory.terminate(session.id);
+            }
+        }
+    })
+}
+
+/// the session id, user, peer, uptime, idle time and size as displayed
+fn row_labels(session: &SessionInfo) -> [String; 6] {
+    [
+        session.id.to_string(),
+        session.username.as_deref().unwrap_or("-").to_string(),
+        session.peer.to_string(),
+        format_duration(session.uptime()),
+        format_duration(session.last_activity.elapsed()),
+        format!("{}x{}", session.size.0, session.size.1),
+    ]
+}
+
+/// lists all the connected sessions on the server, allows admins
+/// to see who is connected from where and terminate sessions
+pub struct SessionsView {
+    directory: Arc<dyn SessionDirectory>,
+    capabilities: Capabilities,
+    selected: usize,
+    /// the rows as of the last tick, the view is rendered
+    /// again only when one of them changed
+    rows: Vec<(Presence, [String; 6])>,
+}
+
+impl SessionsView {
+    pub fn new(directory: Arc<dyn SessionDirectory>, capabilities: Capabilities) -> Self {
+        SessionsView {
+            directory,
+            capabilities,
+            selected: 0,
+            rows: Vec::new(),
+        }
+    }
+
+    /// returns the sessions sorted by their connection time, the
+    /// order is stable between renders so the selection won't jump
+    fn sessions(&self) -> Vec<SessionInfo> {
+        let mut sessions = self.directory.sessions();
+        sessions.sort_by_key(|session| session.id);
+        sessions
+    }
+
+    fn selected(&self) -> Option<SessionInfo> {
+        let sessions = self.sessions();
+        let index = self.selected.min(sessions.len().saturating_sub(1));
+        sessions.into_iter().nth(index)
+    }
+}
+
+#[async_trait]
+impl View for SessionsView {
+    fn name(&self) -> &str {
+        "sessions"
+    }
+
+    async fn handle_keys<'a>(&self, keys: &[Key], mode: Mode) -> Option<&'a BindCallback> {
+        match mode {
+            Mode::Normal => NORMAL_MODE_COMBOS.get(keys),
+            Mode::Insert => None,
+        }
+    }
+
+    /// the idle and uptime durations change all the time, but
+    /// their labels only change every second at most
+    async fn tick(&mut self) -> bool {
+        let rows = self
+            .sessions()
+            .iter()
+            .map(|session| (session.presence(), row_labels(session)))
+            .collect::<Vec<_>>();
+        if rows == self.rows {
+            return false;
+        }
+        self.rows = rows;
+        true
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let sessions = self.sessions();
+        let selected = self.selected.min(sessions.len().saturating_sub(1));
+
+        let rows = sessions.iter().enumerate().map(|(i, session)| {
+            let presence = session.presence();
+            let symbol =
+                Cell::from(presence.symbol(self.capabilities.unicode)).style(presence.style());
+            let row = Row::new(
+                std::iter::once(symbol).chain(row_labels(session).into_iter().map(Cell::from)),
+            );
+
+            if i == selected {
+                row.style(Style::new().black().on_yellow())
+            } else {
+                row
+            }
+        });
+
+        let widths = [
+            Constraint::Length(1),
+            Constraint::Length(6),
+            Constraint::Fill(1),
+            Constraint::Length(22),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(9),
+        ];
+
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(["", "id", "user", "peer", "up", "idle", "size"])
+                    .style(Style::new().bold().dark_gray()),
+            )
+            .block(Block::new().title_top(format!(" {} sessions ", sessions.len())));
+        Widget::render(table, area, buf);
+    }
+}
@@ -0,0 +1,283 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Paragraph;
+use tokio::sync::mpsc::Sender;
+
+use threet_storage::Database;
+use threet_storage::StorageEvent;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+
+use crate::app::Context;
+use crate::app::Mode;
+use crate::bind::BindCallback;
+use crate::bind::Binder;
+use crate::event::Event;
+use crate::event::Key;
+use crate::event::KeyCode;
+use crate::notifications::Notification;
+use crate::widgets::TextArea;
+
+use super::View;
+use super::message_spans;
+use super::notify;
+use super::request_render;
+
+/// how many lines the composer grows to before scrolling
+const COMPOSER_HEIGHT: u16 = 4;
+
+static NORMAL_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Char('i'); 1], change_to_insert_mode);
+    combos
+});
+
+static INSERT_MODE_COMBOS: LazyLock<Binder> = LazyLock::new(|| {
+    let mut combos = Binder::new();
+    combos.add([KeyCode::Enter; 1], send);
+    combos
+});
+
+/// every other key typed in insert mode goes to the composer
+static INSERT_MODE_CALLBACK: BindCallback = insert_key;
+
+fn change_to_insert_mode<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        cx.state.mode = Mode::Insert;
+    })
+}
+
+/// sends the composer content as a reply to the thread
+fn send<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(view) = cx.compositor.current_view_as_mut::<ThreadView>() else {
+            return;
+        };
+        let body = view.composer.value().trim().to_string();
+        if body.is_empty() {
+            return;
+        }
+        let Some(user) = cx.state.user.clone() else {
+            let notification =
+                Notification::warning("thread".to_string(), "login first to reply".to_string());
+            notify(&cx.dispatcher, notification).await;
+            return;
+        };
+        view.composer.submit();
+
+        // the reply is displayed once the storage publishes it
+        let root = view.root.clone();
+        let database = view.database.clone();
+        let dispatcher = cx.dispatcher.clone();
+        tokio::spawn(async move {
+            if let Err(err) = Message::reply(database, &root, &user, &body).await {
+                log::warn!("couldn't reply to message {}, {}", root.id(), err);
+                let notification = Notification::error(
+                    "thread error".to_string(),
+                    "couldn't send the reply".to_string(),
+                );
+                notify(&dispatcher, notification).await;
+            }
+        });
+    })
+}
+
+fn insert_key<'a>(cx: Context<'a>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(key) = cx.keys.last() else {
+            return;
+        };
+        let Some(view) = cx.compositor.current_view_as_mut::<ThreadView>() else {
+            return;
+        };
+
+        view.composer.handle_key(key);
+    })
+}
+
+/// the thread replies, shared with the background task loading them
+#[derive(Default)]
+struct Replies {
+    messages: Vec<Message>,
+    loading: bool,
+}
+
+impl Replies {
+    /// appends a new reply, replies already loaded are ignored
+    /// since they may arrive while loading
+    fn push(&mut self, reply: Message) -> bool {
+        if self
+            .messages
+            .last()
+            .is_some_and(|last| last.id() >= reply.id())
+        {
+            return false;
+        }
+        self.messages.push(reply);
+        true
+    }
+}
+
+/// displays a message and its replies, with a composer to reply
+/// to the thread, it is opened next to the channel chat
+pub struct ThreadView {
+    database: Database,
+    channel: Channel,
+    root: Message,
+    replies: Arc<Mutex<Replies>>,
+    composer: TextArea,
+}
+
+impl ThreadView {
+    pub fn new(
+        dispatcher: Sender<Event>,
+        database: Database,
+        channel: Channel,
+        root: Message,
+    ) -> Self {
+        let replies = Arc::new(Mutex::new(Replies {
+            messages: Vec::new(),
+            loading: true,
+        }));
+
+        tokio::spawn({
+            let database = database.clone();
+            let replies = replies.clone();
+            let root_id = root.id();
+
+            async move {
+                match Message::thread(database, root_id).await {
+                    Ok(loaded) => {
+                        let mut replies = replies.lock().unwrap();
+                        let sent_while_loading = std::mem::replace(&mut replies.messages, loaded);
+                        replies.loading = false;
+                        for reply in sent_while_loading {
+                            replies.push(reply);
+                        }
+                    }
+                    Err(err) => {
+                        log::warn!("couldn't load message {} thread, {}", root_id, err);
+                        replies.lock().unwrap().loading = false;
+                        let notification = Notification::error(
+                            "thread error".to_string(),
+                            "couldn't load the thread replies".to_string(),
+                        );
+                        notify(&dispatcher, notification).await;
+                        return;
+                    }
+                }
+                request_render(&dispatcher).await;
+            }
+        });
+
+        ThreadView {
+            database,
+            channel,
+            root,
+            replies,
+            composer: TextArea::new(COMPOSER_HEIGHT),
+        }
+    }
+}
+
+#[async_trait]
+impl View for ThreadView {
+    fn name(&self) -> &str {
+        "thread"
+    }
+
+    async fn handle_keys<'a>(&self, keys: &[Key], mode: Mode) -> Option<&'a BindCallback> {
+        match mode {
+            Mode::Normal => NORMAL_MODE_COMBOS.get(keys),
+            Mode::Insert => INSERT_MODE_COMBOS.get(keys).or(Some(&INSERT_MODE_CALLBACK)),
+        }
+    }
+
+    fn storage_event(&mut self, event: &StorageEvent) -> bool {
+        match event {
+            StorageEvent::MessageCreated(message)
+                if message.parent_id() == Some(self.root.id()) =>
+            {
+                self.root.count_reply();
+                self.replies.lock().unwrap().push(message.clone())
+            }
+            StorageEvent::MessageEdited(message)
+            | StorageEvent::MessageDeleted(message)
+            | StorageEvent::MessageReacted(message)
+                if message.id() == self.root.id() =>
+            {
+                self.root = message.clone();
+                true
+            }
+            StorageEvent::MessageEdited(message)
+            | StorageEvent::MessageDeleted(message)
+            | StorageEvent::MessageReacted(message)
+                if message.parent_id() == Some(self.root.id()) =>
+            {
+                let mut replies = self.replies.lock().unwrap();
+                match replies
+                    .messages
+                    .iter_mut()
+                    .find(|reply| reply.id() == message.id())
+                {
+                    Some(reply) => {
+                        *reply = message.clone();
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::new().title_top(format!(" thread #{} ", self.channel.name()).bold());
+        let inner = block.inner(area);
+        let [root_area, replies_area, composer_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(self.composer.height(inner.width)),
+        ])
+        .areas(inner);
+        block.render(area, buf);
+
+        Paragraph::new(Line::from(message_spans(&self.root)))
+            .block(Block::new().borders(Borders::BOTTOM))
+            .render(root_area, buf);
+
+        let replies = self.replies.lock().unwrap();
+        if replies.loading && replies.messages.is_empty() {
+            Paragraph::new("loading")
+                .centered()
+                .render(replies_area, buf);
+        } else if replies.messages.is_empty() {
+            Paragraph::new("no replies yet")
+                .centered()
+                .dark_gray()
+                .render(replies_area, buf);
+        } else {
+            // the newest replies are kept visible
+            let height = replies_area.height as usize;
+            let lines = replies
+                .messages
+                .iter()
+                .skip(replies.messages.len().saturating_sub(height))
+                .map(|reply| Line::from(message_spans(reply)))
+                .collect::<Vec<_>>();
+            Paragraph::new(lines).render(replies_area, buf);
+        }
+
+        self.composer
+            .widget()
+            .placeholder("reply...")
+            .render(composer_area, buf);
+    }
+}
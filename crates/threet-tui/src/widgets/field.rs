@@ -2,6 +2,8 @@ use ratatui::prelude::*;
 use ratatui::widgets::Block;
 use ratatui::widgets::Padding;
 use ratatui::widgets::Paragraph;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 use crate::conditional_build;
 
@@ -9,38 +11,26 @@ use crate::conditional_build;
 /// it is easier to create a `Field` with the builder pattern
 #[derive(Default)]
 pub struct FieldBuilder {
-    buffer: String,
     kind: FieldKind,
-    style: FieldStyle,
     min: usize,
     max: usize,
 }
 
 impl FieldBuilder {
-    #[inline]
-    pub fn initial_buffer(mut self, value: String) -> Self {
-        self.buffer = value;
-        self
-    }
-
     #[inline]
     pub fn kind(mut self, kind: FieldKind) -> Self {
         self.kind = kind;
         self
     }
 
-    #[inline]
-    pub fn style(mut self, style: FieldStyle) -> Self {
-        self.style = style;
-        self
-    }
-
+    /// the min amount of characters for the field to be valid
     #[inline]
     pub fn min(mut self, value: usize) -> Self {
         self.min = value;
         self
     }
 
+    /// the max amount of characters the field holds, `0` for no limit
     #[inline]
     pub fn max(mut self, value: usize) -> Self {
         self.max = value;
@@ -50,10 +40,9 @@ impl FieldBuilder {
     #[inline]
     pub fn build(self) -> Field {
         Field {
-            cursor: self.buffer.len(),
-            buffer: self.buffer,
+            cursor: 0,
+            buffer: String::new(),
             kind: self.kind,
-            style: self.style,
             min: self.min,
             max: self.max,
         }
@@ -69,66 +58,107 @@ pub enum FieldKind {
     Secret,
 }
 
-#[derive(Clone, Default)]
-pub enum FieldStyle {
-    #[default]
-    Outline,
-    Transparent,
-}
-
 /// represent a single line text field that can hold
 /// a string value
 ///
 /// the type support cursor position for inserting characters between
 /// other characters, also will render its character with respect to the
 /// given field type
+///
+/// the field is edited by grapheme clusters, the characters as the user
+/// sees them, so an emoji or a letter with its accents is a single character
 #[derive(Default)]
 pub struct Field {
     buffer: String,
     kind: FieldKind,
+    /// the cursor byte offset in the buffer, always between two graphemes
     cursor: usize,
-    style: FieldStyle,
+    /// the min and max amount of characters, counted as graphemes
     min: usize,
     max: usize,
 }
 
 impl Field {
-    /// returns the kinds of the current field
+    /// returns true if the field holds at least its min amount of
+    /// characters, the max is never exceeded while typing
     #[inline]
-    pub fn kind(&self) -> FieldKind {
-        self.kind.clone()
+    pub fn valid(&self) -> bool {
+        self.len() >= self.min
     }
 
+    /// returns how many characters the field holds
     #[inline]
-    pub fn valid(&self) -> bool {
-        self.min > 0 && self.buffer.len() > self.min
+    fn len(&self) -> usize {
+        self.buffer.graphemes(true).count()
     }
 
     /// pust a character into the field buffer, the char will be
     /// push in relevense to the cursor position, the returned
     /// bool indicate if the char was actually pushed
     pub fn push_char(&mut self, c: char) -> bool {
-        if self.max > 0 && self.max <= self.buffer.len() {
+        self.buffer.insert(self.cursor, c);
+        // a combining character joins the previous character, it
+        // doesn't count against the max
+        if self.max > 0 && self.len() > self.max {
+            self.buffer.remove(self.cursor);
             return false;
         }
 
-        self.buffer.insert(self.cursor, c);
-        self.cursor += 1;
+        self.cursor += c.len_utf8();
         true
     }
 
     /// return a boolean value indicating if a character was
     /// removed
     pub fn remove_char(&mut self) -> bool {
-        if self.cursor > 0 {
-            self.cursor -= 1;
-            self.buffer.remove(self.cursor);
-            true
-        } else {
-            false
+        let Some((start, _)) = self.buffer[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+        else {
+            return false;
+        };
+        self.buffer.drain(start..self.cursor);
+        self.cursor = start;
+        true
+    }
+
+    /// moves the cursor a character to the left, the returned
+    /// bool indicate if the cursor moved
+    pub fn move_left(&mut self) -> bool {
+        match self.buffer[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+        {
+            Some((start, _)) => {
+                self.cursor = start;
+                true
+            }
+            None => false,
         }
     }
 
+    /// moves the cursor a character to the right, the returned
+    /// bool indicate if the cursor moved
+    pub fn move_right(&mut self) -> bool {
+        match self.buffer[self.cursor..].graphemes(true).next() {
+            Some(grapheme) => {
+                self.cursor += grapheme.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline]
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    #[inline]
+    pub fn move_end(&mut self) {
+        self.cursor = self.buffer.len();
+    }
+
     /// returns the field current value
     #[inline]
     pub fn value(&self) -> &str {
@@ -138,16 +168,19 @@ impl Field {
     /// returns a widget that represent the current field, can
     /// be used in ratatui render
     #[inline]
-    pub fn widget(&self) -> FieldWidget {
-        let content = match self.kind {
-            FieldKind::String => self.buffer.clone(),
-            FieldKind::Secret => self.buffer.chars().map(|_| "*").collect::<String>(),
+    pub fn widget(&self) -> FieldWidget<'_> {
+        let (content, cursor) = match self.kind {
+            FieldKind::String => (self.buffer.clone(), self.cursor),
+            // the secret is displayed with a `*` for each character
+            FieldKind::Secret => (
+                "*".repeat(self.len()),
+                self.buffer[..self.cursor].graphemes(true).count(),
+            ),
         };
         FieldWidget {
             content,
-            label: None,
+            cursor,
             focused: false,
-            field_style: self.style.clone(),
             placeholder: None,
             max: self.max,
         }
@@ -157,9 +190,9 @@ impl Field {
 /// field widget representation, used in other widget `render` functions
 pub struct FieldWidget<'a> {
     content: String,
-    field_style: FieldStyle,
+    /// the cursor byte offset in the content
+    cursor: usize,
     placeholder: Option<&'a str>,
-    label: Option<&'a str>,
     focused: bool,
     max: usize,
 }
@@ -171,12 +204,6 @@ impl<'a> FieldWidget<'a> {
         self
     }
 
-    #[inline]
-    pub fn label(mut self, label: &'a str) -> Self {
-        self.label = Some(label);
-        self
-    }
-
     #[inline]
     pub fn placeholder(mut self, placeholder: &'a str) -> Self {
         self.placeholder = Some(placeholder);
@@ -189,35 +216,57 @@ impl<'a> Widget for FieldWidget<'a> {
     where
         Self: Sized,
     {
-        let line = if self.content.is_empty() {
-            Line::styled(
+        let len = self.content.graphemes(true).count();
+        let block = conditional_build!(
+            Block::bordered().padding(Padding::left(1)),
+            (self.focused, (style(Style::new().yellow())) else style(Style::new().dark_gray())),
+            (
+                self.max > 0,
+                (title_bottom(
+                    Line::from(format!(" {}/{} ", len, self.max)).right_aligned(),
+                ))
+            )
+        );
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if inner.is_empty() {
+            return;
+        }
+
+        // the text is scrolled horizontally so the cursor, and the
+        // cell it's drawn on, are always in the field
+        let width = inner.width as usize;
+        let cursor = self.content[..self.cursor].width();
+        let scroll = (cursor + 1).saturating_sub(width);
+
+        if self.content.is_empty() {
+            Paragraph::new(Line::styled(
                 self.placeholder.unwrap_or_default(),
                 Style::new().dark_gray().italic(),
-            )
+            ))
+            .render(inner, buf);
         } else {
-            Line::from(self.content.as_str())
-        };
-
-        match self.field_style {
-            FieldStyle::Outline => {
-                let block = conditional_build!(
-                    Block::bordered().padding(Padding::left(1)),
-                    (self.focused, (style(Style::new().yellow())) else style(Style::new().dark_gray())),
-                    (self.label.is_some(), (title_top(self.label.unwrap()))),
-                    (
-                        self.max > 0,
-                        (title_bottom(
-                            Line::from(format!(" {}/{} ", self.content.len(), self.max))
-                                .right_aligned(),
-                        ))
-                    )
-                );
-
-                Paragraph::new(line).block(block).render(area, buf);
-            }
-            FieldStyle::Transparent => {
-                Paragraph::new(line).render(area, buf);
+            let mut visible = String::new();
+            let mut column = 0;
+            for grapheme in self.content.graphemes(true) {
+                let start = column;
+                column += grapheme.width();
+                if column > scroll + width {
+                    break;
+                }
+                if start >= scroll {
+                    visible.push_str(grapheme);
+                } else if column > scroll {
+                    // a wide character cut by the scroll is not displayed
+                    visible.push_str(&" ".repeat(column - scroll));
+                }
             }
+            Paragraph::new(visible).render(inner, buf);
+        }
+
+        if self.focused {
+            let x = inner.x + (cursor - scroll) as u16;
+            buf[(x, inner.y)].set_style(Style::new().reversed());
         }
     }
 }
@@ -2,6 +2,7 @@
 mod button;
 mod field;
 mod status;
+mod textarea;
 
 pub use field::Field;
 pub use field::FieldBuilder;
@@ -11,3 +12,6 @@ pub use field::FieldWidget;
 pub use button::ButtonWidget;
 
 pub use status::StatusWidget;
+
+pub use textarea::TextArea;
+pub use textarea::TextAreaWidget;
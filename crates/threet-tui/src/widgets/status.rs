@@ -2,19 +2,51 @@ use ratatui::prelude::*;
 use ratatui::text::Line;
 use ratatui::text::Span;
 
+use crate::utils::format_bytes;
+
 pub struct StatusWidget<'a> {
     view_name: &'a str,
+    separator: &'a str,
+    bytes_per_minute: Option<u64>,
+    low_bandwidth: bool,
 }
 
 impl<'a> StatusWidget<'a> {
     pub fn new(view_name: &'a str) -> Self {
-        StatusWidget { view_name }
+        StatusWidget {
+            view_name,
+            separator: "\u{e0b0}",
+            bytes_per_minute: None,
+            low_bandwidth: false,
+        }
+    }
+
+    /// displays how many bytes were sent to the terminal in the last minute
+    #[inline]
+    pub fn bytes_per_minute(mut self, bytes: u64) -> Self {
+        self.bytes_per_minute = Some(bytes);
+        self
+    }
+
+    /// indicates that the session runs in low bandwidth mode
+    #[inline]
+    pub fn low_bandwidth(mut self, low_bandwidth: bool) -> Self {
+        self.low_bandwidth = low_bandwidth;
+        self
+    }
+
+    /// the glyph rendered between the status sections, terminals without
+    /// powerline fonts should use a plain character instead
+    #[inline]
+    pub fn separator(mut self, separator: &'a str) -> Self {
+        self.separator = separator;
+        self
     }
 
-    fn view_spans(&self) -> [Span; 2] {
+    fn view_spans(&self) -> [Span<'_>; 2] {
         [
             Span::from(format!(" {} ", self.view_name)).style(Style::new().italic().on_blue()),
-            Span::from("\u{e0b0}").style(Style::new().blue()),
+            Span::from(self.separator).style(Style::new().blue()),
         ]
     }
 }
@@ -27,5 +59,14 @@ impl Widget for StatusWidget<'_> {
         let mut spans = Vec::with_capacity(4);
         spans.extend(self.view_spans());
         Line::from_iter(spans).render(area, buf);
+
+        let mut spans = Vec::with_capacity(2);
+        if self.low_bandwidth {
+            spans.push(Span::from(" low bandwidth ").style(Style::new().black().on_yellow()));
+        }
+        if let Some(bytes) = self.bytes_per_minute {
+            spans.push(Span::from(format!(" {}/min ", format_bytes(bytes))).dark_gray());
+        }
+        Line::from_iter(spans).right_aligned().render(area, buf);
     }
 }
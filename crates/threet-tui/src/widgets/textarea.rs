@@ -0,0 +1,531 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use ratatui::widgets::Padding;
+use ratatui::widgets::Paragraph;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::event::Key;
+use crate::event::KeyCode;
+use crate::event::Modifier;
+
+/// how many edits can be undone
+const UNDO_SIZE: usize = 100;
+
+/// how many killed texts are kept to be yanked
+const KILL_RING_SIZE: usize = 16;
+
+/// how many submitted texts can be recalled
+const HISTORY_SIZE: usize = 100;
+
+/// the last change made to the text area, a run of the same
+/// change is undone at once and consecutive kills are yanked
+/// as a single text
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    #[default]
+    Move,
+    Insert,
+    Delete,
+    Kill,
+    Yank,
+    Replace,
+    Undo,
+}
+
+/// the text and cursor before an edit, to undo it
+struct Snapshot {
+    buffer: String,
+    cursor: usize,
+}
+
+/// represent a multiline text area, edited with the emacs keys
+///
+/// the text area keeps the texts it submitted, they are recalled with
+/// up and down from the first and the last lines, and grows with its
+/// text up to the given max height
+///
+/// like the `Field` type, the text is edited by grapheme clusters
+#[derive(Default)]
+pub struct TextArea {
+    buffer: String,
+    /// the cursor byte offset in the buffer, always between two graphemes
+    cursor: usize,
+    max_height: u16,
+    last: Action,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// the killed texts, the newest first
+    kill_ring: VecDeque<String>,
+    /// where the last yanked text starts, it ends at the cursor
+    yank_start: usize,
+    history: Vec<String>,
+    /// the recalled history entry, `None` when editing a new text
+    recalled: Option<usize>,
+    /// the text edited before recalling the history
+    draft: String,
+}
+
+impl TextArea {
+    /// creates an empty text area displaying at most `max_height` lines
+    pub fn new(max_height: u16) -> Self {
+        TextArea {
+            max_height,
+            ..Default::default()
+        }
+    }
+
+    /// returns the text area current value
+    #[inline]
+    pub fn value(&self) -> &str {
+        &self.buffer
+    }
+
+    /// replaces the text with the given value, with the cursor at the
+    /// end, the previous text can be restored with an undo
+    pub fn set_value(&mut self, value: &str) {
+        self.edit(Action::Replace, 0..self.buffer.len(), value);
+    }
+
+    /// removes all the text, and the edits to undo
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.cursor = 0;
+        self.last = Action::Move;
+        self.undo.clear();
+        self.redo.clear();
+        self.recalled = None;
+        self.draft.clear();
+    }
+
+    /// adds the current text to the history and clears the text area
+    pub fn submit(&mut self) {
+        if !self.buffer.trim().is_empty() && self.history.last() != Some(&self.buffer) {
+            self.history.push(std::mem::take(&mut self.buffer));
+            if self.history.len() > HISTORY_SIZE {
+                self.history.remove(0);
+            }
+        }
+        self.clear();
+    }
+
+    /// edits the text with the given key, the returned bool indicate
+    /// if the key is a text area key, plain enter isn't one since it
+    /// is left to submit the text, shift or alt enter inserts a newline
+    pub fn handle_key(&mut self, key: &Key) -> bool {
+        let ctrl = key.modifiers.contains(Modifier::CTRL);
+        let alt = key.modifiers.contains(Modifier::ALT);
+        let shift = key.modifiers.contains(Modifier::SHIFT);
+
+        match (&key.keycode, ctrl, alt) {
+            (KeyCode::Enter, false, _) if alt || shift => self.insert("\n"),
+            (KeyCode::Char(c), false, false) => self.insert(c.encode_utf8(&mut [0; 4])),
+            (KeyCode::Space, false, false) => self.insert(" "),
+            (KeyCode::Backspace, false, false) | (KeyCode::Char('h'), true, false) => {
+                self.delete(self.previous_grapheme()..self.cursor)
+            }
+            (KeyCode::Char('d'), true, false) => self.delete(self.cursor..self.next_grapheme()),
+
+            (KeyCode::Left, false, false) | (KeyCode::Char('b'), true, false) => {
+                self.move_to(self.previous_grapheme())
+            }
+            (KeyCode::Right, false, false) | (KeyCode::Char('f'), true, false) => {
+                self.move_to(self.next_grapheme())
+            }
+            (KeyCode::Left, _, _) | (KeyCode::Char('b'), false, true) => {
+                self.move_to(self.previous_word())
+            }
+            (KeyCode::Right, _, _) | (KeyCode::Char('f'), false, true) => {
+                self.move_to(self.next_word())
+            }
+            (KeyCode::Home, ..) | (KeyCode::Char('a'), true, false) => {
+                self.move_to(self.line_start(self.cursor))
+            }
+            (KeyCode::End, ..) | (KeyCode::Char('e'), true, false) => {
+                self.move_to(self.line_end(self.cursor))
+            }
+            (KeyCode::Up, ..) | (KeyCode::Char('p'), true, false) => self.move_up(),
+            (KeyCode::Down, ..) | (KeyCode::Char('n'), true, false) => self.move_down(),
+
+            (KeyCode::Char('w'), true, false) | (KeyCode::Backspace, _, true) => {
+                self.kill(self.previous_word()..self.cursor)
+            }
+            (KeyCode::Char('d'), false, true) => self.kill(self.cursor..self.next_word()),
+            (KeyCode::Char('k'), true, false) => {
+                // at the end of a line the newline is killed, joining the next line
+                let end = match self.line_end(self.cursor) {
+                    end if end == self.cursor => self.next_grapheme(),
+                    end => end,
+                };
+                self.kill(self.cursor..end)
+            }
+            (KeyCode::Char('u'), true, false) => {
+                self.kill(self.line_start(self.cursor)..self.cursor)
+            }
+            (KeyCode::Char('y'), true, false) => self.yank(),
+            (KeyCode::Char('y'), false, true) => self.yank_pop(),
+
+            // the terminals send ctrl-_ and ctrl-/ as ctrl-7
+            (KeyCode::Char('z' | '_' | '/' | '7'), true, false) => self.undo(),
+            (KeyCode::Char('r'), true, false) => self.redo(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// returns the height of the text area displaying its text in
+    /// the given width, the borders included
+    pub fn height(&self, width: u16) -> u16 {
+        // the borders and the left padding
+        let rows = wrap(&self.buffer, width.saturating_sub(3) as usize, self.cursor).len();
+        (rows as u16).clamp(1, self.max_height.max(1)) + 2
+    }
+
+    /// returns a widget that represent the current text area, can
+    /// be used in ratatui render
+    #[inline]
+    pub fn widget(&self) -> TextAreaWidget<'_> {
+        TextAreaWidget {
+            content: &self.buffer,
+            cursor: self.cursor,
+            placeholder: None,
+        }
+    }
+
+    /// replaces the given range with the text, saving the text to undo
+    /// the edit, the cursor is moved to the end of the inserted text
+    fn edit(&mut self, action: Action, range: Range<usize>, text: &str) {
+        if range.is_empty() && text.is_empty() {
+            return;
+        }
+
+        // a run of typed or deleted characters is undone at once
+        let continued = action == self.last && matches!(action, Action::Insert | Action::Delete);
+        if !continued || action == Action::Replace {
+            self.undo.push(Snapshot {
+                buffer: self.buffer.clone(),
+                cursor: self.cursor,
+            });
+            if self.undo.len() > UNDO_SIZE {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.replace(range, text);
+        self.last = action;
+    }
+
+    fn replace(&mut self, range: Range<usize>, text: &str) {
+        self.cursor = range.start + text.len();
+        self.buffer.replace_range(range, text);
+    }
+
+    fn insert(&mut self, text: &str) {
+        self.edit(Action::Insert, self.cursor..self.cursor, text);
+    }
+
+    fn delete(&mut self, range: Range<usize>) {
+        self.edit(Action::Delete, range, "");
+    }
+
+    #[inline]
+    fn move_to(&mut self, cursor: usize) {
+        self.cursor = cursor;
+        self.last = Action::Move;
+    }
+
+    /// moves the cursor to the same column on the previous line,
+    /// from the first line the previous submitted text is recalled
+    fn move_up(&mut self) {
+        let start = self.line_start(self.cursor);
+        if start == 0 {
+            self.recall_previous();
+            return;
+        }
+        let column = self.buffer[start..self.cursor].width();
+        let previous = self.line_start(start - 1);
+        self.move_to(self.column_offset(previous..start - 1, column));
+    }
+
+    /// moves the cursor to the same column on the next line, from
+    /// the last line the next submitted text is recalled
+    fn move_down(&mut self) {
+        let end = self.line_end(self.cursor);
+        if end == self.buffer.len() {
+            self.recall_next();
+            return;
+        }
+        let column = self.buffer[self.line_start(self.cursor)..self.cursor].width();
+        let next = end + 1;
+        self.move_to(self.column_offset(next..self.line_end(next), column));
+    }
+
+    fn recall_previous(&mut self) {
+        let index = match self.recalled {
+            Some(index) => match index.checked_sub(1) {
+                Some(index) => index,
+                None => return,
+            },
+            None => match self.history.len().checked_sub(1) {
+                Some(index) => {
+                    self.draft = self.buffer.clone();
+                    index
+                }
+                None => return,
+            },
+        };
+        self.recalled = Some(index);
+        self.set_value(&self.history[index].clone());
+    }
+
+    fn recall_next(&mut self) {
+        let Some(index) = self.recalled else {
+            return;
+        };
+        if index + 1 < self.history.len() {
+            self.recalled = Some(index + 1);
+            self.set_value(&self.history[index + 1].clone());
+        } else {
+            // past the newest text, the draft is back
+            self.recalled = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.set_value(&draft);
+        }
+    }
+
+    /// removes the text in the range and adds it to the kill ring,
+    /// consecutive kills are added as a single text
+    fn kill(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let killed = &self.buffer[range.clone()];
+        match self.kill_ring.front_mut() {
+            Some(front) if self.last == Action::Kill => {
+                if range.start < self.cursor {
+                    front.insert_str(0, killed);
+                } else {
+                    front.push_str(killed);
+                }
+            }
+            _ => {
+                self.kill_ring.push_front(killed.to_string());
+                self.kill_ring.truncate(KILL_RING_SIZE);
+            }
+        }
+        self.edit(Action::Kill, range, "");
+    }
+
+    /// inserts the last killed text
+    fn yank(&mut self) {
+        let Some(text) = self.kill_ring.front().cloned() else {
+            return;
+        };
+        self.yank_start = self.cursor;
+        self.edit(Action::Yank, self.cursor..self.cursor, &text);
+    }
+
+    /// replaces the text just yanked with the previous killed text
+    fn yank_pop(&mut self) {
+        if self.last != Action::Yank || self.kill_ring.len() < 2 {
+            return;
+        }
+        self.kill_ring.rotate_left(1);
+        let text = self.kill_ring[0].clone();
+        self.replace(self.yank_start..self.cursor, &text);
+    }
+
+    fn undo(&mut self) {
+        let Some(snapshot) = self.undo.pop() else {
+            return;
+        };
+        let current = self.restore(snapshot);
+        self.redo.push(current);
+    }
+
+    fn redo(&mut self) {
+        let Some(snapshot) = self.redo.pop() else {
+            return;
+        };
+        let current = self.restore(snapshot);
+        self.undo.push(current);
+    }
+
+    /// sets the text and cursor of the snapshot, returns the replaced ones
+    fn restore(&mut self, snapshot: Snapshot) -> Snapshot {
+        self.last = Action::Undo;
+        Snapshot {
+            buffer: std::mem::replace(&mut self.buffer, snapshot.buffer),
+            cursor: std::mem::replace(&mut self.cursor, snapshot.cursor),
+        }
+    }
+
+    fn previous_grapheme(&self) -> usize {
+        self.buffer[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(self.cursor, |(start, _)| start)
+    }
+
+    fn next_grapheme(&self) -> usize {
+        self.buffer[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    /// returns where the word before the cursor starts, the
+    /// spaces and punctuation before the cursor are skipped
+    fn previous_word(&self) -> usize {
+        let mut start = self.cursor;
+        let mut in_word = false;
+        for (i, grapheme) in self.buffer[..self.cursor].grapheme_indices(true).rev() {
+            if is_word(grapheme) {
+                in_word = true;
+            } else if in_word {
+                break;
+            }
+            start = i;
+        }
+        start
+    }
+
+    /// returns where the word after the cursor ends, the
+    /// spaces and punctuation after the cursor are skipped
+    fn next_word(&self) -> usize {
+        let mut end = self.cursor;
+        let mut in_word = false;
+        for grapheme in self.buffer[self.cursor..].graphemes(true) {
+            if is_word(grapheme) {
+                in_word = true;
+            } else if in_word {
+                break;
+            }
+            end += grapheme.len();
+        }
+        end
+    }
+
+    fn line_start(&self, offset: usize) -> usize {
+        self.buffer[..offset].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self, offset: usize) -> usize {
+        self.buffer[offset..]
+            .find('\n')
+            .map_or(self.buffer.len(), |i| offset + i)
+    }
+
+    /// returns the offset of the given column in the line, or the
+    /// line end if the line is shorter
+    fn column_offset(&self, line: Range<usize>, column: usize) -> usize {
+        let mut width = 0;
+        for (i, grapheme) in self.buffer[line.clone()].grapheme_indices(true) {
+            width += grapheme.width();
+            if width > column {
+                return line.start + i;
+            }
+        }
+        line.end
+    }
+}
+
+fn is_word(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// splits the text in the rows displayed in the given width, returns
+/// the rows byte ranges, the long lines are wrapped at the character
+/// that doesn't fit and a line filling the width gets an empty row
+/// when the cursor is at its end, so the cursor is always displayed
+fn wrap(text: &str, width: usize, cursor: usize) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut line_start = 0;
+    for line in text.split('\n') {
+        let line_end = line_start + line.len();
+        let mut row_start = line_start;
+        let mut column = 0;
+        for (i, grapheme) in line.grapheme_indices(true) {
+            let grapheme_width = grapheme.width();
+            if column > 0 && column + grapheme_width > width {
+                rows.push(row_start..line_start + i);
+                row_start = line_start + i;
+                column = 0;
+            }
+            column += grapheme_width;
+        }
+        rows.push(row_start..line_end);
+        if column >= width && cursor == line_end {
+            rows.push(line_end..line_end);
+        }
+        line_start = line_end + 1;
+    }
+    rows
+}
+
+/// text area widget representation, used in other widget `render` functions
+pub struct TextAreaWidget<'a> {
+    content: &'a str,
+    /// the cursor byte offset in the content
+    cursor: usize,
+    placeholder: Option<&'a str>,
+}
+
+impl<'a> TextAreaWidget<'a> {
+    #[inline]
+    pub fn placeholder(mut self, placeholder: &'a str) -> Self {
+        self.placeholder = Some(placeholder);
+        self
+    }
+}
+
+impl<'a> Widget for TextAreaWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let block = Block::bordered()
+            .padding(Padding::left(1))
+            .style(Style::new().dark_gray());
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if inner.is_empty() {
+            return;
+        }
+
+        if self.content.is_empty() {
+            Paragraph::new(Line::styled(
+                self.placeholder.unwrap_or_default(),
+                Style::new().dark_gray().italic(),
+            ))
+            .render(inner, buf);
+        }
+
+        // the rows are scrolled so the cursor row is always displayed
+        let rows = wrap(self.content, inner.width as usize, self.cursor);
+        let cursor_row = rows
+            .iter()
+            .rposition(|row| row.start <= self.cursor && self.cursor <= row.end)
+            .unwrap_or_default();
+        let scroll = (cursor_row + 1).saturating_sub(inner.height as usize);
+
+        if !self.content.is_empty() {
+            let lines = rows[scroll..]
+                .iter()
+                .take(inner.height as usize)
+                .map(|row| Line::raw(&self.content[row.clone()]));
+            Paragraph::new(lines.collect::<Vec<_>>()).render(inner, buf);
+        }
+
+        let row = &rows[cursor_row];
+        let x = inner.x + self.content[row.start..self.cursor].width() as u16;
+        let y = inner.y + (cursor_row - scroll) as u16;
+        buf[(x.min(inner.right() - 1), y)].set_style(Style::new().reversed());
+    }
+}
@@ -0,0 +1,1082 @@
+use std::time::Duration;
+
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use threet_storage::Database;
+use threet_storage::DatabaseBuilder;
+use threet_storage::models::Channel;
+use threet_storage::models::Message;
+use threet_storage::models::ReadMarker;
+use threet_storage::models::User;
+use threet_tui::AppBuilder;
+use threet_tui::Capabilities;
+use threet_tui::Event;
+use threet_tui::Notification;
+use threet_tui::SessionDirectory;
+use threet_tui::SessionId;
+use threet_tui::testing::TestApp;
+
+const SIZE: (u16, u16) = (140, 30);
+
+/// how many times the given text is drawn on the last frame
+fn occurrences(app: &TestApp, text: &str) -> usize {
+    app.lines()
+        .iter()
+        .map(|line| line.matches(text).count())
+        .sum()
+}
+
+/// creates the given user and authenticates the app as that user
+async fn login(app: &mut TestApp, username: &str) -> User {
+    let user = User::create(app.database(), username, "hunter2")
+        .await
+        .unwrap();
+    app.send(Event::SetUser(user.clone())).await;
+    user
+}
+
+async fn create_channel(db: &Database, name: &str) -> Channel {
+    db.repository::<Channel>()
+        .insert(&Channel::new(name))
+        .await
+        .unwrap()
+}
+
+/// opens the given channel chat from the channels list
+async fn open_chat(app: &mut TestApp, channel: &str) {
+    let channels = Channel::all(app.database()).await.unwrap();
+    let position = channels
+        .iter()
+        .position(|other| other.name() == channel)
+        .unwrap();
+
+    app.type_str("c").await;
+    app.flush().await;
+    for _ in 0..position {
+        app.type_str("j").await;
+    }
+    app.press(b"\r").await;
+    // the chat loads its messages and read marker separately
+    app.flush().await;
+}
+
+/// the style of the first cell of the given text on the last frame
+fn cell_style(app: &TestApp, text: &str) -> Style {
+    let lines = app.lines();
+    let (y, x) = lines
+        .iter()
+        .enumerate()
+        .find_map(|(y, line)| Some((y, line.find(text)?)))
+        .expect("the text is on the frame");
+    // the lines are indexed by bytes, the cells by characters
+    let x = lines[y][..x].chars().count();
+    app.buffer()[(x as u16, y as u16)].style()
+}
+
+/// the lines drawn with the selection background
+fn selected_lines(app: &TestApp) -> Vec<String> {
+    let buffer = app.buffer();
+    app.lines()
+        .into_iter()
+        .enumerate()
+        .filter(|(y, _)| {
+            (buffer.area.left()..buffer.area.right())
+                .any(|x| buffer[(x, *y as u16)].bg == Color::Yellow)
+        })
+        .map(|(_, line)| line)
+        .collect()
+}
+
+/// the test backend keeps the cell after a wide emoji as it was,
+/// so the emoji and its count are looked for separately
+fn has_reaction(app: &TestApp, emoji: &str, count: usize) -> bool {
+    let count = format!(" {}", count);
+    app.lines()
+        .iter()
+        .any(|line| line.contains(emoji) && line.trim_end().ends_with(&count))
+}
+
+/// the symbol drawn between two views split vertically, the
+/// left view takes the first half including the border
+fn border_symbol(app: &TestApp) -> &str {
+    app.buffer()[(SIZE.0 / 2 - 1, SIZE.1 / 2)].symbol()
+}
+
+#[tokio::test]
+async fn starts_with_the_authenticate_view() {
+    let app = TestApp::new(SIZE).await;
+
+    assert!(app.contains("[ Authenticate ]"));
+    assert!(app.contains("username..."));
+    assert!(app.contains("password..."));
+    assert!(app.contains("LOGIN"));
+
+    // the last line is the status bar with the focused view name
+    let lines = app.lines();
+    assert!(lines.last().unwrap().contains("authenticate"));
+}
+
+#[tokio::test]
+async fn types_into_the_focused_field_in_insert_mode() {
+    let mut app = TestApp::new(SIZE).await;
+
+    app.type_str("i").await;
+    app.type_str("bob").await;
+    assert!(app.contains("bob"));
+    assert!(!app.contains("username..."));
+
+    // tab moves to the password which is displayed hidden
+    app.press(b"\t").await;
+    app.type_str("secret").await;
+    assert!(app.contains("******"));
+    assert!(!app.contains("secret"));
+}
+
+#[tokio::test]
+async fn backspace_removes_the_last_character() {
+    let mut app = TestApp::new(SIZE).await;
+
+    app.type_str("ibobby").await;
+    app.press(b"\x7f").await;
+    app.press(b"\x7f").await;
+    assert!(app.contains("bob"));
+    assert!(!app.contains("bobb"));
+}
+
+#[tokio::test]
+async fn escape_goes_back_to_normal_mode() {
+    let mut app = TestApp::new(SIZE).await;
+
+    app.type_str("ibob").await;
+    app.press(b"\x1b").await;
+
+    // in normal mode `j` moves the focuse instead of typing
+    app.type_str("j").await;
+    assert!(app.contains("bob"));
+    assert!(!app.contains("bobj"));
+
+    // and typing goes to the password field now
+    app.type_str("ipass").await;
+    assert!(app.contains("****"));
+    assert!(!app.contains("bobpass"));
+}
+
+#[tokio::test]
+async fn vertical_split_draws_both_views() {
+    let mut app = TestApp::new(SIZE).await;
+    assert_eq!(occurrences(&app, "[ Authenticate ]"), 1);
+
+    app.type_str("a").await;
+    assert_eq!(occurrences(&app, "[ Authenticate ]"), 2);
+
+    // the left view has a border on its right side
+    assert_eq!(border_symbol(&app), "│");
+}
+
+#[tokio::test]
+async fn split_views_keep_their_own_state() {
+    let mut app = TestApp::new(SIZE).await;
+
+    app.type_str("ibob").await;
+    app.press(b"\x1b").await;
+    app.type_str("a").await;
+
+    // the new view is focused and empty, the first one is drawn
+    // from its own state
+    assert_eq!(occurrences(&app, "bob"), 1);
+    assert_eq!(occurrences(&app, "username..."), 1);
+
+    app.type_str("ialice").await;
+    assert_eq!(occurrences(&app, "bob"), 1);
+    assert_eq!(occurrences(&app, "alice"), 1);
+}
+
+#[tokio::test]
+async fn displays_pushed_notifications() {
+    let mut app = TestApp::new(SIZE).await;
+
+    let notification = Notification::info("greetings".to_string(), "hello there".to_string());
+    app.send(Event::Notification((notification, Duration::from_secs(5))))
+        .await;
+
+    assert!(app.contains("greetings"));
+    assert!(app.contains("hello there"));
+}
+
+#[tokio::test]
+async fn expired_notifications_are_removed_on_tick() {
+    let mut app = TestApp::new(SIZE).await;
+
+    let notification = Notification::warning("gone".to_string(), "soon".to_string());
+    app.send(Event::Notification((notification, Duration::ZERO)))
+        .await;
+    app.send(Event::Tick).await;
+
+    assert!(!app.contains("gone"));
+    assert!(app.contains("[ Authenticate ]"));
+}
+
+#[tokio::test]
+async fn resize_redraws_to_the_new_size() {
+    let mut app = TestApp::new(SIZE).await;
+
+    app.resize((100, 20)).await;
+    let area = app.buffer().area;
+    assert_eq!((area.width, area.height), (100, 20));
+    assert!(app.contains("LOGIN"));
+    assert!(app.lines().last().unwrap().contains("authenticate"));
+}
+
+#[tokio::test]
+async fn quit_combo_stops_the_app() {
+    let mut app = TestApp::new(SIZE).await;
+    assert_eq!(app.exit_status(), None);
+
+    app.type_str(":q").await;
+    assert_eq!(app.exit_status(), Some(0));
+}
+
+#[tokio::test]
+async fn low_bandwidth_mode_uses_plain_borders() {
+    let mut app = TestApp::with_builder(SIZE, AppBuilder::default().low_bandwidth(true)).await;
+    assert!(app.lines().last().unwrap().contains("low bandwidth"));
+
+    app.type_str("a").await;
+    assert_eq!(border_symbol(&app), "|");
+}
+
+#[tokio::test]
+async fn authenticates_with_valid_credentials() {
+    let mut app = TestApp::new(SIZE).await;
+    User::create(app.database(), "bob", "hunter2")
+        .await
+        .unwrap();
+
+    app.type_str("ibob\thunter2\t").await;
+    app.press(b"\r").await;
+    app.settle().await;
+
+    let sessions = app.sessions().sessions();
+    assert_eq!(sessions[0].username.as_deref(), Some("bob"));
+}
+
+#[tokio::test]
+async fn starts_on_the_channels_for_an_authenticated_user() {
+    // the user authenticated before the app started, like a ssh
+    // session, the app only needs the user it runs for
+    let database = DatabaseBuilder::default()
+        .in_memory()
+        .build()
+        .await
+        .unwrap();
+    let bob = User::create(database, "bob", "hunter2").await.unwrap();
+
+    let app = TestApp::with_builder(SIZE, AppBuilder::default().user(bob)).await;
+    assert!(!app.contains("[ Authenticate ]"));
+    assert!(app.contains("channels"));
+    let sessions = app.sessions().sessions();
+    assert_eq!(sessions[0].username.as_deref(), Some("bob"));
+}
+
+#[tokio::test]
+async fn rejects_invalid_credentials() {
+    let mut app = TestApp::new(SIZE).await;
+    User::create(app.database(), "bob", "hunter2")
+        .await
+        .unwrap();
+
+    app.type_str("ibob\twrong\t").await;
+    app.press(b"\r").await;
+    app.settle().await;
+
+    assert!(app.contains("authentiation error"));
+    let sessions = app.sessions().sessions();
+    assert_eq!(sessions[0].username, None);
+}
+
+#[tokio::test]
+async fn rejects_too_short_credentials() {
+    let mut app = TestApp::new(SIZE).await;
+    User::create(app.database(), "b", "hunter2").await.unwrap();
+
+    // the username is a single character, less than the field min
+    app.type_str("ib\thunter2\t").await;
+    app.press(b"\r").await;
+
+    assert!(app.contains("the username or the password is too short"));
+    assert!(app.contains("LOGIN"));
+    let sessions = app.sessions().sessions();
+    assert_eq!(sessions[0].username, None);
+}
+
+#[tokio::test]
+async fn reports_an_unavailable_database() {
+    let mut app = TestApp::new(SIZE).await;
+    app.database().close().await;
+
+    app.type_str("ibob\thunter2\t").await;
+    app.press(b"\r").await;
+    app.settle().await;
+
+    assert!(app.contains("database unavailable"));
+    assert!(!app.contains("authentiation error"));
+}
+
+#[tokio::test]
+async fn channels_require_authentication() {
+    let mut app = TestApp::new(SIZE).await;
+    app.type_str("c").await;
+    assert_eq!(occurrences(&app, "[ Authenticate ]"), 1);
+    assert!(!app.contains("channels"));
+}
+
+#[tokio::test]
+async fn opens_a_channel_chat_from_the_channels_list() {
+    let mut app = TestApp::new(SIZE).await;
+    let bob = login(&mut app, "bob").await;
+    let db = app.database();
+    let general = create_channel(&db, "general").await;
+    create_channel(&db, "random").await;
+    Message::create(db, &general, &bob, "first message")
+        .await
+        .unwrap();
+    app.settle().await;
+
+    app.type_str("c").await;
+    app.flush().await;
+    assert!(app.contains("2 channels"));
+    assert!(app.contains("#general"));
+    assert!(app.contains("#random"));
+
+    app.press(b"\r").await;
+    app.flush().await;
+    assert!(app.contains(" #general "));
+    assert!(app.contains("bob first message"));
+    assert!(app.lines().last().unwrap().contains("chat"));
+}
+
+#[tokio::test]
+async fn channels_list_the_online_members() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    // a user with two sessions is listed once, the
+    // sessions that didn't authenticate are not listed
+    app.sessions().add(SessionId(2), Some("alice"));
+    app.sessions().add(SessionId(3), Some("alice"));
+    app.sessions().add(SessionId(4), None);
+
+    app.type_str("c").await;
+    app.flush().await;
+    assert!(app.contains("2 online"));
+    assert!(app.contains("● alice"));
+    assert!(app.contains("● bob"));
+    assert_eq!(occurrences(&app, "alice"), 1);
+}
+
+#[tokio::test]
+async fn chat_displays_new_messages_live() {
+    let mut app = TestApp::new(SIZE).await;
+    let bob = login(&mut app, "bob").await;
+    let db = app.database();
+    let general = create_channel(&db, "general").await;
+    let random = create_channel(&db, "random").await;
+    open_chat(&mut app, "general").await;
+
+    Message::create(db.clone(), &general, &bob, "hello there")
+        .await
+        .unwrap();
+    app.settle().await;
+    assert!(app.contains("bob hello there"));
+
+    // messages sent to other channels are not displayed
+    Message::create(db, &random, &bob, "somewhere else")
+        .await
+        .unwrap();
+    app.settle().await;
+    assert!(!app.contains("somewhere else"));
+}
+
+#[tokio::test]
+async fn chat_sends_typed_messages() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let general = create_channel(&app.database(), "general").await;
+    open_chat(&mut app, "general").await;
+
+    app.type_str("ihello world").await;
+    assert!(app.contains("hello world"));
+    app.press(b"\r").await;
+    app.settle().await;
+
+    assert!(app.contains("bob hello world"));
+    let history = Message::history(app.database(), general.id(), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].body(), "hello world");
+}
+
+#[tokio::test]
+async fn search_jumps_to_the_result_in_its_channel() {
+    let mut app = TestApp::new(SIZE).await;
+    let bob = login(&mut app, "bob").await;
+    let db = app.database();
+    let general = create_channel(&db, "general").await;
+    let random = create_channel(&db, "random").await;
+
+    // enough messages for the result to be far from the newest ones
+    Message::create(db.clone(), &random, &bob, "the deploy is on friday")
+        .await
+        .unwrap();
+    for i in 0..100 {
+        Message::create(db.clone(), &random, &bob, &format!("chatter {}", i))
+            .await
+            .unwrap();
+    }
+    Message::create(db, &general, &bob, "no deploy today")
+        .await
+        .unwrap();
+    app.flush().await;
+
+    app.type_str("/").await;
+    app.type_str("deploy in:random").await;
+    app.press(b"\r").await;
+    app.settle().await;
+    assert!(app.contains("#random"));
+    assert!(app.contains("deploy is on friday"));
+    assert!(!app.contains("no deploy today"));
+
+    app.press(b"\r").await;
+    app.flush().await;
+    assert!(app.contains(" #random "));
+    assert!(app.contains("bob the deploy is on friday"));
+    assert!(!app.contains("chatter 99"));
+}
+
+#[tokio::test]
+async fn replies_in_a_thread_next_to_the_chat() {
+    let mut app = TestApp::new(SIZE).await;
+    let bob = login(&mut app, "bob").await;
+    let general = create_channel(&app.database(), "general").await;
+    let root = Message::create(app.database(), &general, &bob, "deploy?")
+        .await
+        .unwrap();
+    open_chat(&mut app, "general").await;
+
+    app.type_str("k").await;
+    app.press(b"\r").await;
+    app.settle().await;
+    assert!(app.contains("thread #general"));
+    assert!(app.contains("no replies yet"));
+
+    app.type_str("ion it").await;
+    app.press(b"\r").await;
+    app.settle().await;
+
+    assert!(app.contains("bob on it"));
+    assert!(app.contains("deploy? 1 reply"));
+    // the reply stays out of the channel history
+    let history = Message::history(app.database(), general.id(), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    let thread = Message::thread(app.database(), root.id()).await.unwrap();
+    assert_eq!(thread[0].body(), "on it");
+}
+
+#[tokio::test]
+async fn edits_and_deletes_own_messages() {
+    let mut app = TestApp::new(SIZE).await;
+    let bob = login(&mut app, "bob").await;
+    let general = create_channel(&app.database(), "general").await;
+    Message::create(app.database(), &general, &bob, "helo")
+        .await
+        .unwrap();
+    open_chat(&mut app, "general").await;
+
+    app.type_str("ke").await;
+    app.press(b"\x7f").await;
+    app.type_str("lo").await;
+    app.press(b"\r").await;
+    app.settle().await;
+    assert!(app.contains("bob hello (edited)"));
+
+    app.press(b"\x1b").await;
+    app.type_str("kdd").await;
+    app.settle().await;
+    assert!(app.contains("bob message deleted"));
+    assert!(!app.contains("hello"));
+}
+
+#[tokio::test]
+async fn chat_displays_edits_from_other_sessions_live() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let alice = User::create(app.database(), "alice", "secret")
+        .await
+        .unwrap();
+    let general = create_channel(&app.database(), "general").await;
+    let message = Message::create(app.database(), &general, &alice, "lunch?")
+        .await
+        .unwrap();
+    open_chat(&mut app, "general").await;
+
+    // bob can't edit alice's message
+    app.type_str("ke").await;
+    app.flush().await;
+    assert!(app.contains("you can only edit your own messages"));
+
+    Message::edit(app.database(), message.id(), &alice, "lunch at noon?")
+        .await
+        .unwrap();
+    app.flush().await;
+    assert!(app.contains("alice lunch at noon? (edited)"));
+}
+
+#[tokio::test]
+async fn reacts_to_the_selected_message() {
+    let mut app = TestApp::new(SIZE).await;
+    let bob = login(&mut app, "bob").await;
+    let general = create_channel(&app.database(), "general").await;
+    let message = Message::create(app.database(), &general, &bob, "shipped")
+        .await
+        .unwrap();
+    open_chat(&mut app, "general").await;
+
+    app.type_str("kr").await;
+    assert!(app.contains("react"));
+    app.type_str("ll").await;
+    app.press(b"\r").await;
+    app.settle().await;
+    assert!(!app.contains("react"));
+
+    let history = Message::history(app.database(), general.id(), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(history[0].reactions()[0].emoji(), "tada");
+    assert!(has_reaction(&app, "\u{1f389}", 1));
+
+    // reactions from the other sessions show up live
+    let alice = User::create(app.database(), "alice", "secret")
+        .await
+        .unwrap();
+    Message::react(app.database(), message.id(), &alice, "tada")
+        .await
+        .unwrap();
+    app.flush().await;
+    assert!(has_reaction(&app, "\u{1f389}", 2));
+}
+
+#[tokio::test]
+async fn channels_list_the_unread_messages() {
+    let mut app = TestApp::new(SIZE).await;
+    let db = app.database();
+    let bob = User::create(db.clone(), "bob", "hunter2").await.unwrap();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+    create_channel(&db, "random").await;
+    Message::create(db.clone(), &general, &alice, "morning")
+        .await
+        .unwrap();
+    Message::create(db, &general, &alice, "@bob standup?")
+        .await
+        .unwrap();
+    app.flush().await;
+
+    // authenticated after the mention, so it doesn't notify over the list
+    app.send(Event::SetUser(bob)).await;
+    app.type_str("c").await;
+    app.flush().await;
+    assert!(app.contains("#general 2 @1"));
+    assert!(app.contains("#random "));
+
+    // reading the channel clears its counts
+    app.press(b"\r").await;
+    app.flush().await;
+    app.type_str("c").await;
+    app.flush().await;
+    assert!(!app.contains("#general 2"));
+}
+
+#[tokio::test]
+async fn chat_marks_the_unread_messages() {
+    let mut app = TestApp::new(SIZE).await;
+    let bob = login(&mut app, "bob").await;
+    let db = app.database();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+    let read = Message::create(db.clone(), &general, &alice, "morning")
+        .await
+        .unwrap();
+    ReadMarker::mark(db.clone(), &bob, general.id(), read.id())
+        .await
+        .unwrap();
+    Message::create(db.clone(), &general, &alice, "standup?")
+        .await
+        .unwrap();
+    let newest = Message::create(db.clone(), &general, &alice, "hello?")
+        .await
+        .unwrap();
+    app.flush().await;
+
+    open_chat(&mut app, "general").await;
+    app.flush().await;
+    assert!(app.contains("new messages"));
+    let marker = ReadMarker::last_read(db, &bob, general.id())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(marker.message_id(), newest.id());
+
+    app.type_str("u").await;
+    let selected = selected_lines(&app);
+    assert_eq!(selected.len(), 1);
+    assert!(selected[0].contains("alice standup?"));
+}
+
+#[tokio::test]
+async fn notifies_the_mentioned_user() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let db = app.database();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+
+    Message::create(db.clone(), &general, &alice, "morning")
+        .await
+        .unwrap();
+    app.flush().await;
+    assert!(!app.contains("mentioned you"));
+
+    Message::create(db, &general, &alice, "@bob standup?")
+        .await
+        .unwrap();
+    app.flush().await;
+    assert!(app.contains("alice mentioned you"));
+}
+
+#[tokio::test]
+async fn mentions_inbox_opens_the_mention_in_its_channel() {
+    let mut app = TestApp::new(SIZE).await;
+    let db = app.database();
+    let bob = User::create(db.clone(), "bob", "hunter2").await.unwrap();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    create_channel(&db, "general").await;
+    let random = create_channel(&db, "random").await;
+    Message::create(db.clone(), &random, &alice, "@bob lunch?")
+        .await
+        .unwrap();
+    // bob never read the channel, so it's not mentioned by @channel
+    Message::create(db, &random, &alice, "@channel meeting")
+        .await
+        .unwrap();
+    app.flush().await;
+
+    app.send(Event::SetUser(bob)).await;
+    app.type_str("m").await;
+    app.flush().await;
+    assert!(app.contains(" 1 mentions "));
+    assert!(app.contains("#random "));
+    assert!(app.contains("alice @bob lunch?"));
+
+    app.press(b"\r").await;
+    app.flush().await;
+    assert!(app.contains("#random"));
+    let selected = selected_lines(&app);
+    assert_eq!(selected.len(), 1);
+    assert!(selected[0].contains("alice @bob lunch?"));
+}
+
+#[tokio::test]
+async fn chat_formats_the_messages_markdown() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let db = app.database();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+    let body = "**bold** and `code`\n> quoted\n- item\n```\nfn main() {}\n```";
+    Message::create(db, &general, &alice, body).await.unwrap();
+
+    open_chat(&mut app, "general").await;
+    assert!(app.contains("alice bold and code"));
+    assert!(
+        cell_style(&app, "bold")
+            .add_modifier
+            .contains(Modifier::BOLD)
+    );
+    assert_eq!(cell_style(&app, "code").fg, Some(Color::Yellow));
+    assert!(app.contains("│ quoted"));
+    assert!(app.contains("- item"));
+    assert!(app.contains("fn main() {}"));
+    assert!(!app.contains("**"));
+    assert!(!app.contains("```"));
+}
+
+#[tokio::test]
+async fn chat_strips_the_escape_sequences() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let db = app.database();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+    let body = "\x1b[2J\x1b]0;owned\x07hello\x1b[31m \u{9b}1mworld\x1b]52;c;aGk=\x1b\\";
+    Message::create(db, &general, &alice, body).await.unwrap();
+
+    open_chat(&mut app, "general").await;
+    assert!(app.contains("alice hello world"));
+    assert!(!app.contains("owned"));
+    let buffer = app.buffer();
+    assert!(
+        buffer
+            .content()
+            .iter()
+            .all(|cell| !cell.symbol().chars().any(char::is_control))
+    );
+}
+
+#[tokio::test]
+async fn chat_wraps_the_long_messages() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let db = app.database();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+    let body = "lorem ipsum dolor sit amet ".repeat(5);
+    Message::create(db, &general, &alice, body.trim())
+        .await
+        .unwrap();
+
+    open_chat(&mut app, "general").await;
+    assert_eq!(occurrences(&app, "lorem"), 5);
+    assert_eq!(occurrences(&app, "amet"), 5);
+    // the lines after the first one are aligned with the author
+    let lines = app.lines();
+    let first = lines
+        .iter()
+        .position(|line| line.contains("alice"))
+        .unwrap();
+    let author = lines[first].find("alice").unwrap();
+    let next = &lines[first + 1];
+    assert_eq!(&next[author - 1..author], " ");
+    assert!(!next[author..].starts_with(' '));
+}
+
+#[tokio::test]
+async fn chat_highlights_the_code_blocks() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let db = app.database();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+    let body = "```rust\nfn main() {}\n```\n```\nSELECT 1;\n```";
+    Message::create(db, &general, &alice, body).await.unwrap();
+
+    open_chat(&mut app, "general").await;
+    assert!(app.contains("fn main() {}"));
+    assert!(!app.contains("rust"));
+    // the code blocks without a language are not highlighted
+    assert!(matches!(cell_style(&app, "fn").fg, Some(Color::Rgb(..))));
+    assert_eq!(cell_style(&app, "SELECT").fg, Some(Color::Yellow));
+}
+
+#[tokio::test]
+async fn chat_copies_the_selected_message_code_block() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let db = app.database();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+    let body = "try this\n```sh\ncargo test \\\n  --workspace\n```";
+    Message::create(db.clone(), &general, &alice, body)
+        .await
+        .unwrap();
+    Message::create(db, &general, &alice, "no code")
+        .await
+        .unwrap();
+
+    open_chat(&mut app, "general").await;
+    app.type_str("kyb").await;
+    app.flush().await;
+    assert_eq!(app.clipboard(), None);
+    assert!(app.contains("the message has no code block"));
+
+    app.type_str("kyb").await;
+    app.flush().await;
+    assert_eq!(app.clipboard(), Some("cargo test \\\n  --workspace"));
+    assert!(app.contains("copied 26 characters"));
+}
+
+#[tokio::test]
+async fn chat_cycles_through_the_message_code_blocks() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let db = app.database();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+    let body = "```sh
+cargo build
+```
+then
+```sh
+cargo test
+```";
+    Message::create(db, &general, &alice, body).await.unwrap();
+
+    open_chat(&mut app, "general").await;
+    app.type_str("kyb").await;
+    app.flush().await;
+    assert_eq!(app.clipboard(), Some("cargo build"));
+    assert!(app.contains("code block 1 of 2"));
+
+    app.type_str("yb").await;
+    app.flush().await;
+    assert_eq!(app.clipboard(), Some("cargo test"));
+    assert!(app.contains("code block 2 of 2"));
+
+    // past the last block the first one is copied again
+    app.type_str("yb").await;
+    app.flush().await;
+    assert_eq!(app.clipboard(), Some("cargo build"));
+}
+
+#[tokio::test]
+async fn chat_copies_the_selected_message_and_its_url() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let db = app.database();
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    let general = create_channel(&db, "general").await;
+    let body = "the **docs** are at <https://docs.rs/ratatui>.";
+    Message::create(db, &general, &alice, body).await.unwrap();
+
+    open_chat(&mut app, "general").await;
+    app.type_str("kyy").await;
+    app.flush().await;
+    assert_eq!(app.clipboard(), Some(body));
+
+    app.type_str("yu").await;
+    app.flush().await;
+    assert_eq!(app.clipboard(), Some("https://docs.rs/ratatui"));
+}
+
+#[tokio::test]
+async fn copying_requires_a_terminal_clipboard() {
+    let capabilities = Capabilities {
+        clipboard: false,
+        ..Default::default()
+    };
+    let builder = AppBuilder::default().capabilities(capabilities);
+    let mut app = TestApp::with_builder(SIZE, builder).await;
+    login(&mut app, "bob").await;
+    let db = app.database();
+    let general = create_channel(&db, "general").await;
+    let alice = User::create(db.clone(), "alice", "secret").await.unwrap();
+    Message::create(db, &general, &alice, "hello")
+        .await
+        .unwrap();
+
+    open_chat(&mut app, "general").await;
+    app.type_str("kyy").await;
+    app.flush().await;
+    assert_eq!(app.clipboard(), None);
+    assert!(app.contains("the terminal doesn't support copying"));
+}
+
+#[tokio::test]
+async fn fields_edit_the_characters_as_displayed() {
+    let mut app = TestApp::new(SIZE).await;
+    // the accent is a combining character, `é` is a single character
+    app.type_str("ie\u{301}lo日本").await;
+    // the wide characters are drawn on two cells
+    assert!(app.contains("e\u{301}lo日 本"));
+    assert!(app.contains(" 5/16 "));
+
+    app.press(b"\x7f").await;
+    app.press(b"\x1b[D").await;
+    app.press(b"\x1b[D").await;
+    app.type_str("x").await;
+    assert!(app.contains("e\u{301}lxo日"));
+    assert!(app.contains(" 5/16 "));
+
+    app.press(b"\x1b[H").await;
+    app.press(b"\x1b[C").await;
+    app.press(b"\x7f").await;
+    assert!(app.contains(" 4/16 "));
+    assert!(app.contains("│ lxo日"));
+
+    // the secret has a `*` for each character
+    app.type_str("\tpässwörd").await;
+    assert!(app.contains("******** "));
+    assert!(app.contains(" 8/32 "));
+}
+
+/// the column of the reversed cell the field cursor is drawn on in the given row
+fn cursor_column(app: &TestApp, row: u16) -> Option<u16> {
+    let area = app.buffer().area;
+    (area.left()..area.right())
+        .find(|&x| app.buffer()[(x, row)].modifier.contains(Modifier::REVERSED))
+}
+
+#[tokio::test]
+async fn fields_scroll_to_the_cursor() {
+    // the username field is 11 cells wide on such a narrow terminal
+    let mut app = TestApp::new((20, 24)).await;
+    app.type_str("iabcdefghij日本e\u{301}").await;
+
+    let row = app
+        .lines()
+        .iter()
+        .position(|line| line.contains("fghij"))
+        .expect("the end of the username is displayed") as u16;
+    let line = &app.lines()[row as usize];
+    // the cell after a wide character is covered by it, the
+    // test backend keeps what was drawn there before
+    assert!(line.contains("│ fghij日"));
+    assert!(line.contains("本e\u{301} │"));
+    assert!(!line.contains("abcde"));
+    assert!(app.contains(" 13/16 "));
+
+    // the cursor is right after the last character, past the wide
+    // characters which are drawn on two cells each
+    let start = (0..app.buffer().area.width)
+        .find(|&x| app.buffer()[(x, row)].symbol() == "f")
+        .unwrap();
+    assert_eq!(cursor_column(&app, row), Some(start + 10));
+
+    // the start of the text is displayed again with the cursor on it
+    app.press(b"\x1b[H").await;
+    let line = &app.lines()[row as usize];
+    assert!(line.contains("│ abcdefghij │"));
+    assert_eq!(cursor_column(&app, row), Some(start));
+}
+
+#[tokio::test]
+async fn chat_composer_wraps_the_long_messages() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let general = create_channel(&app.database(), "general").await;
+    open_chat(&mut app, "general").await;
+
+    let body = format!("{} and the end", "日本語 ".repeat(20));
+    app.type_str("i").await;
+    app.type_str(&body).await;
+    assert!(app.contains("and the end"));
+    assert!(!app.contains("message #general"));
+    app.press(b"\r").await;
+    app.settle().await;
+
+    let history = Message::history(app.database(), general.id(), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(history[0].body(), body);
+}
+
+#[tokio::test]
+async fn chat_composer_writes_multiline_messages() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let general = create_channel(&app.database(), "general").await;
+    open_chat(&mut app, "general").await;
+
+    // alt-enter, and shift-enter as sent by the terminals reporting it
+    app.type_str("ifirst").await;
+    app.press(b"\x1b\r").await;
+    app.type_str("second").await;
+    app.press(b"\x1b[13;2u").await;
+    app.type_str("third").await;
+    // the composer grows with its lines
+    assert!(app.contains("│ first"));
+    assert!(app.contains("│ second"));
+    assert!(app.contains("│ third"));
+
+    app.press(b"\r").await;
+    app.settle().await;
+
+    let history = Message::history(app.database(), general.id(), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(history[0].body(), "first\nsecond\nthird");
+}
+
+#[tokio::test]
+async fn chat_composer_edits_with_the_emacs_keys() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let general = create_channel(&app.database(), "general").await;
+    open_chat(&mut app, "general").await;
+
+    app.type_str("ihello big world").await;
+    // ctrl-w kills the word, ctrl-u kills the line before it,
+    // the consecutive kills are yanked back as one text
+    app.press(b"\x17").await;
+    assert!(app.contains("│ hello big "));
+    assert!(!app.contains("world"));
+    app.press(b"\x15").await;
+    assert!(app.contains("message #general..."));
+    app.press(b"\x19").await;
+    assert!(app.contains("│ hello big world"));
+
+    // alt-b moves by words, ctrl-k kills the end of the line
+    app.press(b"\x1bb").await;
+    app.press(b"\x1bb").await;
+    app.press(b"\x0b").await;
+    assert!(!app.contains("big"));
+
+    // alt-y replaces the yanked text with the previous kill
+    app.press(b"\x19").await;
+    assert!(app.contains("│ hello big world"));
+    app.press(b"\x1by").await;
+    assert!(app.contains("│ hello hello big world"));
+
+    // ctrl-z undoes the yank, then the typed word, ctrl-r redoes it
+    app.press(b"\x1a").await;
+    app.type_str("small").await;
+    app.press(b"\x1a").await;
+    assert!(!app.contains("small"));
+    app.press(b"\x12").await;
+    assert!(app.contains("│ hello small"));
+
+    app.press(b"\r").await;
+    app.settle().await;
+
+    let history = Message::history(app.database(), general.id(), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(history[0].body(), "hello small");
+}
+
+#[tokio::test]
+async fn chat_composer_recalls_the_sent_messages() {
+    let mut app = TestApp::new(SIZE).await;
+    login(&mut app, "bob").await;
+    let general = create_channel(&app.database(), "general").await;
+    open_chat(&mut app, "general").await;
+
+    app.type_str("ifirst").await;
+    app.press(b"\r").await;
+    app.type_str("second").await;
+    app.press(b"\r").await;
+    app.type_str("draft").await;
+
+    app.press(b"\x1b[A").await;
+    assert!(app.contains("│ second"));
+    app.press(b"\x1b[A").await;
+    assert!(app.contains("│ first"));
+    app.press(b"\x1b[B").await;
+    assert!(app.contains("│ second"));
+    // past the newest message the draft is back
+    app.press(b"\x1b[B").await;
+    assert!(app.contains("│ draft"));
+
+    app.press(b"\x1b[A").await;
+    app.type_str(" again").await;
+    app.press(b"\r").await;
+    app.settle().await;
+
+    let history = Message::history(app.database(), general.id(), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[2].body(), "second again");
+}
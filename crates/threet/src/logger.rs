@@ -28,11 +28,7 @@ impl Logger {
     where
         P: AsRef<Path>,
     {
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .append(true)
-            .open(path)?;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
         Ok(Self::new(file))
     }
 }
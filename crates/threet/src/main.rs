@@ -1,18 +1,25 @@
 use std::net::SocketAddr;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use clap::Parser;
+use clap::Subcommand;
+use threet_server::SessionOptions;
 use tokio::runtime::Builder;
 
 mod logger;
 
 #[derive(Parser, Debug)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// the ip address and port the server should run on
     /// in a format of `<address>:<port>`
-    #[arg(short, long)]
-    address: SocketAddr,
+    #[arg(short, long, required = true)]
+    address: Option<SocketAddr>,
 
     /// define the amount of worker threads that will be used
     /// to handle different connection and tasks
@@ -26,6 +33,65 @@ struct Args {
 
     #[arg(long, default_value_t = String::from("./threet.sqlite"))]
     database: String,
+
+    /// how many seconds connected sessions are given to
+    /// finish before the server shuts down
+    #[arg(long, default_value_t = 10)]
+    shutdown_grace: u64,
+
+    /// the max frames per second sent to each connected
+    /// session, lower it to save bandwidth
+    #[arg(long, default_value_t = 30)]
+    max_fps: u32,
+
+    /// the directory session recordings are stored in, sessions
+    /// are recorded if they set `THREET_RECORD` in their environment
+    #[arg(long)]
+    record_dir: Option<PathBuf>,
+
+    /// record all the sessions, requires `--record-dir`
+    #[arg(long, requires = "record_dir")]
+    record_all: bool,
+
+    /// record what the users type too, requires `--record-dir`
+    #[arg(long, requires = "record_dir")]
+    record_input: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// plays a session recording back to the terminal
+    Replay {
+        /// the asciicast recording to play
+        path: PathBuf,
+
+        /// the playback speed, `2` plays the recording twice as fast
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+
+        /// the max seconds to wait between two events, long
+        /// pauses in the recording are shortened to it
+        #[arg(long)]
+        max_idle: Option<f64>,
+    },
+}
+
+/// plays the given recording on a single threaded runtime, the
+/// server logger is not set so nothing else writes to the terminal
+fn replay(path: PathBuf, speed: f64, max_idle: Option<f64>) -> anyhow::Result<()> {
+    if speed <= 0.0 {
+        anyhow::bail!("the playback speed must be greater than 0");
+    }
+    if max_idle.is_some_and(|max_idle| max_idle < 0.0) {
+        anyhow::bail!("the max idle time can't be negative");
+    }
+
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    runtime.block_on(threet_server::replay(
+        path,
+        speed,
+        max_idle.map(Duration::from_secs_f64),
+    ))
 }
 
 fn setup_logger<P>(path: P) -> anyhow::Result<()>
@@ -39,6 +105,17 @@ where
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    if let Some(Command::Replay {
+        path,
+        speed,
+        max_idle,
+    }) = args.command
+    {
+        return replay(path, speed, max_idle);
+    }
+
+    // clap makes sure the address is there when no command is given
+    let address = args.address.expect("the server address is required");
     setup_logger(args.log.unwrap_or("threet.log".into()))?;
 
     let runtime = Builder::new_multi_thread()
@@ -52,8 +129,15 @@ fn main() -> anyhow::Result<()> {
         .build()
         .unwrap();
     runtime.block_on(threet_server::main(
-        args.address,
+        address,
         args.database,
         args.threads,
+        Duration::from_secs(args.shutdown_grace),
+        SessionOptions {
+            max_fps: args.max_fps,
+            record_dir: args.record_dir,
+            record_all: args.record_all,
+            record_input: args.record_input,
+        },
     ))
 }